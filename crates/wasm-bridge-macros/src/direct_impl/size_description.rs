@@ -3,10 +3,26 @@ use proc_macro2::*;
 use quote::{format_ident, quote};
 use syn::{DataEnum, DataStruct};
 
-pub fn size_description_struct(name: Ident, data: DataStruct) -> TokenStream {
+pub fn size_description_struct(name: Ident, data: DataStruct, is_record: bool) -> TokenStream {
     let fields = data.fields;
     let field_count = fields.len();
 
+    // Structs without the record style are generated by the `flags!` macro
+    let typecheck = if is_record {
+        let mut checks = TokenStream::new();
+        for (i, field) in fields.iter().enumerate() {
+            let field_type = &field.ty;
+            checks.extend(quote!(<#field_type>::typecheck(&types[#i])?;));
+        }
+        quote!(
+            let types = wasm_bridge::direct::typecheck_record(ty, #field_count)?;
+            #checks
+            Ok(())
+        )
+    } else {
+        quote!(wasm_bridge::direct::typecheck_flags(ty))
+    };
+
     let mut alignment = quote!(1usize);
     for field in fields.iter() {
         let field_type = &field.ty;
@@ -74,6 +90,10 @@ pub fn size_description_struct(name: Ident, data: DataStruct) -> TokenStream {
                 #layout_impl
                 [#layout_return Self::BYTE_SIZE]
             }
+
+            fn typecheck(ty: &wasm_bridge::component::Type) -> wasm_bridge::Result<()> {
+                #typecheck
+            }
         }
       }
     )
@@ -86,6 +106,8 @@ pub fn size_description_enum(name: Ident, data: DataEnum) -> TokenStream {
         ));
     }
 
+    let case_count = data.variants.len();
+
     quote!(
         impl wasm_bridge::direct::SizeDescription for #name {
             const NUM_ARGS: usize = 1;
@@ -97,6 +119,10 @@ pub fn size_description_enum(name: Ident, data: DataEnum) -> TokenStream {
             fn layout() -> Self::StructLayout {
                 [0, 1, 1]
             }
+
+            fn typecheck(ty: &wasm_bridge::component::Type) -> wasm_bridge::Result<()> {
+                wasm_bridge::direct::typecheck_enum(ty, #case_count)
+            }
         }
     )
 }
@@ -133,6 +159,19 @@ pub fn size_description_variant(name: Ident, data: DataEnum) -> TokenStream {
         }
     }
 
+    let case_count = variants.len();
+    let mut case_checks = TokenStream::new();
+    for (i, variant) in variants.iter().enumerate() {
+        let check = match variant.fields.iter().next() {
+            Some(field) => {
+                let field_type = &field.ty;
+                quote!(Some(<#field_type>::typecheck as fn(&wasm_bridge::component::Type) -> wasm_bridge::Result<()>))
+            }
+            None => quote!(None),
+        };
+        case_checks.extend(quote!(wasm_bridge::direct::typecheck_case(&cases[#i], #check)?;));
+    }
+
     quote!(
         impl wasm_bridge::direct::SizeDescription for #name {
             const ALIGNMENT: usize = #alignment;
@@ -145,6 +184,12 @@ pub fn size_description_variant(name: Ident, data: DataEnum) -> TokenStream {
             fn layout() -> Self::StructLayout {
                 [0, Self::BYTE_SIZE, Self::BYTE_SIZE]
             }
+
+            fn typecheck(ty: &wasm_bridge::component::Type) -> wasm_bridge::Result<()> {
+                let cases = wasm_bridge::direct::typecheck_variant(ty, #case_count)?;
+                #case_checks
+                Ok(())
+            }
         }
     )
 }
@@ -213,6 +258,12 @@ pub fn size_description_tuple(tokens: proc_macro::TokenStream) -> proc_macro::To
         layout_return.extend(ret);
     }
 
+    let mut checks = TokenStream::new();
+    for i in 0..count {
+        let name = format_ident!("T{i}");
+        checks.extend(quote!(<#name>::typecheck(&types[#i])?;));
+    }
+
     let result = quote!(
         impl<#generics> SizeDescription for (#tuple) {
             const ALIGNMENT: usize = #alignment;
@@ -227,6 +278,12 @@ pub fn size_description_tuple(tokens: proc_macro::TokenStream) -> proc_macro::To
                 #layout_impl
                 [#layout_return Self::BYTE_SIZE]
             }
+
+            fn typecheck(ty: &Type) -> Result<()> {
+                let types = typecheck_tuple(ty, #count)?;
+                #checks
+                Ok(())
+            }
         }
     );
 
@@ -1,4 +1,4 @@
-use std::{ops::Deref, str::FromStr};
+use std::str::FromStr;
 
 use original::{Style, VariantStyle};
 use quote::ToTokens;
@@ -140,10 +140,10 @@ self\s*\.\s*funcs\s*\.\s*method_
     proc_macro::TokenStream::from_str(&as_string).unwrap()
 }
 
-fn add_safe_instantiation(as_string: &str) -> impl Deref<Target = str> + '_ {
+fn add_safe_instantiation(as_string: &str) -> String {
     let regex = Regex::new("pub\\s+fn\\s+instantiate\\s*<([^{]*)\\{").unwrap();
 
-    regex.replace_all(as_string, r#"
+    let as_string = regex.replace_all(as_string, r#"
     pub async fn instantiate_safe<T>(
         mut store: impl wasm_bridge::AsContextMut<Data = T>,
         component: &wasm_bridge::component::Component,
@@ -159,7 +159,26 @@ fn add_safe_instantiation(as_string: &str) -> impl Deref<Target = str> + '_ {
     )]
     pub fn instantiate< $1 {
         #[allow(deprecated)]
-        "#)
+        "#);
+
+    let regex = Regex::new("pub\\s+fn\\s+instantiate_pre\\s*<([^{]*)\\{").unwrap();
+
+    regex.replace_all(&as_string, r#"
+    pub async fn instantiate_pre_safe<T>(
+        mut store: impl wasm_bridge::AsContextMut<Data = T>,
+        instance_pre: &wasm_bridge::component::InstancePre<T>,
+    ) -> wasm_bridge::Result<(Self, wasm_bridge::component::Instance)> {
+        let instance = instance_pre.instantiate_safe(&mut store).await?;
+        Ok((Self::new(store, &instance)?, instance))
+    }
+
+    #[deprecated(
+        since = "0.5.0",
+        note = "Instantiating a component synchronously can panic on the web, please use `instantiate_pre_safe` instead."
+    )]
+    pub fn instantiate_pre< $1 {
+        #[allow(deprecated)]
+        "#).into_owned()
 }
 
 #[proc_macro_derive(SizeDescription, attributes(component))]
@@ -170,7 +189,10 @@ pub fn derive_size_description(input: proc_macro::TokenStream) -> proc_macro::To
     let struct_style = style_from_attributes(&derive_input.attrs);
 
     let tokens = match derive_input.data {
-        syn::Data::Struct(data) => direct_impl::size_description_struct(name, data),
+        syn::Data::Struct(data) => {
+            let is_record = matches!(struct_style, Some(Style::Record));
+            direct_impl::size_description_struct(name, data, is_record)
+        }
         syn::Data::Enum(data) => match struct_style.expect("cannot find attribute style") {
             Style::Record => unreachable!("enum is not a record"),
             Style::Variant(VariantStyle::Enum) => direct_impl::size_description_enum(name, data),
@@ -5,12 +5,14 @@ use crate::*;
 #[derive(Debug)]
 pub struct Caller<T> {
     store: Store<T>,
+    exports: LazyExports,
 }
 
 impl<T> Caller<T> {
-    pub(crate) fn new(handle: DataHandle<T>) -> Self {
+    pub(crate) fn new(handle: DataHandle<T>, exports: LazyExports) -> Self {
         Self {
             store: Store::from_handle(handle),
+            exports,
         }
     }
 
@@ -18,15 +20,38 @@ impl<T> Caller<T> {
         self.store.data()
     }
 
+    /// Returns the engine of the store, for example to call [`Engine::increment_epoch`].
+    pub fn engine(&self) -> &Engine {
+        self.store.engine()
+    }
+
     pub fn data_mut(&mut self) -> impl DerefMut<Target = T> + '_ {
         self.store.data_mut()
     }
+
+    /// Returns the remaining fuel in the store, see [`Store::get_fuel`].
+    pub fn get_fuel(&self) -> Result<u64> {
+        self.store.get_fuel()
+    }
+
+    /// Sets the fuel in the store, see [`Store::set_fuel`].
+    pub fn set_fuel(&mut self, fuel: u64) -> Result<()> {
+        self.store.set_fuel(fuel)
+    }
+
+    /// Looks up an export of the calling instance, for example its `"memory"`.
+    ///
+    /// Returns `None` while the instance is still being instantiated.
+    pub fn get_export(&mut self, name: &str) -> Option<Extern> {
+        self.exports.get_export(name)
+    }
 }
 
 impl<T> Clone for Caller<T> {
     fn clone(&self) -> Self {
         Self {
             store: self.store.clone(),
+            exports: self.exports.clone(),
         }
     }
 }
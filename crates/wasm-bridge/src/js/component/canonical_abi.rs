@@ -0,0 +1,751 @@
+//! Lowering and lifting of dynamic [`Val`]s, driven by the decoded component types.
+//!
+//! The typed path uses the `Lift` and `Lower` traits instead, where the layout is known statically.
+
+use anyhow::{bail, Context};
+use wasm_bindgen::JsValue;
+
+use super::{types::*, ResourceAny, Val};
+use crate::{
+    direct::{ReadableMemory, WriteableMemory},
+    FromJsValue, Result, ToJsValue,
+};
+
+/// Parameters that flatten to more values are passed in memory instead
+pub(crate) const MAX_FLAT_PARAMS: usize = 16;
+
+/// Results that flatten to more values are returned in memory instead
+pub(crate) const MAX_FLAT_RESULTS: usize = 1;
+
+/// Core wasm type of a flattened value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FlatType {
+    I32,
+    I64,
+    F32,
+    F64,
+}
+
+impl FlatType {
+    /// Type of a variant slot shared by cases of these two types.
+    fn join(self, other: Self) -> Self {
+        match (self, other) {
+            (a, b) if a == b => a,
+            (Self::I32, Self::F32) | (Self::F32, Self::I32) => Self::I32,
+            _ => Self::I64,
+        }
+    }
+}
+
+/// A flattened value, passed to or returned from a core function.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum FlatValue {
+    I32(i32),
+    I64(i64),
+    F32(f32),
+    F64(f64),
+}
+
+impl FlatValue {
+    fn zero(ty: FlatType) -> Self {
+        match ty {
+            FlatType::I32 => Self::I32(0),
+            FlatType::I64 => Self::I64(0),
+            FlatType::F32 => Self::F32(0.0),
+            FlatType::F64 => Self::F64(0.0),
+        }
+    }
+
+    /// Converts a value of a variant case to the type of the shared slot.
+    fn widen(self, ty: FlatType) -> Self {
+        match (self, ty) {
+            (Self::I32(value), FlatType::I64) => Self::I64(value as u32 as i64),
+            (Self::F32(value), FlatType::I32) => Self::I32(value.to_bits() as i32),
+            (Self::F32(value), FlatType::I64) => Self::I64(value.to_bits() as i64),
+            (Self::F64(value), FlatType::I64) => Self::I64(value.to_bits() as i64),
+            (value, _) => value,
+        }
+    }
+
+    /// Converts a value of a shared variant slot back to the type of the case.
+    fn narrow(self, ty: FlatType) -> Self {
+        match (self, ty) {
+            (Self::I64(value), FlatType::I32) => Self::I32(value as i32),
+            (Self::I32(value), FlatType::F32) => Self::F32(f32::from_bits(value as u32)),
+            (Self::I64(value), FlatType::F32) => Self::F32(f32::from_bits(value as u32)),
+            (Self::I64(value), FlatType::F64) => Self::F64(f64::from_bits(value as u64)),
+            (value, _) => value,
+        }
+    }
+
+    fn i32(self) -> Result<i32> {
+        match self {
+            Self::I32(value) => Ok(value),
+            other => bail!("Expected an i32 core value, got {other:?}"),
+        }
+    }
+
+    fn i64(self) -> Result<i64> {
+        match self {
+            Self::I64(value) => Ok(value),
+            other => bail!("Expected an i64 core value, got {other:?}"),
+        }
+    }
+
+    fn f32(self) -> Result<f32> {
+        match self {
+            Self::F32(value) => Ok(value),
+            other => bail!("Expected an f32 core value, got {other:?}"),
+        }
+    }
+
+    fn f64(self) -> Result<f64> {
+        match self {
+            Self::F64(value) => Ok(value),
+            other => bail!("Expected an f64 core value, got {other:?}"),
+        }
+    }
+
+    pub(crate) fn to_js_value(self) -> JsValue {
+        match self {
+            Self::I32(value) => value.to_js_value(),
+            Self::I64(value) => value.to_js_value(),
+            Self::F32(value) => value.to_js_value(),
+            Self::F64(value) => value.to_js_value(),
+        }
+    }
+
+    pub(crate) fn from_js_value(value: &JsValue, ty: FlatType) -> Result<Self> {
+        Ok(match ty {
+            FlatType::I32 => Self::I32(i32::from_js_value(value)?),
+            FlatType::I64 => Self::I64(i64::from_js_value(value)?),
+            FlatType::F32 => Self::F32(f32::from_js_value(value)?),
+            FlatType::F64 => Self::F64(f64::from_js_value(value)?),
+        })
+    }
+}
+
+fn align_to(offset: usize, align: usize) -> usize {
+    offset.div_ceil(align) * align
+}
+
+fn discriminant_size(cases: usize) -> usize {
+    match cases {
+        0..=0x100 => 1,
+        0x101..=0x10000 => 2,
+        _ => 4,
+    }
+}
+
+/// Number of 32-bit words that store the flags.
+fn flags_words(flags: &Flags) -> usize {
+    flags.0.len().div_ceil(32)
+}
+
+/// Size and alignment of a value of this type in memory.
+pub(crate) fn size_align(ty: &Type) -> (usize, usize) {
+    match ty {
+        Type::Bool | Type::S8 | Type::U8 => (1, 1),
+        Type::S16 | Type::U16 => (2, 2),
+        Type::S32 | Type::U32 | Type::Float32 | Type::Char => (4, 4),
+        Type::Own(_) | Type::Borrow(_) => (4, 4),
+        Type::S64 | Type::U64 | Type::Float64 => (8, 8),
+        Type::String | Type::List(_) => (8, 4),
+        Type::Record(record) => fields_size_align(record.0.iter().map(|(_, ty)| ty)),
+        Type::Tuple(tuple) => fields_size_align(tuple.0.iter()),
+        Type::Flags(flags) => match flags.0.len() {
+            0 => (0, 1),
+            1..=8 => (1, 1),
+            9..=16 => (2, 2),
+            _ => (4 * flags_words(flags), 4),
+        },
+        _ => {
+            let (size, align, _) = variant_layout(&variant_cases(ty));
+            (size, align)
+        }
+    }
+}
+
+/// Size and alignment of a record with fields of these types.
+pub(crate) fn fields_size_align<'a>(fields: impl Iterator<Item = &'a Type>) -> (usize, usize) {
+    let (mut size, mut align) = (0, 1);
+    for ty in fields {
+        let (field_size, field_align) = size_align(ty);
+        size = align_to(size, field_align) + field_size;
+        align = align.max(field_align);
+    }
+
+    (align_to(size, align), align)
+}
+
+/// Size, alignment and payload offset of a variant with these cases.
+fn variant_layout(cases: &[(&str, Option<&Type>)]) -> (usize, usize, usize) {
+    let discriminant = discriminant_size(cases.len());
+    let (mut payload_size, mut align) = (0, discriminant);
+    for ty in cases.iter().filter_map(|(_, ty)| *ty) {
+        let (case_size, case_align) = size_align(ty);
+        payload_size = payload_size.max(case_size);
+        align = align.max(case_align);
+    }
+
+    let payload_offset = align_to(discriminant, align);
+    (
+        align_to(payload_offset + payload_size, align),
+        align,
+        payload_offset,
+    )
+}
+
+/// Names and payload types of the cases of a variant, enum, option or result.
+fn variant_cases(ty: &Type) -> Vec<(&str, Option<&Type>)> {
+    match ty {
+        Type::Variant(variant) => variant
+            .0
+            .iter()
+            .map(|(name, ty)| (name.as_str(), ty.as_ref()))
+            .collect(),
+        Type::Enum(names) => names.0.iter().map(|name| (name.as_str(), None)).collect(),
+        Type::Option(option) => vec![("none", None), ("some", Some(&*option.0))],
+        Type::Result(result) => vec![("ok", result.0 .0.as_ref()), ("err", result.0 .1.as_ref())],
+        _ => unreachable!("{} is not a variant", ty.desc()),
+    }
+}
+
+/// Appends the core types that a value of this type flattens to.
+pub(crate) fn flatten(ty: &Type, out: &mut Vec<FlatType>) {
+    match ty {
+        Type::Bool | Type::S8 | Type::U8 | Type::S16 | Type::U16 | Type::S32 | Type::U32 => {
+            out.push(FlatType::I32)
+        }
+        Type::Char | Type::Own(_) | Type::Borrow(_) => out.push(FlatType::I32),
+        Type::S64 | Type::U64 => out.push(FlatType::I64),
+        Type::Float32 => out.push(FlatType::F32),
+        Type::Float64 => out.push(FlatType::F64),
+        Type::String | Type::List(_) => out.extend([FlatType::I32, FlatType::I32]),
+        Type::Record(record) => record.0.iter().for_each(|(_, ty)| flatten(ty, out)),
+        Type::Tuple(tuple) => tuple.0.iter().for_each(|ty| flatten(ty, out)),
+        Type::Flags(flags) => out.extend((0..flags_words(flags)).map(|_| FlatType::I32)),
+        _ => flatten_variant(&variant_cases(ty), out),
+    }
+}
+
+fn flatten_variant(cases: &[(&str, Option<&Type>)], out: &mut Vec<FlatType>) {
+    out.push(FlatType::I32);
+
+    // Cases share the slots after the discriminant
+    let start = out.len();
+    for ty in cases.iter().filter_map(|(_, ty)| *ty) {
+        let mut case_flat = Vec::new();
+        flatten(ty, &mut case_flat);
+
+        for (index, flat) in case_flat.into_iter().enumerate() {
+            match out.get_mut(start + index) {
+                Some(slot) => *slot = slot.join(flat),
+                None => out.push(flat),
+            }
+        }
+    }
+}
+
+fn type_mismatch(ty: &Type, val: &Val) -> anyhow::Error {
+    anyhow::anyhow!(
+        "type mismatch: expected {}, found {}",
+        ty.desc(),
+        val.desc()
+    )
+}
+
+/// Returns the case index and the payload of a variant-like value of this type.
+fn variant_case<'a>(
+    ty: &Type,
+    cases: &[(&str, Option<&Type>)],
+    val: &'a Val,
+) -> Result<(usize, Option<&'a Val>)> {
+    let (name, payload) = match (ty, val) {
+        (Type::Variant(_), Val::Variant(name, payload)) => (name.as_str(), payload.as_deref()),
+        (Type::Enum(_), Val::Enum(name)) => (name.as_str(), None),
+        (Type::Option(_), Val::Option(None)) => ("none", None),
+        (Type::Option(_), Val::Option(Some(payload))) => ("some", Some(&**payload)),
+        (Type::Result(_), Val::Result(Ok(payload))) => ("ok", payload.as_deref()),
+        (Type::Result(_), Val::Result(Err(payload))) => ("err", payload.as_deref()),
+        _ => return Err(type_mismatch(ty, val)),
+    };
+
+    let index = cases
+        .iter()
+        .position(|(case, _)| *case == name)
+        .with_context(|| format!("unknown {} case `{name}`", ty.desc()))?;
+
+    match (cases[index].1, payload) {
+        (Some(_), None) => bail!("expected a payload for case `{name}`"),
+        (None, Some(_)) => bail!("did not expect a payload for case `{name}`"),
+        _ => Ok((index, payload)),
+    }
+}
+
+/// Creates a variant-like value of this type.
+fn variant_val(
+    ty: &Type,
+    cases: &[(&str, Option<&Type>)],
+    index: usize,
+    payload: Option<Val>,
+) -> Val {
+    let payload = payload.map(Box::new);
+    match ty {
+        Type::Variant(_) => Val::Variant(cases[index].0.to_owned(), payload),
+        Type::Enum(_) => Val::Enum(cases[index].0.to_owned()),
+        Type::Option(_) => Val::Option(payload),
+        _ if index == 0 => Val::Result(Ok(payload)),
+        _ => Val::Result(Err(payload)),
+    }
+}
+
+/// Returns the bits of the flags that are set in the value.
+fn flags_bits(flags: &Flags, names: &[String]) -> Result<Vec<u32>> {
+    let mut bits = vec![0u32; flags_words(flags)];
+    for name in names {
+        let index = flags
+            .0
+            .iter()
+            .position(|flag| flag == name)
+            .with_context(|| format!("unknown flag `{name}`"))?;
+        bits[index / 32] |= 1 << (index % 32);
+    }
+
+    Ok(bits)
+}
+
+fn flags_names(flags: &Flags, bits: &[u32]) -> Vec<String> {
+    flags
+        .0
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| bits[index / 32] & (1 << (index % 32)) != 0)
+        .map(|(_, name)| name.clone())
+        .collect()
+}
+
+/// Writes the bytes to newly allocated guest memory, returns their address.
+pub(crate) fn write_bytes<M: WriteableMemory>(
+    bytes: &[u8],
+    align: usize,
+    memory: &M,
+) -> Result<u32> {
+    let mut buffer = memory.allocate(align, bytes.len())?;
+    buffer.write_bytes(bytes);
+    Ok(memory.flush(buffer) as u32)
+}
+
+/// Writes the list elements to guest memory, returns their address.
+fn store_list<M: WriteableMemory>(values: &[Val], ty: &Type, memory: &M) -> Result<u32> {
+    let (size, align) = size_align(ty);
+    let mut bytes = vec![0; size * values.len()];
+    for (val, elem) in values.iter().zip(bytes.chunks_exact_mut(size.max(1))) {
+        store(val, ty, memory, &mut elem[..size])?;
+    }
+
+    write_bytes(&bytes, align, memory)
+}
+
+/// Lowers the value to flat core values.
+pub(crate) fn lower<M: WriteableMemory>(
+    val: &Val,
+    ty: &Type,
+    memory: &M,
+    out: &mut Vec<FlatValue>,
+) -> Result<()> {
+    let flat = match (ty, val) {
+        (Type::Bool, Val::Bool(value)) => FlatValue::I32(*value as i32),
+        (Type::S8, Val::S8(value)) => FlatValue::I32(*value as i32),
+        (Type::U8, Val::U8(value)) => FlatValue::I32(*value as i32),
+        (Type::S16, Val::S16(value)) => FlatValue::I32(*value as i32),
+        (Type::U16, Val::U16(value)) => FlatValue::I32(*value as i32),
+        (Type::S32, Val::S32(value)) => FlatValue::I32(*value),
+        (Type::U32, Val::U32(value)) => FlatValue::I32(*value as i32),
+        (Type::S64, Val::S64(value)) => FlatValue::I64(*value),
+        (Type::U64, Val::U64(value)) => FlatValue::I64(*value as i64),
+        (Type::Float32, Val::Float32(value)) => FlatValue::F32(*value),
+        (Type::Float64, Val::Float64(value)) => FlatValue::F64(*value),
+        (Type::Char, Val::Char(value)) => FlatValue::I32(*value as i32),
+        (Type::Own(_) | Type::Borrow(_), Val::Resource(resource)) => {
+            FlatValue::I32(resource.id as i32)
+        }
+        (Type::String, Val::String(value)) => {
+            let addr = write_bytes(value.as_bytes(), 1, memory)?;
+            out.push(FlatValue::I32(addr as i32));
+            FlatValue::I32(value.len() as i32)
+        }
+        (Type::List(list), Val::List(values)) => {
+            let addr = store_list(values, &list.0, memory)?;
+            out.push(FlatValue::I32(addr as i32));
+            FlatValue::I32(values.len() as i32)
+        }
+        (Type::Record(record), Val::Record(values)) => {
+            if values.len() != record.0.len() {
+                bail!("expected {} fields, got {}", record.0.len(), values.len());
+            }
+            for ((name, ty), (val_name, val)) in record.0.iter().zip(values) {
+                if name != val_name {
+                    bail!("expected field `{name}`, got `{val_name}`");
+                }
+                lower(val, ty, memory, out)?;
+            }
+            return Ok(());
+        }
+        (Type::Tuple(tuple), Val::Tuple(values)) => {
+            if values.len() != tuple.0.len() {
+                bail!("expected {} types, got {}", tuple.0.len(), values.len());
+            }
+            for (ty, val) in tuple.0.iter().zip(values) {
+                lower(val, ty, memory, out)?;
+            }
+            return Ok(());
+        }
+        (Type::Flags(flags), Val::Flags(names)) => {
+            let bits = flags_bits(flags, names)?;
+            out.extend(bits.into_iter().map(|word| FlatValue::I32(word as i32)));
+            return Ok(());
+        }
+        (Type::Variant(_) | Type::Enum(_) | Type::Option(_) | Type::Result(_), _) => {
+            let cases = variant_cases(ty);
+            let (index, payload) = variant_case(ty, &cases, val)?;
+
+            let mut slots = Vec::new();
+            flatten_variant(&cases, &mut slots);
+
+            let mut payload_flat = Vec::new();
+            if let (Some(ty), Some(payload)) = (cases[index].1, payload) {
+                lower(payload, ty, memory, &mut payload_flat)?;
+            }
+
+            out.push(FlatValue::I32(index as i32));
+            for (slot, ty) in slots.into_iter().skip(1).enumerate() {
+                let value = payload_flat.get(slot).map(|value| value.widen(ty));
+                out.push(value.unwrap_or(FlatValue::zero(ty)));
+            }
+            return Ok(());
+        }
+        _ => return Err(type_mismatch(ty, val)),
+    };
+
+    out.push(flat);
+    Ok(())
+}
+
+/// Stores the values as fields of a record, `bytes` must have the size of the record.
+pub(crate) fn store_fields<'a, M: WriteableMemory>(
+    values: impl Iterator<Item = &'a Val>,
+    types: &[Type],
+    memory: &M,
+    bytes: &mut [u8],
+) -> Result<()> {
+    let mut offset = 0;
+    for (val, ty) in values.zip(types) {
+        let (size, align) = size_align(ty);
+        offset = align_to(offset, align);
+        store(val, ty, memory, &mut bytes[offset..offset + size])?;
+        offset += size;
+    }
+
+    Ok(())
+}
+
+/// Stores the value in memory, `bytes` must have the size of the type.
+pub(crate) fn store<M: WriteableMemory>(
+    val: &Val,
+    ty: &Type,
+    memory: &M,
+    bytes: &mut [u8],
+) -> Result<()> {
+    match (ty, val) {
+        (Type::Bool, Val::Bool(value)) => bytes[0] = *value as u8,
+        (Type::S8, Val::S8(value)) => bytes.copy_from_slice(&value.to_le_bytes()),
+        (Type::U8, Val::U8(value)) => bytes.copy_from_slice(&value.to_le_bytes()),
+        (Type::S16, Val::S16(value)) => bytes.copy_from_slice(&value.to_le_bytes()),
+        (Type::U16, Val::U16(value)) => bytes.copy_from_slice(&value.to_le_bytes()),
+        (Type::S32, Val::S32(value)) => bytes.copy_from_slice(&value.to_le_bytes()),
+        (Type::U32, Val::U32(value)) => bytes.copy_from_slice(&value.to_le_bytes()),
+        (Type::S64, Val::S64(value)) => bytes.copy_from_slice(&value.to_le_bytes()),
+        (Type::U64, Val::U64(value)) => bytes.copy_from_slice(&value.to_le_bytes()),
+        (Type::Float32, Val::Float32(value)) => bytes.copy_from_slice(&value.to_le_bytes()),
+        (Type::Float64, Val::Float64(value)) => bytes.copy_from_slice(&value.to_le_bytes()),
+        (Type::Char, Val::Char(value)) => bytes.copy_from_slice(&(*value as u32).to_le_bytes()),
+        (Type::Own(_) | Type::Borrow(_), Val::Resource(resource)) => {
+            bytes.copy_from_slice(&resource.id.to_le_bytes())
+        }
+        (Type::String, Val::String(value)) => {
+            let addr = write_bytes(value.as_bytes(), 1, memory)?;
+            bytes[..4].copy_from_slice(&addr.to_le_bytes());
+            bytes[4..].copy_from_slice(&(value.len() as u32).to_le_bytes());
+        }
+        (Type::List(list), Val::List(values)) => {
+            let addr = store_list(values, &list.0, memory)?;
+            bytes[..4].copy_from_slice(&addr.to_le_bytes());
+            bytes[4..].copy_from_slice(&(values.len() as u32).to_le_bytes());
+        }
+        (Type::Record(record), Val::Record(values)) => {
+            if values.len() != record.0.len() {
+                bail!("expected {} fields, got {}", record.0.len(), values.len());
+            }
+            for ((name, _), (val_name, _)) in record.0.iter().zip(values) {
+                if name != val_name {
+                    bail!("expected field `{name}`, got `{val_name}`");
+                }
+            }
+            let types = record
+                .0
+                .iter()
+                .map(|(_, ty)| ty.clone())
+                .collect::<Vec<_>>();
+            store_fields(values.iter().map(|(_, val)| val), &types, memory, bytes)?;
+        }
+        (Type::Tuple(tuple), Val::Tuple(values)) => {
+            if values.len() != tuple.0.len() {
+                bail!("expected {} types, got {}", tuple.0.len(), values.len());
+            }
+            store_fields(values.iter(), &tuple.0, memory, bytes)?;
+        }
+        (Type::Flags(flags), Val::Flags(names)) => {
+            let bits = flags_bits(flags, names)?;
+            match flags.0.len() {
+                0 => {}
+                1..=8 => bytes[0] = bits[0] as u8,
+                9..=16 => bytes.copy_from_slice(&(bits[0] as u16).to_le_bytes()),
+                _ => {
+                    for (word, chunk) in bits.iter().zip(bytes.chunks_exact_mut(4)) {
+                        chunk.copy_from_slice(&word.to_le_bytes());
+                    }
+                }
+            }
+        }
+        (Type::Variant(_) | Type::Enum(_) | Type::Option(_) | Type::Result(_), _) => {
+            let cases = variant_cases(ty);
+            let (index, payload) = variant_case(ty, &cases, val)?;
+
+            let discriminant = discriminant_size(cases.len());
+            bytes[..discriminant].copy_from_slice(&(index as u32).to_le_bytes()[..discriminant]);
+
+            if let (Some(ty), Some(payload)) = (cases[index].1, payload) {
+                let (_, _, offset) = variant_layout(&cases);
+                let (size, _) = size_align(ty);
+                store(payload, ty, memory, &mut bytes[offset..offset + size])?;
+            }
+        }
+        _ => return Err(type_mismatch(ty, val)),
+    }
+
+    Ok(())
+}
+
+fn next_flat(flat: &mut dyn Iterator<Item = FlatValue>) -> Result<FlatValue> {
+    flat.next().context("Not enough core values to lift")
+}
+
+/// Reads a string or list from memory, returns its bytes and length.
+fn read_list<M: ReadableMemory>(
+    addr: i32,
+    len: i32,
+    elem_size: usize,
+    memory: &M,
+) -> Result<(Vec<u8>, usize)> {
+    let len = len as u32 as usize;
+    let byte_len = elem_size.checked_mul(len).context("List is too long")?;
+    Ok((memory.read_to_vec(addr as u32 as usize, byte_len)?, len))
+}
+
+fn load_list<M: ReadableMemory>(addr: i32, len: i32, ty: &Type, memory: &M) -> Result<Val> {
+    let (size, _) = size_align(ty);
+    let (bytes, len) = read_list(addr, len, size, memory)?;
+
+    let values = (0..len)
+        .map(|index| load(ty, &bytes[index * size..(index + 1) * size], memory))
+        .collect::<Result<_>>()?;
+    Ok(Val::List(values))
+}
+
+fn load_string<M: ReadableMemory>(addr: i32, len: i32, memory: &M) -> Result<Val> {
+    let (bytes, _) = read_list(addr, len, 1, memory)?;
+    Ok(Val::String(String::from_utf8(bytes)?))
+}
+
+fn load_char(code: u32) -> Result<Val> {
+    let value = char::from_u32(code).context("converted integer out of range for `char`")?;
+    Ok(Val::Char(value))
+}
+
+/// Lifts a value from flat core values.
+pub(crate) fn lift<M: ReadableMemory>(
+    ty: &Type,
+    flat: &mut dyn Iterator<Item = FlatValue>,
+    memory: &M,
+) -> Result<Val> {
+    Ok(match ty {
+        Type::Bool => Val::Bool(next_flat(flat)?.i32()? != 0),
+        Type::S8 => Val::S8(next_flat(flat)?.i32()? as i8),
+        Type::U8 => Val::U8(next_flat(flat)?.i32()? as u8),
+        Type::S16 => Val::S16(next_flat(flat)?.i32()? as i16),
+        Type::U16 => Val::U16(next_flat(flat)?.i32()? as u16),
+        Type::S32 => Val::S32(next_flat(flat)?.i32()?),
+        Type::U32 => Val::U32(next_flat(flat)?.i32()? as u32),
+        Type::S64 => Val::S64(next_flat(flat)?.i64()?),
+        Type::U64 => Val::U64(next_flat(flat)?.i64()? as u64),
+        Type::Float32 => Val::Float32(next_flat(flat)?.f32()?),
+        Type::Float64 => Val::Float64(next_flat(flat)?.f64()?),
+        Type::Char => load_char(next_flat(flat)?.i32()? as u32)?,
+        Type::Own(_) | Type::Borrow(_) => Val::Resource(ResourceAny {
+            id: next_flat(flat)?.i32()? as u32,
+        }),
+        Type::String => {
+            let addr = next_flat(flat)?.i32()?;
+            load_string(addr, next_flat(flat)?.i32()?, memory)?
+        }
+        Type::List(list) => {
+            let addr = next_flat(flat)?.i32()?;
+            load_list(addr, next_flat(flat)?.i32()?, &list.0, memory)?
+        }
+        Type::Record(record) => Val::Record(
+            record
+                .0
+                .iter()
+                .map(|(name, ty)| Ok((name.clone(), lift(ty, flat, memory)?)))
+                .collect::<Result<_>>()?,
+        ),
+        Type::Tuple(tuple) => Val::Tuple(
+            tuple
+                .0
+                .iter()
+                .map(|ty| lift(ty, flat, memory))
+                .collect::<Result<_>>()?,
+        ),
+        Type::Flags(flags) => {
+            let bits = (0..flags_words(flags))
+                .map(|_| Ok(next_flat(flat)?.i32()? as u32))
+                .collect::<Result<Vec<_>>>()?;
+            Val::Flags(flags_names(flags, &bits))
+        }
+        _ => {
+            let cases = variant_cases(ty);
+
+            let mut slots = Vec::new();
+            flatten_variant(&cases, &mut slots);
+
+            let index = next_flat(flat)?.i32()? as u32 as usize;
+            let values = (1..slots.len())
+                .map(|_| next_flat(flat))
+                .collect::<Result<Vec<_>>>()?;
+
+            let (_, case) = cases
+                .get(index)
+                .with_context(|| format!("invalid {} discriminant: {index}", ty.desc()))?;
+
+            let payload = match case {
+                Some(case) => {
+                    let mut case_flat = Vec::new();
+                    flatten(case, &mut case_flat);
+
+                    let mut values = values
+                        .into_iter()
+                        .zip(case_flat)
+                        .map(|(value, ty)| value.narrow(ty));
+                    Some(lift(case, &mut values, memory)?)
+                }
+                None => None,
+            };
+
+            variant_val(ty, &cases, index, payload)
+        }
+    })
+}
+
+/// Loads the fields of a record with these types, `bytes` must have the size of the record.
+pub(crate) fn load_fields<M: ReadableMemory>(
+    types: &[Type],
+    bytes: &[u8],
+    memory: &M,
+) -> Result<Vec<Val>> {
+    let mut offset = 0;
+    types
+        .iter()
+        .map(|ty| {
+            let (size, align) = size_align(ty);
+            offset = align_to(offset, align);
+            let val = load(ty, &bytes[offset..offset + size], memory);
+            offset += size;
+            val
+        })
+        .collect()
+}
+
+/// Loads a value from memory, `bytes` must have the size of the type.
+pub(crate) fn load<M: ReadableMemory>(ty: &Type, bytes: &[u8], memory: &M) -> Result<Val> {
+    let u32_at = |offset: usize| u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+
+    Ok(match ty {
+        Type::Bool => Val::Bool(bytes[0] != 0),
+        Type::S8 => Val::S8(bytes[0] as i8),
+        Type::U8 => Val::U8(bytes[0]),
+        Type::S16 => Val::S16(i16::from_le_bytes(bytes.try_into()?)),
+        Type::U16 => Val::U16(u16::from_le_bytes(bytes.try_into()?)),
+        Type::S32 => Val::S32(i32::from_le_bytes(bytes.try_into()?)),
+        Type::U32 => Val::U32(u32::from_le_bytes(bytes.try_into()?)),
+        Type::S64 => Val::S64(i64::from_le_bytes(bytes.try_into()?)),
+        Type::U64 => Val::U64(u64::from_le_bytes(bytes.try_into()?)),
+        Type::Float32 => Val::Float32(f32::from_le_bytes(bytes.try_into()?)),
+        Type::Float64 => Val::Float64(f64::from_le_bytes(bytes.try_into()?)),
+        Type::Char => load_char(u32_at(0))?,
+        Type::Own(_) | Type::Borrow(_) => Val::Resource(ResourceAny { id: u32_at(0) }),
+        Type::String => load_string(u32_at(0) as i32, u32_at(4) as i32, memory)?,
+        Type::List(list) => load_list(u32_at(0) as i32, u32_at(4) as i32, &list.0, memory)?,
+        Type::Record(record) => {
+            let types = record
+                .0
+                .iter()
+                .map(|(_, ty)| ty.clone())
+                .collect::<Vec<_>>();
+            let values = load_fields(&types, bytes, memory)?;
+            Val::Record(
+                record
+                    .0
+                    .iter()
+                    .map(|(name, _)| name.clone())
+                    .zip(values)
+                    .collect(),
+            )
+        }
+        Type::Tuple(tuple) => Val::Tuple(load_fields(&tuple.0, bytes, memory)?),
+        Type::Flags(flags) => {
+            let bits = match flags.0.len() {
+                0 => vec![],
+                1..=8 => vec![bytes[0] as u32],
+                9..=16 => vec![u16::from_le_bytes(bytes.try_into()?) as u32],
+                _ => (0..flags_words(flags))
+                    .map(|word| u32_at(word * 4))
+                    .collect(),
+            };
+            Val::Flags(flags_names(flags, &bits))
+        }
+        _ => {
+            let cases = variant_cases(ty);
+            let (_, _, offset) = variant_layout(&cases);
+
+            let mut discriminant = [0u8; 4];
+            let discriminant_len = discriminant_size(cases.len());
+            discriminant[..discriminant_len].copy_from_slice(&bytes[..discriminant_len]);
+            let index = u32::from_le_bytes(discriminant) as usize;
+
+            let (_, case) = cases
+                .get(index)
+                .with_context(|| format!("invalid {} discriminant: {index}", ty.desc()))?;
+
+            let payload = match case {
+                Some(case) => {
+                    let (size, _) = size_align(case);
+                    Some(load(case, &bytes[offset..offset + size], memory)?)
+                }
+                None => None,
+            };
+
+            variant_val(ty, &cases, index, payload)
+        }
+    })
+}
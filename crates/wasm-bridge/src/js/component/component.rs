@@ -1,19 +1,34 @@
+use std::rc::Rc;
+
 use anyhow::{bail, Context};
-use js_sys::{Function, Object, Reflect, Uint8Array, WebAssembly};
-use wasm_bindgen::JsValue;
+use js_sys::{Function, Object, Reflect, WebAssembly};
 use wasm_bindgen_futures::JsFuture;
+use web_sys::{ReadableStream, Response};
 
 use crate::{
+    compile_module, compile_module_async,
     direct::{LazyModuleMemory, ModuleMemory},
-    helpers::{map_js_error, static_str_to_js},
+    helpers::{map_js_error, read_stream, response_body, static_str_to_js},
     DropHandles, Engine, Result,
 };
 
 use super::*;
 
+/// A value which represents a known export of a component.
+///
+/// This is the return value of [`Component::export_index`] and can be passed
+/// to [`Instance::get_func`] for example.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ComponentExportIndex {
+    pub(crate) id: u32,
+    pub(crate) index: usize,
+}
+
+#[derive(Clone)]
 pub struct Component {
-    main_module: WebAssembly::Module,
-    wasi_module: Option<WebAssembly::Module>,
+    pub(crate) main_module: WebAssembly::Module,
+    pub(crate) wasi_module: Option<WebAssembly::Module>,
+    types: Rc<ComponentTypes>,
 }
 
 impl Component {
@@ -21,17 +36,22 @@ impl Component {
         since = "0.4.0",
         note = "Compiling a component synchronously can panic on the web, please use `new_safe` instead."
     )]
-    pub fn new(_engine: &Engine, bytes: impl AsRef<[u8]>) -> Result<Self> {
+    pub fn new(engine: &Engine, bytes: impl AsRef<[u8]>) -> Result<Self> {
         let files = ComponentLoader::generate_files(bytes.as_ref())?;
+        let types = ComponentTypes::decode(engine, bytes.as_ref())?;
 
-        let main_module = WebAssembly::Module::new(&bytes_to_js_value(&files.main_core))
-            .map_err(map_js_error("Synchronously compile main core module"))?;
+        let main_module = compile_module(
+            engine,
+            &files.main_core,
+            "Synchronously compile main core module",
+        )?;
 
         let wasi_module = if let Some(wasi_core) = files.wasi_core {
-            Some(
-                WebAssembly::Module::new(&bytes_to_js_value(&wasi_core))
-                    .map_err(map_js_error("Synchronously compile wasi core module"))?,
-            )
+            Some(compile_module(
+                engine,
+                &wasi_core,
+                "Synchronously compile wasi core module",
+            )?)
         } else {
             None
         };
@@ -39,24 +59,30 @@ impl Component {
         Ok(Self {
             main_module,
             wasi_module,
+            types: Rc::new(types),
         })
     }
 
-    pub async fn new_safe(_engine: &Engine, bytes: impl AsRef<[u8]>) -> Result<Self> {
+    pub async fn new_safe(engine: &Engine, bytes: impl AsRef<[u8]>) -> Result<Self> {
         let files = ComponentLoader::generate_files(bytes.as_ref())?;
+        let types = ComponentTypes::decode(engine, bytes.as_ref())?;
 
-        let promise = WebAssembly::compile(&bytes_to_js_value(&files.main_core));
-        let module = JsFuture::from(promise)
-            .await
-            .map_err(map_js_error("Asynchronously compile main core module"))?;
-        let main_module = module.into();
+        let main_module = compile_module_async(
+            engine,
+            &files.main_core,
+            "Asynchronously compile main core module",
+        )
+        .await?;
 
         let wasi_module = if let Some(wasi_core) = files.wasi_core {
-            let promise = WebAssembly::compile(&bytes_to_js_value(&wasi_core));
-            let module = JsFuture::from(promise)
-                .await
-                .map_err(map_js_error("Asynchronously compile wasi core module"))?;
-            Some(module.into())
+            Some(
+                compile_module_async(
+                    engine,
+                    &wasi_core,
+                    "Asynchronously compile wasi core module",
+                )
+                .await?,
+            )
         } else {
             None
         };
@@ -64,9 +90,69 @@ impl Component {
         Ok(Self {
             main_module,
             wasi_module,
+            types: Rc::new(types),
         })
     }
 
+    /// Loads a component from a fetch response.
+    ///
+    /// Components are transpiled to core modules before they are compiled,
+    /// so unlike [`Module::from_response`](crate::Module::from_response),
+    /// the whole response is read first.
+    pub async fn from_response(engine: &Engine, response: &Response) -> Result<Self> {
+        Self::from_stream(engine, &response_body(response)?).await
+    }
+
+    /// Same as [`Component::from_response`], but reads the component from a stream of `Uint8Array` chunks.
+    pub async fn from_stream(engine: &Engine, stream: &ReadableStream) -> Result<Self> {
+        let bytes = read_stream(stream).await?;
+        Self::new_safe(engine, bytes).await
+    }
+
+    /// Returns the type of this component as a [`types::Component`].
+    ///
+    /// This method enables runtime introspection of the type of a component
+    /// before instantiation, if necessary.
+    ///
+    /// On the web, the type is decoded from the component binary when it is loaded.
+    pub fn component_type(&self) -> types::Component {
+        self.types.component.clone()
+    }
+
+    /// Looks up a specific export of this component by `name` optionally nested
+    /// within the `instance` provided.
+    ///
+    /// This method is primarily used to acquire a [`ComponentExportIndex`]
+    /// which can be used with [`Instance`] when looking up exports. Export
+    /// lookup with [`ComponentExportIndex`] can skip string lookups at runtime
+    /// and instead use a more efficient index-based lookup.
+    ///
+    /// If `instance` is `None`, the export is looked up on the root of the
+    /// component itself, otherwise it is looked up on the `instance` specified,
+    /// which must have come from a previous invocation of this method.
+    ///
+    /// If the export is located then two values are returned: a
+    /// [`types::ComponentItem`] which enables introspection about the type of
+    /// the export and a [`ComponentExportIndex`]. The index returned notably
+    /// implements the [`InstanceExportLookup`] trait which enables using it
+    /// with [`Instance::get_func`] for example.
+    pub fn export_index(
+        &self,
+        instance: Option<&ComponentExportIndex>,
+        name: &str,
+    ) -> Option<(types::ComponentItem, ComponentExportIndex)> {
+        let index = self.types.export_index(instance, name)?;
+        let item = self.types.exports[index].item.clone();
+
+        Some((
+            item,
+            ComponentExportIndex {
+                id: self.types.id,
+                index,
+            },
+        ))
+    }
+
     pub(crate) fn is_wasi(&self) -> bool {
         self.wasi_module.is_some()
     }
@@ -83,7 +169,7 @@ impl Component {
         let memory = Self::create_module_memory(&main_instance, "cabi_realloc")?;
         lazy_memory.set(memory.clone());
 
-        Instance::new(main_instance, drop_handles, &memory)
+        Instance::new(main_instance, drop_handles, &memory, &self.types)
     }
 
     pub(crate) async fn instantiate_async(
@@ -101,7 +187,7 @@ impl Component {
         let memory = Self::create_module_memory(&main_instance, "cabi_realloc")?;
         lazy_memory.set(memory.clone());
 
-        Instance::new(main_instance, drop_handles, &memory)
+        Instance::new(main_instance, drop_handles, &memory, &self.types)
     }
 
     pub(crate) fn instantiate_wasi(
@@ -132,7 +218,7 @@ impl Component {
 
         Self::link_wasi_exports(&wasi_instance, &dyn_fns)?;
 
-        Instance::new(main_instance, drop_handles, &main_memory)
+        Instance::new(main_instance, drop_handles, &main_memory, &self.types)
     }
 
     pub(crate) async fn instantiate_wasi_async(
@@ -169,7 +255,7 @@ impl Component {
 
         Self::link_wasi_exports(&wasi_instance, &dyn_fns)?;
 
-        Instance::new(main_instance, drop_handles, &main_memory)
+        Instance::new(main_instance, drop_handles, &main_memory, &self.types)
     }
 
     fn prepare_wasi_imports(
@@ -258,12 +344,8 @@ impl Component {
         let realloc: Function = realloc.into();
 
         Ok(ModuleMemory::new(
-            crate::Memory::new(existing_memory),
+            crate::Memory::from_exported(existing_memory, None),
             realloc,
         ))
     }
 }
-
-fn bytes_to_js_value(bytes: &[u8]) -> JsValue {
-    Uint8Array::from(bytes).into()
-}
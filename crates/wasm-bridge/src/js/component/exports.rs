@@ -1,7 +1,7 @@
-use std::{collections::HashMap, marker::PhantomData};
+use std::{cell::Cell, collections::HashMap, marker::PhantomData, rc::Rc};
 
-use anyhow::Context;
-use js_sys::{Function, Object, Reflect};
+use anyhow::{bail, Context};
+use js_sys::{Function, Reflect};
 use wasm_bindgen::JsValue;
 
 use crate::{direct::ModuleMemory, helpers::map_js_error, DropHandles, Result};
@@ -20,10 +20,17 @@ impl Exports {
     pub fn root(&self) -> &ExportsRoot {
         &self.root
     }
+
+    /// Same as [`ExportsRoot::instance`]
+    pub fn instance<'a>(&'a self, name: &str) -> Option<ExportInstance<'a, 'static>> {
+        self.root.instance(name)
+    }
 }
 
 pub struct ExportsRoot {
-    exported_fns: HashMap<String, Func>,
+    types: Rc<ComponentTypes>,
+    /// Exported functions, at the same indices as the exports in `types`
+    funcs: Vec<Option<Func>>,
 }
 
 impl ExportsRoot {
@@ -31,74 +38,150 @@ impl ExportsRoot {
         exports: JsValue,
         drop_handles: DropHandles,
         memory: &ModuleMemory,
+        types: &Rc<ComponentTypes>,
     ) -> Result<Self> {
-        let mut exported_js_fns = HashMap::<String, Function>::new();
-        let mut post_return_js_fns = HashMap::<String, Function>::new();
-
         const POST_RETURN_PREFIX: &str = "cabi_post_";
 
-        let names = Object::get_own_property_names(&exports.clone().into());
-        for i in 0..names.length() {
-            let name =
-                Reflect::get_u32(&names, i).map_err(map_js_error("Get name of an export"))?;
-            let name_string = name.as_string().context("Export name should be a string")?;
-
+        let get_function = |name: &str| -> Result<Option<Function>> {
             let exported =
-                Reflect::get(&exports, &name).map_err(map_js_error("Get exported value"))?;
-
-            if exported.is_function() {
-                if name_string.starts_with(POST_RETURN_PREFIX) {
-                    post_return_js_fns.insert(name_string, exported.into());
-                } else {
-                    exported_js_fns.insert(name_string, exported.into());
+                Reflect::get(&exports, &name.into()).map_err(map_js_error("Get exported value"))?;
+            Ok(exported.is_function().then(|| exported.into()))
+        };
+
+        let enter_state = Rc::new(Cell::new(EnterState::Ready));
+
+        let mut funcs = Vec::with_capacity(types.exports.len());
+        for export in types.exports.iter() {
+            let func = match &export.item {
+                types::ComponentItem::ComponentFunc(ty) => {
+                    // Functions that JCO does not export from the main module are left out
+                    get_function(&export.core_name)?
+                        .map(|function| -> Result<Func> {
+                            let post_return =
+                                get_function(&format!("{POST_RETURN_PREFIX}{}", export.core_name))?;
+
+                            Ok(Func::new(
+                                function,
+                                post_return,
+                                memory.clone(),
+                                ty.clone(),
+                                enter_state.clone(),
+                                drop_handles.clone(),
+                            ))
+                        })
+                        .transpose()?
                 }
-            }
+                _ => None,
+            };
+            funcs.push(func);
         }
 
-        let mut exported_fns = HashMap::<String, Func>::new();
-        for (name, func) in exported_js_fns.into_iter() {
-            let post_return_name = format!("{POST_RETURN_PREFIX}{name}");
-            let post_return = post_return_js_fns.get(&post_return_name).cloned();
-            exported_fns.insert(
-                name,
-                Func::new(func, post_return, memory.clone(), drop_handles.clone()),
-            );
+        Ok(Self {
+            types: types.clone(),
+            funcs,
+        })
+    }
+
+    /// Returns the exported function at this index, if the index is from this component.
+    pub(crate) fn func_at(&self, index: &ComponentExportIndex) -> Option<Func> {
+        self.funcs[self.types.index_of(index)?].clone()
+    }
+
+    pub(crate) fn export_index(
+        &self,
+        instance: Option<&ComponentExportIndex>,
+        name: &str,
+    ) -> Option<ComponentExportIndex> {
+        Some(ComponentExportIndex {
+            id: self.types.id,
+            index: self.types.export_index(instance, name)?,
+        })
+    }
+
+    fn names(&self, instance: Option<usize>) -> &HashMap<String, usize> {
+        match instance {
+            Some(index) => &self.types.exports[index].children,
+            None => &self.types.root_exports,
         }
+    }
 
-        Ok(Self { exported_fns })
+    fn lookup_func(&self, instance: Option<usize>, name: &str) -> Option<Func> {
+        let index = *self.names(instance).get(name)?;
+        self.funcs[index].clone()
     }
 
-    pub fn typed_func<Params, Return>(&self, name: &str) -> Result<TypedFunc<Params, Return>> {
-        let func = self
-            .exported_fns
+    fn lookup_typed_func<Params, Return>(
+        &self,
+        instance: Option<usize>,
+        name: &str,
+    ) -> Result<TypedFunc<Params, Return>> {
+        let index = *self
+            .names(instance)
             .get(name)
-            .with_context(|| format!("Exported function '{name}' not found"))?
-            .clone();
+            .with_context(|| format!("failed to find function export `{name}`"))?;
+
+        let Some(func) = self.funcs[index].clone() else {
+            match self.types.exports[index].item {
+                types::ComponentItem::ComponentFunc(_) => {
+                    bail!("exported function `{name}` is not implemented by the main core module")
+                }
+                _ => bail!("export `{name}` is not a function"),
+            }
+        };
 
         Ok(TypedFunc::new(func))
     }
 
+    fn lookup_instance(&self, instance: Option<usize>, name: &str) -> Option<usize> {
+        let index = *self.names(instance).get(name)?;
+        match self.types.exports[index].item {
+            types::ComponentItem::ComponentInstance(_) => Some(index),
+            _ => None,
+        }
+    }
+
+    pub fn typed_func<Params, Return>(&self, name: &str) -> Result<TypedFunc<Params, Return>> {
+        self.lookup_typed_func(None, name)
+    }
+
+    /// Returns an exported function, to be called with dynamic [`Val`]s.
+    pub fn func(&self, name: &str) -> Option<Func> {
+        self.lookup_func(None, name)
+    }
+
+    /// Returns an exported instance, or `None` if there is no instance export with this name.
     pub fn instance<'a>(&'a self, name: &str) -> Option<ExportInstance<'a, 'static>> {
-        Some(ExportInstance::new(self, name))
+        let index = self.lookup_instance(None, name)?;
+        Some(ExportInstance::new(self, index))
     }
 }
 
 pub struct ExportInstance<'a, 'b> {
     root: &'a ExportsRoot,
-    name: String,
+    index: usize,
     _phantom: PhantomData<&'b ()>,
 }
 
 impl<'a, 'b> ExportInstance<'a, 'b> {
-    pub(crate) fn new(root: &'a ExportsRoot, name: &str) -> Self {
+    pub(crate) fn new(root: &'a ExportsRoot, index: usize) -> Self {
         Self {
             root,
+            index,
             _phantom: PhantomData,
-            name: name.into(),
         }
     }
 
     pub fn typed_func<Params, Return>(&self, name: &str) -> Result<TypedFunc<Params, Return>> {
-        self.root.typed_func(&format!("{}#{}", self.name, name))
+        self.root.lookup_typed_func(Some(self.index), name)
+    }
+
+    pub fn func(&self, name: &str) -> Option<Func> {
+        self.root.lookup_func(Some(self.index), name)
+    }
+
+    /// Returns an instance exported from this instance, or `None` if there is no such export.
+    pub fn instance(&self, name: &str) -> Option<ExportInstance<'a, 'b>> {
+        let index = self.root.lookup_instance(Some(self.index), name)?;
+        Some(ExportInstance::new(self.root, index))
     }
 }
@@ -1,12 +1,29 @@
-use js_sys::Function;
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
 
-use crate::{direct::ModuleMemory, DropHandles};
+use anyhow::{bail, Context, Error};
+use js_sys::{Array, Function};
+use wasm_bindgen::JsValue;
+
+use super::{canonical_abi::*, types::ComponentFunc, types::Type, Val};
+use crate::{
+    direct::{ModuleMemory, ReadableMemory},
+    helpers::map_js_error,
+    AsContext, AsContextMut, DropHandles, FromJsValue, Result, Trap,
+};
 
 #[derive(Debug, Clone)]
 pub struct Func {
     pub(crate) function: Function,
     pub(crate) post_return: Option<Function>,
     pub(crate) memory: ModuleMemory,
+    ty: ComponentFunc,
+    /// Whether the instance can be entered, shared by all functions of an instance
+    enter_state: Rc<Cell<EnterState>>,
+    /// Value returned by the last call, passed to the post-return function
+    post_return_arg: Rc<RefCell<Option<JsValue>>>,
     _drop_handles: DropHandles,
 }
 
@@ -15,13 +32,244 @@ impl Func {
         function: Function,
         post_return: Option<Function>,
         memory: ModuleMemory,
+        ty: ComponentFunc,
+        enter_state: Rc<Cell<EnterState>>,
         drop_handles: DropHandles,
     ) -> Self {
         Self {
             function,
             post_return,
             memory,
+            ty,
+            enter_state,
+            post_return_arg: Rc::new(RefCell::new(None)),
             _drop_handles: drop_handles,
         }
     }
+
+    /// Enters the instance before a call, fails if the instance is already in a call,
+    /// or if the previous call has not been followed by [`Self::post_return`].
+    ///
+    /// If the call fails, the instance can be entered again, unless the guest traps.
+    /// Like in wasmtime, a trap "poisons" the instance and it can never be entered again.
+    pub(crate) fn enter(&self) -> Result<Entered<'_>> {
+        match self.enter_state.get() {
+            EnterState::Ready => {
+                self.enter_state.set(EnterState::InCall);
+                Ok(Entered {
+                    state: &self.enter_state,
+                    finished: false,
+                })
+            }
+            EnterState::InCall => bail!(Trap::CannotEnterComponent),
+            EnterState::Poisoned => {
+                Err(Error::new(Trap::CannotEnterComponent).context("instance poisoned by trap"))
+            }
+        }
+    }
+
+    /// Keeps the value returned by a successful call for [`Self::post_return`],
+    /// which allows the instance to be entered again.
+    pub(crate) fn leave(&self, mut entered: Entered<'_>, result: JsValue) {
+        *self.post_return_arg.borrow_mut() = Some(result);
+        entered.finished = true;
+    }
+
+    /// Get the parameter types for this function.
+    pub fn params(&self, _store: impl AsContext) -> Box<[Type]> {
+        self.ty.params().collect()
+    }
+
+    /// Get the result types for this function.
+    pub fn results(&self, _store: impl AsContext) -> Box<[Type]> {
+        self.ty.results().collect()
+    }
+
+    /// Invokes this function with the `params` given and returns the result.
+    ///
+    /// The `params` provided must match the parameters that this function takes
+    /// in terms of their types and the number of parameters. Results will be
+    /// written to the `results` slice provided if the call completes
+    /// successfully. The initial types of the values in `results` are ignored
+    /// and values are overwritten to write the result. It's required that the
+    /// size of `results` exactly matches the number of results that this
+    /// function produces.
+    ///
+    /// Note that after a function is invoked the embedder needs to invoke
+    /// [`Func::post_return`] to execute any final cleanup required by the
+    /// guest. Until then, calling any function of the instance returns an error.
+    pub fn call(
+        &self,
+        mut store: impl AsContextMut,
+        params: &[Val],
+        results: &mut [Val],
+    ) -> Result<()> {
+        let param_types = &self.ty.0.params;
+        let result_types = &self.ty.0.results;
+
+        if params.len() != param_types.len() {
+            bail!(
+                "expected {} argument(s), got {}",
+                param_types.len(),
+                params.len()
+            );
+        }
+        if results.len() != result_types.len() {
+            bail!(
+                "expected {} result(s), got {}",
+                result_types.len(),
+                results.len()
+            );
+        }
+
+        // Releases the store's data if called from a host function
+        store.as_context_mut();
+        let entered = self.enter()?;
+
+        let memory = &self.memory;
+        let args = Array::new();
+
+        let mut flat_params = Vec::new();
+        param_types
+            .iter()
+            .for_each(|ty| flatten(ty, &mut flat_params));
+
+        if flat_params.len() <= MAX_FLAT_PARAMS {
+            let mut values = Vec::with_capacity(flat_params.len());
+            for (val, ty) in params.iter().zip(param_types.iter()) {
+                lower(val, ty, memory, &mut values)?;
+            }
+
+            for value in values {
+                args.push(&value.to_js_value());
+            }
+        } else {
+            // Too many parameters, they are passed as a tuple in memory
+            let (size, align) = fields_size_align(param_types.iter());
+            let mut bytes = vec![0; size];
+            store_fields(params.iter(), param_types, memory, &mut bytes)?;
+
+            let addr = write_bytes(&bytes, align, memory)?;
+            args.push(&addr.into());
+        }
+
+        let result_js = self
+            .function
+            .apply(&JsValue::UNDEFINED, &args)
+            .map_err(map_js_error("Error inside exported function"))
+            .inspect_err(|error| entered.poison_on_trap(error))?;
+
+        let mut flat_results = Vec::new();
+        result_types
+            .iter()
+            .for_each(|ty| flatten(ty, &mut flat_results));
+
+        let values = if flat_results.len() <= MAX_FLAT_RESULTS {
+            let mut flat = flat_results
+                .first()
+                .map(|ty| FlatValue::from_js_value(&result_js, *ty))
+                .transpose()?
+                .into_iter();
+
+            result_types
+                .iter()
+                .map(|ty| lift(ty, &mut flat, memory))
+                .collect::<Result<Vec<_>>>()
+        } else {
+            // The results are returned as a tuple in memory
+            let addr = u32::from_js_value(&result_js)? as usize;
+            let (size, _) = fields_size_align(result_types.iter());
+            let bytes = memory.read_to_vec(addr, size)?;
+
+            load_fields(result_types, &bytes, memory)
+        }
+        .context("Cannot lift the results of the exported function")?;
+
+        for (result, value) in results.iter_mut().zip(values) {
+            *result = value;
+        }
+
+        self.leave(entered, result_js);
+
+        Ok(())
+    }
+
+    /// Exactly like [`Self::call`], exists to match wasmtime's API.
+    pub async fn call_async(
+        &self,
+        store: impl AsContextMut,
+        params: &[Val],
+        results: &mut [Val],
+    ) -> Result<()> {
+        self.call(store, params, results)
+    }
+
+    /// Invokes the `post-return` canonical ABI option, if specified, after a
+    /// [`Func::call`] has finished.
+    ///
+    /// Returns an error if this function has not been called since the last
+    /// `post_return`, for example when called from inside of the call itself.
+    pub fn post_return(&self, _store: impl AsContextMut) -> Result<()> {
+        let arg = self.post_return_arg.take().context(
+            "post_return can only be called after a function has previously been called",
+        )?;
+
+        let result = match &self.post_return {
+            Some(func) => func
+                .call1(&JsValue::UNDEFINED, &arg)
+                .map(|_| ())
+                .map_err(map_js_error("Call post_return")),
+            None => Ok(()),
+        };
+
+        // Like the call itself, the post-return function poisons the instance if it traps
+        let state = match &result {
+            Err(error) if is_trap(error) => EnterState::Poisoned,
+            _ => EnterState::Ready,
+        };
+        self.enter_state.set(state);
+        result
+    }
+
+    pub async fn post_return_async(&self, store: impl AsContextMut) -> Result<()> {
+        self.post_return(store)
+    }
+}
+
+/// Whether the functions of an instance can be called, shared by all of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum EnterState {
+    Ready,
+    /// In a call, or waiting for [`Func::post_return`] after one
+    InCall,
+    /// The guest trapped, the instance can never be entered again
+    Poisoned,
+}
+
+/// Returned by [`Func::enter`], allows the instance to be entered again
+/// when dropped before [`Func::leave`], unless the guest trapped.
+pub(crate) struct Entered<'a> {
+    state: &'a Cell<EnterState>,
+    finished: bool,
+}
+
+impl Entered<'_> {
+    /// Poisons the instance if the guest function failed with a trap.
+    pub(crate) fn poison_on_trap(&self, error: &Error) {
+        if is_trap(error) {
+            self.state.set(EnterState::Poisoned);
+        }
+    }
+}
+
+impl Drop for Entered<'_> {
+    fn drop(&mut self) {
+        if !self.finished && self.state.get() != EnterState::Poisoned {
+            self.state.set(EnterState::Ready);
+        }
+    }
+}
+
+fn is_trap(error: &Error) -> bool {
+    error.downcast_ref::<Trap>().is_some()
 }
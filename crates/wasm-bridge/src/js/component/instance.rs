@@ -1,5 +1,6 @@
-use std::marker::PhantomData;
+use std::rc::Rc;
 
+use anyhow::Context;
 use js_sys::WebAssembly;
 use wasm_bindgen::JsValue;
 
@@ -15,9 +16,10 @@ impl Instance {
         instance: WebAssembly::Instance,
         drop_handles: DropHandles,
         memory: &ModuleMemory,
+        types: &Rc<ComponentTypes>,
     ) -> Result<Self> {
         let js_exports: JsValue = instance.exports().into();
-        let exports_root = ExportsRoot::new(js_exports, drop_handles, memory)?;
+        let exports_root = ExportsRoot::new(js_exports, drop_handles, memory, types)?;
         let exports = Exports::new(exports_root);
 
         Ok(Self { exports })
@@ -26,18 +28,148 @@ impl Instance {
     pub fn exports(&self, _store: impl AsContextMut) -> &Exports {
         &self.exports
     }
+
+    /// Looks up an exported function by name or by a [`ComponentExportIndex`].
+    ///
+    /// Looking up by an index avoids hashing the name on every call.
+    /// Returns `None` if the export is not a function, or if the index
+    /// belongs to a different component.
+    pub fn get_func(
+        &self,
+        _store: impl AsContextMut,
+        name: impl InstanceExportLookup,
+    ) -> Option<Func> {
+        let index = name.lookup(self)?;
+        self.exports.root().func_at(&index)
+    }
+
+    /// Looks up an exported [`Func`] value by name and with its type.
+    ///
+    /// Returns an error if `name` isn't a function export.
+    pub fn get_typed_func<Params, Results>(
+        &self,
+        store: impl AsContextMut,
+        name: impl InstanceExportLookup,
+    ) -> Result<TypedFunc<Params, Results>> {
+        let func = self
+            .get_func(store, name)
+            .context("failed to find function export")?;
+        Ok(TypedFunc::new(func))
+    }
+
+    /// Looks up the index of the export `name`, nested within the exported `instance`
+    /// if one is provided.
+    ///
+    /// The index can be passed to [`Instance::get_func`] and [`Instance::get_export`].
+    /// The same index is returned by [`Component::export_index`].
+    pub fn get_export(
+        &self,
+        _store: impl AsContextMut,
+        instance: Option<&ComponentExportIndex>,
+        name: &str,
+    ) -> Option<ComponentExportIndex> {
+        self.exports.root().export_index(instance, name)
+    }
+}
+
+/// Trait used to lookup the export of a component instance.
+///
+/// This trait is used as an implementation detail of [`Instance::get_func`]
+/// and related `get_*` methods. Notable implementors of this trait are:
+///
+/// * `str`
+/// * `String`
+/// * [`ComponentExportIndex`]
+///
+/// Note that this is intended to be a wasm-bridge-sealed trait so it shouldn't
+/// need to be implemented externally.
+pub trait InstanceExportLookup {
+    #[doc(hidden)]
+    fn lookup(&self, instance: &Instance) -> Option<ComponentExportIndex>;
 }
 
+impl<T> InstanceExportLookup for &T
+where
+    T: InstanceExportLookup + ?Sized,
+{
+    fn lookup(&self, instance: &Instance) -> Option<ComponentExportIndex> {
+        T::lookup(self, instance)
+    }
+}
+
+impl InstanceExportLookup for str {
+    fn lookup(&self, instance: &Instance) -> Option<ComponentExportIndex> {
+        instance.exports.root().export_index(None, self)
+    }
+}
+
+impl InstanceExportLookup for String {
+    fn lookup(&self, instance: &Instance) -> Option<ComponentExportIndex> {
+        str::lookup(self, instance)
+    }
+}
+
+impl InstanceExportLookup for ComponentExportIndex {
+    fn lookup(&self, _instance: &Instance) -> Option<ComponentExportIndex> {
+        Some(*self)
+    }
+}
+
+/// A component with its imports resolved by [`Linker::instantiate_pre`],
+/// ready to be instantiated cheaply in many stores.
 pub struct InstancePre<T> {
-    _phantom: PhantomData<T>,
+    component: Component,
+    imports: Rc<ResolvedImports<T>>,
+}
+
+impl<T> Clone for InstancePre<T> {
+    fn clone(&self) -> Self {
+        Self {
+            component: self.component.clone(),
+            imports: self.imports.clone(),
+        }
+    }
 }
 
 impl<T> InstancePre<T> {
-    pub fn instantiate(&self, _store: impl AsContextMut<Data = T>) -> Result<Instance> {
-        todo!()
+    pub(crate) fn new(component: Component, imports: Rc<ResolvedImports<T>>) -> Self {
+        Self { component, imports }
     }
 
-    pub async fn instantiate_async(&self, _store: impl AsContextMut<Data = T>) -> Result<Instance> {
-        todo!()
+    pub fn component(&self) -> &Component {
+        &self.component
+    }
+
+    #[deprecated(
+        since = "0.5.0",
+        note = "Instantiating a component synchronously can panic on the web, please use `instantiate_safe` instead."
+    )]
+    pub fn instantiate(&self, store: impl AsContextMut<Data = T>) -> Result<Instance> {
+        let (imports, drop_handles, memory, wasi_info) = self.imports.prepare(store);
+
+        if let Some(wasi_info) = wasi_info {
+            self.component
+                .instantiate_wasi(&imports, drop_handles, &memory, wasi_info)
+        } else {
+            self.component.instantiate(&imports, drop_handles, &memory)
+        }
+    }
+
+    pub async fn instantiate_safe(&self, store: impl AsContextMut<Data = T>) -> Result<Instance> {
+        self.instantiate_async(store).await
+    }
+
+    pub async fn instantiate_async(&self, store: impl AsContextMut<Data = T>) -> Result<Instance> {
+        let (imports, drop_handles, memory, wasi_info) = self.imports.prepare(store);
+
+        if let Some(wasi_info) = wasi_info {
+            self.component
+                .instantiate_wasi_async(&imports, drop_handles, &memory, wasi_info)
+                .await
+        } else {
+            self.component
+                .instantiate_async(&imports, drop_handles, &memory)
+                .await
+        }
     }
 }
@@ -1,7 +1,7 @@
 use std::{collections::HashMap, rc::Rc};
 
-use anyhow::Context;
-use js_sys::{Array, Function, Object, Reflect};
+use anyhow::{bail, Context};
+use js_sys::{Array, Function, Object, Reflect, WebAssembly};
 use wasm_bindgen::JsValue;
 
 use crate::{
@@ -11,6 +11,9 @@ use crate::{
 
 use super::*;
 
+/// Import module of the functions imported by the component itself, not by its instances.
+const ROOT_INSTANCE: &str = "$root";
+
 static WASI_IMPORT_NAMES: &[&str] = &[
     "clock_time_get",
     "random_get",
@@ -24,7 +27,7 @@ static WASI_IMPORT_NAMES: &[&str] = &[
 pub struct Linker<T> {
     interfaces: HashMap<String, LinkerInstance<T>>,
     wasi_interfaces: HashMap<String, LinkerInstance<T>>,
-    wasi_object: Option<Box<dyn Fn() -> Object>>,
+    wasi_object: Option<Rc<dyn Fn() -> Object>>,
 }
 
 impl<T> Linker<T> {
@@ -45,13 +48,8 @@ impl<T> Linker<T> {
         store: impl AsContextMut<Data = T>,
         component: &Component,
     ) -> Result<Instance> {
-        let (imports, drop_handles, memory, wasi_info) = self.prepare_imports(store, component)?;
-
-        if let Some(wasi_info) = wasi_info {
-            component.instantiate_wasi(&imports, drop_handles, &memory, wasi_info)
-        } else {
-            component.instantiate(&imports, drop_handles, &memory)
-        }
+        #[allow(deprecated)]
+        self.instantiate_pre(component)?.instantiate(store)
     }
 
     pub async fn instantiate_safe(
@@ -67,83 +65,45 @@ impl<T> Linker<T> {
         store: impl AsContextMut<Data = T>,
         component: &Component,
     ) -> Result<Instance> {
-        let (imports, drop_handles, memory, wasi_info) = self.prepare_imports(store, component)?;
-
-        if let Some(wasi_info) = wasi_info {
-            component
-                .instantiate_wasi_async(&imports, drop_handles, &memory, wasi_info)
-                .await
-        } else {
-            component
-                .instantiate_async(&imports, drop_handles, &memory)
-                .await
-        }
+        self.instantiate_pre(component)?
+            .instantiate_async(store)
+            .await
     }
 
-    fn prepare_imports(
-        &self,
-        mut store: impl AsContextMut<Data = T>,
-        component: &Component,
-    ) -> Result<(Object, DropHandles, LazyModuleMemory, Option<WasiInfo>)> {
-        let mut closures = Vec::new();
-
-        let (imports, wasi_info) = if component.is_wasi() {
-            let wasi_imports = self.wasi_object.as_ref().context("Get wasi shim object")?();
-            let wasi_memory = LazyModuleMemory::new();
-
-            for (name, interface) in self.wasi_interfaces.iter() {
-                let name_js: JsValue = name.into();
-
-                let mut imports_obj =
-                    Reflect::get(&wasi_imports, &name_js).expect("imports is an object");
-                if imports_obj.is_undefined() {
-                    imports_obj = Object::new().into();
-                }
-
-                interface.prepare_imports(&mut store, &mut closures, &imports_obj, &wasi_memory);
-                Reflect::set(&wasi_imports, &name_js, &imports_obj).expect("imports is an object");
-            }
-
-            let preview = Object::new();
-            let mut setters = HashMap::<&'static str, Array>::new();
-            for name in WASI_IMPORT_NAMES {
-                let (func, setter) = create_dyn_fn(name);
-                Reflect::set(&preview, &(*name).into(), &func).expect("preview is an object");
-                setters.insert(name, setter);
+    /// Resolves the imports of the component, so that it can be instantiated many times.
+    ///
+    /// Only the functions imported by the component's core modules are kept, and a missing
+    /// import is reported here instead of when instantiating. Each instantiation then only
+    /// creates the closures for the store and instantiates the already compiled modules.
+    pub fn instantiate_pre(&self, component: &Component) -> Result<InstancePre<T>> {
+        let wasi = match component.wasi_module.as_ref() {
+            Some(wasi_module) => {
+                let wasi_object = self.wasi_object.clone().context("Get wasi shim object")?;
+
+                // The wasi shim object provides the imports that are not in the linker
+                let (fns, _) = resolve_fns(&self.wasi_interfaces, wasi_module);
+                Some((wasi_object, fns))
             }
-
-            let imports = Object::new();
-            Reflect::set(
-                &imports,
-                static_str_to_js("wasi_snapshot_preview1"),
-                &preview,
-            )
-            .expect("imports is an object");
-
-            (imports, Some((wasi_imports, setters, wasi_memory)))
-        } else {
-            (Object::new(), None)
+            None => None,
         };
 
-        let memory = LazyModuleMemory::new();
-
-        for (name, interface) in self.interfaces.iter() {
-            let name_js: JsValue = name.into();
-
-            let mut imports_obj = Reflect::get(&imports, &name_js).expect("imports is an object");
-            if imports_obj.is_undefined() {
-                imports_obj = Object::new().into();
+        let (fns, missing) = resolve_fns(&self.interfaces, &component.main_module);
+        for (module, name) in missing {
+            // Provided by the wasi module
+            if wasi.is_some() && module == "wasi_snapshot_preview1" {
+                continue;
             }
 
-            interface.prepare_imports(&mut store, &mut closures, &imports_obj, &memory);
-            Reflect::set(&imports, &name_js, &imports_obj).expect("imports is an object");
+            bail!("Component imports '{name}' from instance '{module}', but it is not defined in the linker");
         }
+        typecheck_fns(&fns, &component.component_type())?;
 
-        Ok((imports, Rc::new(closures), memory, wasi_info))
+        let imports = ResolvedImports { fns, wasi };
+        Ok(InstancePre::new(component.clone(), Rc::new(imports)))
     }
 
     pub fn root(&mut self) -> &mut LinkerInstance<T> {
-        self.instance("$root").unwrap()
+        self.instance(ROOT_INSTANCE).unwrap()
     }
 
     pub fn instance<'a>(&'a mut self, name: &str) -> Result<&'a mut LinkerInstance<T>> {
@@ -167,12 +127,12 @@ impl<T> Linker<T> {
     }
 
     pub fn set_wasi_object(&mut self, creator: impl Fn() -> Object + 'static) {
-        self.wasi_object = Some(Box::new(creator));
+        self.wasi_object = Some(Rc::new(creator));
     }
 }
 
 pub struct LinkerInstance<T> {
-    fns: Vec<PreparedFn<T>>,
+    fns: Vec<Rc<PreparedFn<T>>>,
 }
 
 impl<T> LinkerInstance<T> {
@@ -185,8 +145,11 @@ impl<T> LinkerInstance<T> {
         T: 'static,
         F: IntoMakeClosure<T, Params, Results>,
     {
-        self.fns
-            .push(PreparedFn::new(name, func.into_make_closure()));
+        self.fns.push(Rc::new(PreparedFn {
+            name: name.into(),
+            creator: func.into_make_closure(),
+            typecheck: F::typecheck,
+        }));
 
         Ok(())
     }
@@ -203,47 +166,187 @@ impl<T> LinkerInstance<T> {
         self.func_wrap(&format!("[resource-drop]{name}"), destroy)
     }
 
-    fn prepare_imports(
-        &self,
-        mut store: impl AsContextMut<Data = T>,
-        drop_handles: &mut Vec<DropHandle>,
-        imports: &JsValue,
-        memory: &LazyModuleMemory,
-    ) {
-        let data_handle = store.as_context_mut().data_handle();
-
-        for function in self.fns.iter() {
-            let drop_handle = function.add_to_imports(imports, data_handle.clone(), memory.clone());
-            drop_handles.push(drop_handle);
-        }
+    fn get(&self, name: &str) -> Option<&Rc<PreparedFn<T>>> {
+        // Functions defined later replace the earlier ones
+        self.fns.iter().rev().find(|function| function.name == name)
     }
 }
 
 struct PreparedFn<T> {
     name: String,
     creator: MakeClosure<T>,
+    typecheck: fn(&types::ComponentFunc) -> Result<()>,
 }
 
-impl<T> PreparedFn<T> {
-    fn new(name: &str, creator: MakeClosure<T>) -> Self {
-        Self {
-            name: name.into(),
-            creator,
+/// Linker functions imported by a core module, grouped by the import module name.
+type ResolvedFns<T> = Vec<(JsValue, Vec<(JsValue, Rc<PreparedFn<T>>)>)>;
+
+/// Finds the linker functions imported by a core module,
+/// returns them together with the imports that are not in the linker.
+fn resolve_fns<T>(
+    interfaces: &HashMap<String, LinkerInstance<T>>,
+    module: &WebAssembly::Module,
+) -> (ResolvedFns<T>, Vec<(String, String)>) {
+    let mut resolved = Vec::<(String, Vec<_>)>::new();
+    let mut missing = Vec::new();
+
+    for import in WebAssembly::Module::imports(module).iter() {
+        let get = |key| {
+            Reflect::get(&import, static_str_to_js(key))
+                .expect("import is an object")
+                .as_string()
+                .unwrap_or_default()
+        };
+        let (module, name) = (get("module"), get("name"));
+
+        let Some(function) = interfaces
+            .get(&module)
+            .and_then(|instance| instance.get(&name))
+        else {
+            missing.push((module, name));
+            continue;
+        };
+
+        let entry = (JsValue::from(name.as_str()), function.clone());
+        match resolved
+            .iter_mut()
+            .find(|(resolved, _)| *resolved == module)
+        {
+            Some((_, fns)) => fns.push(entry),
+            None => resolved.push((module, vec![entry])),
+        }
+    }
+
+    let resolved = resolved
+        .into_iter()
+        .map(|(module, fns)| (JsValue::from(module.as_str()), fns))
+        .collect();
+
+    (resolved, missing)
+}
+
+/// Checks the linker functions against the types of the functions imported by the component.
+///
+/// Functions without a known type, like resource destructors, are not checked.
+fn typecheck_fns<T>(fns: &ResolvedFns<T>, component: &types::Component) -> Result<()> {
+    for (module, module_fns) in fns {
+        let module = module.as_string().unwrap_or_default();
+
+        let items = if module == ROOT_INSTANCE {
+            Some(component.0.imports.as_slice())
+        } else {
+            match find_item(&component.0.imports, &module) {
+                Some(types::ComponentItem::ComponentInstance(instance)) => Some(&*instance.0),
+                _ => None,
+            }
+        };
+        let Some(items) = items else {
+            continue;
+        };
+
+        for (name, function) in module_fns {
+            let name = name.as_string().unwrap_or_default();
+            let Some(types::ComponentItem::ComponentFunc(ty)) = find_item(items, &name) else {
+                continue;
+            };
+
+            let result =
+                (function.typecheck)(ty).with_context(|| format!("type mismatch with `{name}`"));
+            if module == ROOT_INSTANCE {
+                result?;
+            } else {
+                result.with_context(|| format!("component imported instance `{module}`"))?;
+            }
         }
     }
 
-    #[must_use]
-    fn add_to_imports(
+    Ok(())
+}
+
+fn find_item<'a>(
+    items: &'a [(String, types::ComponentItem)],
+    name: &str,
+) -> Option<&'a types::ComponentItem> {
+    items
+        .iter()
+        .find(|(item_name, _)| item_name == name)
+        .map(|(_, item)| item)
+}
+
+/// Imports of a component resolved by [`Linker::instantiate_pre`].
+pub(crate) struct ResolvedImports<T> {
+    fns: ResolvedFns<T>,
+    wasi: Option<(Rc<dyn Fn() -> Object>, ResolvedFns<T>)>,
+}
+
+impl<T> ResolvedImports<T> {
+    /// Creates the import objects for an instance in the store.
+    pub(crate) fn prepare(
         &self,
-        imports: &JsValue,
-        handle: DataHandle<T>,
-        memory: LazyModuleMemory,
-    ) -> DropHandle {
-        let (js_val, handler) = (self.creator)(handle, memory);
+        mut store: impl AsContextMut<Data = T>,
+    ) -> (Object, DropHandles, LazyModuleMemory, Option<WasiInfo>) {
+        let data_handle = store.as_context_mut().data_handle().clone();
+        let mut closures = Vec::new();
+
+        let (imports, wasi_info) = if let Some((wasi_object, wasi_fns)) = &self.wasi {
+            let wasi_imports = wasi_object();
+            let wasi_memory = LazyModuleMemory::new();
+            add_fns(
+                &wasi_imports,
+                wasi_fns,
+                &data_handle,
+                &wasi_memory,
+                &mut closures,
+            );
+
+            let preview = Object::new();
+            let mut setters = HashMap::<&'static str, Array>::new();
+            for name in WASI_IMPORT_NAMES {
+                let (func, setter) = create_dyn_fn(name);
+                Reflect::set(&preview, &(*name).into(), &func).expect("preview is an object");
+                setters.insert(name, setter);
+            }
 
-        Reflect::set(imports, &self.name.as_str().into(), &js_val).expect("imports is object");
+            let imports = Object::new();
+            Reflect::set(
+                &imports,
+                static_str_to_js("wasi_snapshot_preview1"),
+                &preview,
+            )
+            .expect("imports is an object");
+
+            (imports, Some((wasi_imports, setters, wasi_memory)))
+        } else {
+            (Object::new(), None)
+        };
+
+        let memory = LazyModuleMemory::new();
+        add_fns(&imports, &self.fns, &data_handle, &memory, &mut closures);
+
+        (imports, Rc::new(closures), memory, wasi_info)
+    }
+}
+
+fn add_fns<T>(
+    imports: &Object,
+    fns: &ResolvedFns<T>,
+    data_handle: &DataHandle<T>,
+    memory: &LazyModuleMemory,
+    closures: &mut Vec<DropHandle>,
+) {
+    for (module, module_fns) in fns {
+        let mut module_imports = Reflect::get(imports, module).expect("imports is an object");
+        if module_imports.is_undefined() {
+            module_imports = Object::new().into();
+        }
+
+        for (name, function) in module_fns {
+            let (closure, drop_handle) = (function.creator)(data_handle.clone(), memory.clone());
+            Reflect::set(&module_imports, name, &closure).expect("module imports is an object");
+            closures.push(drop_handle);
+        }
 
-        handler
+        Reflect::set(imports, module, &module_imports).expect("imports is an object");
     }
 }
 
@@ -6,13 +6,19 @@ use wasm_bindgen::{prelude::*, JsValue};
 use crate::direct::*;
 use crate::FromJsValue;
 use crate::{DataHandle, DropHandle, Result, StoreContextMut};
+use anyhow::Context;
 use js_sys::{Array, Function};
 
+use super::types::{ComponentFunc, Tuple, Type};
+
 pub(crate) type MakeClosure<T> =
     Box<dyn Fn(DataHandle<T>, LazyModuleMemory) -> (JsValue, DropHandle)>;
 
 pub trait IntoMakeClosure<T, Params, Results> {
     fn into_make_closure(self) -> MakeClosure<T>;
+
+    /// Checks the parameters and results against the type of the imported function.
+    fn typecheck(ty: &ComponentFunc) -> Result<()>;
 }
 
 impl<T, P, R, F> IntoMakeClosure<T, P, R> for F
@@ -43,9 +49,8 @@ where
                             .map_err(|err| format!("from js ptr return: {err:?}"))?
                     };
 
-                    let result =
-                        self_clone(StoreContextMut::new(&mut handle.borrow_mut()), args)
-                            .map_err(|err| format!("host imported fn returned error: {err:?}"))?;
+                    let result = self_clone(StoreContextMut::new(&handle), args)
+                        .map_err(|err| format!("host imported fn returned error: {err:?}"))?;
 
                     if R::NUM_ARGS <= 1 {
                         let result = result
@@ -77,6 +82,13 @@ where
 
         Box::new(make_closure)
     }
+
+    fn typecheck(ty: &ComponentFunc) -> Result<()> {
+        P::typecheck(&Type::Tuple(Tuple(ty.params().collect())))
+            .context("type mismatch with parameters")?;
+        R::typecheck(&Type::Tuple(Tuple(ty.results().collect())))
+            .context("type mismatch with results")
+    }
 }
 
 /**
@@ -17,6 +17,14 @@ pub use func::*;
 mod typed_func;
 pub use typed_func::*;
 
+pub mod types;
+pub use types::Type;
+
+mod values;
+pub use values::*;
+
+mod canonical_abi;
+
 mod make_closure;
 pub use make_closure::*;
 
@@ -32,6 +40,9 @@ pub use shared_table::*;
 mod component_loader;
 pub(crate) use component_loader::*;
 
+mod type_decoder;
+pub(crate) use type_decoder::*;
+
 pub use wasm_bridge_macros::bindgen_js as bindgen;
 pub use wasm_bridge_macros::flags_js as flags;
 
@@ -1,4 +1,4 @@
-use std::marker::PhantomData;
+use std::{cell::Cell, marker::PhantomData};
 
 #[derive(Debug, Clone)]
 pub struct Resource<T> {
@@ -30,8 +30,22 @@ impl ResourceAny {
     }
 }
 
-pub struct ResourceType;
+/// Type of a resource, used to tell apart the resources in component types.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct ResourceType {
+    id: u32,
+}
 
 impl ResourceType {
     pub fn host<T>() {}
+
+    /// Creates a resource type that is different from all the others.
+    pub(crate) fn unique() -> Self {
+        thread_local! {
+            static NEXT_ID: Cell<u32> = const { Cell::new(0) };
+        }
+
+        let id = NEXT_ID.with(|next_id| next_id.replace(next_id.get() + 1));
+        Self { id }
+    }
 }
@@ -0,0 +1,390 @@
+use std::{cell::Cell, collections::HashMap, rc::Rc};
+
+use anyhow::Context;
+use wasmparser::{
+    types::{
+        ComponentAnyTypeId, ComponentCoreModuleTypeId, ComponentDefinedType,
+        ComponentDefinedTypeId, ComponentEntityType, ComponentFuncTypeId, ComponentInstanceTypeId,
+        ComponentTypeId, ComponentValType, CoreTypeId, EntityType, ResourceId, Types,
+    },
+    Parser, Payload, PrimitiveValType, Validator, WasmFeatures,
+};
+
+use super::{types::*, ComponentExportIndex};
+use crate::{Engine, ExternType, FuncType, GlobalType, MemoryType, Mutability, Result};
+
+/// Types read from the component binary.
+///
+/// JCO transpiles the component to core modules, which do not have the component types,
+/// so they are decoded from the original binary instead.
+#[derive(Debug)]
+pub(crate) struct ComponentTypes {
+    /// Unique id of the component, export indices of other components are rejected
+    pub(crate) id: u32,
+    /// Type of the whole component, with all its imports and exports
+    pub(crate) component: Component,
+    /// Exports of the component and of its exported instances, indexed by [`ComponentExportIndex`]
+    pub(crate) exports: Vec<ExportDef>,
+    /// Indices of the exports of the component, by name
+    pub(crate) root_exports: HashMap<String, usize>,
+}
+
+/// An export of the component, or of one of its exported instances.
+#[derive(Debug)]
+pub(crate) struct ExportDef {
+    pub(crate) item: ComponentItem,
+    /// Name of the core export that implements an exported function,
+    /// JCO joins the names of the instances and the function with `#`
+    pub(crate) core_name: String,
+    /// Indices of the exports of an exported instance, by name
+    pub(crate) children: HashMap<String, usize>,
+}
+
+thread_local! {
+    static NEXT_ID: Cell<u32> = const { Cell::new(0) };
+}
+
+impl ComponentTypes {
+    pub(crate) fn decode(engine: &Engine, bytes: &[u8]) -> Result<Self> {
+        let types = Validator::new_with_features(WasmFeatures::all())
+            .validate_all(bytes)
+            .context("Validate the component")?;
+
+        let mut decoder = TypeDecoder {
+            engine,
+            types: &types,
+            defined: HashMap::new(),
+            resources: HashMap::new(),
+        };
+
+        let (import_names, export_names) = root_names(bytes)?;
+
+        let mut imports = Vec::new();
+        for name in import_names {
+            let ty = types
+                .component_entity_type_of_import(&name)
+                .with_context(|| format!("Get the type of import '{name}'"))?;
+
+            if let Some(item) = decoder.item(&ty) {
+                imports.push((name, item));
+            }
+        }
+
+        let mut exports = Vec::new();
+        for name in export_names {
+            let ty = types
+                .component_entity_type_of_export(&name)
+                .with_context(|| format!("Get the type of export '{name}'"))?;
+
+            if let Some(item) = decoder.item(&ty) {
+                exports.push((name, item));
+            }
+        }
+
+        let mut export_defs = Vec::new();
+        let root_exports = add_exports(&mut export_defs, &exports, None);
+
+        Ok(Self {
+            id: NEXT_ID.with(|id| id.replace(id.get() + 1)),
+            component: Component(Rc::new(ComponentItems { imports, exports })),
+            exports: export_defs,
+            root_exports,
+        })
+    }
+
+    /// Finds the export `name` of the root, or of the exported instance at `instance`.
+    pub(crate) fn export_index(
+        &self,
+        instance: Option<&ComponentExportIndex>,
+        name: &str,
+    ) -> Option<usize> {
+        let names = match instance {
+            Some(instance) => &self.exports[self.index_of(instance)?].children,
+            None => &self.root_exports,
+        };
+        names.get(name).copied()
+    }
+
+    /// Checks that the index belongs to this component.
+    pub(crate) fn index_of(&self, index: &ComponentExportIndex) -> Option<usize> {
+        (index.id == self.id).then_some(index.index)
+    }
+}
+
+/// Adds the exports and all nested exports to the list, returns the indices of the exports.
+fn add_exports(
+    defs: &mut Vec<ExportDef>,
+    items: &[(String, ComponentItem)],
+    parent: Option<&str>,
+) -> HashMap<String, usize> {
+    let mut indices = HashMap::new();
+
+    for (name, item) in items {
+        let core_name = match parent {
+            Some(parent) => format!("{parent}#{name}"),
+            None => name.clone(),
+        };
+
+        let index = defs.len();
+        defs.push(ExportDef {
+            item: item.clone(),
+            core_name,
+            children: HashMap::new(),
+        });
+
+        if let ComponentItem::ComponentInstance(instance) = item {
+            let parent = defs[index].core_name.clone();
+            defs[index].children = add_exports(defs, &instance.0, Some(&parent));
+        }
+
+        indices.insert(name.clone(), index);
+    }
+
+    indices
+}
+
+/// Reads the names of the imports and exports of the outer component, in declaration order.
+fn root_names(bytes: &[u8]) -> Result<(Vec<String>, Vec<String>)> {
+    let mut imports = Vec::new();
+    let mut exports = Vec::new();
+    let mut depth = 0usize;
+
+    for payload in Parser::new(0).parse_all(bytes) {
+        match payload? {
+            Payload::ModuleSection { .. } | Payload::ComponentSection { .. } => depth += 1,
+            Payload::End(_) => depth = depth.saturating_sub(1),
+            Payload::ComponentImportSection(section) if depth == 0 => {
+                for import in section {
+                    imports.push(import?.name.0.to_owned());
+                }
+            }
+            Payload::ComponentExportSection(section) if depth == 0 => {
+                for export in section {
+                    exports.push(export?.name.0.to_owned());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok((imports, exports))
+}
+
+struct TypeDecoder<'a> {
+    engine: &'a Engine,
+    types: &'a Types,
+    /// Already decoded types, so that types used in many places share their data
+    defined: HashMap<ComponentDefinedTypeId, Type>,
+    resources: HashMap<ResourceId, ResourceType>,
+}
+
+impl TypeDecoder<'_> {
+    /// Decodes the type of an import or export, values are not supported, same as in wasmtime.
+    fn item(&mut self, ty: &ComponentEntityType) -> Option<ComponentItem> {
+        let item = match ty {
+            ComponentEntityType::Module(id) => ComponentItem::Module(self.module(*id)),
+            ComponentEntityType::Func(id) => ComponentItem::ComponentFunc(self.func(*id)),
+            ComponentEntityType::Value(_) => return None,
+            ComponentEntityType::Type { created, .. } => match created {
+                ComponentAnyTypeId::Resource(id) => {
+                    ComponentItem::Resource(self.resource(id.resource()))
+                }
+                ComponentAnyTypeId::Defined(id) => ComponentItem::Type(self.defined(*id)),
+                ComponentAnyTypeId::Func(id) => ComponentItem::ComponentFunc(self.func(*id)),
+                ComponentAnyTypeId::Instance(id) => {
+                    ComponentItem::ComponentInstance(self.instance(*id))
+                }
+                ComponentAnyTypeId::Component(id) => ComponentItem::Component(self.component(*id)),
+            },
+            ComponentEntityType::Instance(id) => {
+                ComponentItem::ComponentInstance(self.instance(*id))
+            }
+            ComponentEntityType::Component(id) => ComponentItem::Component(self.component(*id)),
+        };
+        Some(item)
+    }
+
+    fn items<'b>(
+        &mut self,
+        items: impl IntoIterator<Item = (&'b String, &'b ComponentEntityType)>,
+    ) -> Vec<(String, ComponentItem)> {
+        items
+            .into_iter()
+            .filter_map(|(name, ty)| Some((name.clone(), self.item(ty)?)))
+            .collect()
+    }
+
+    fn component(&mut self, id: ComponentTypeId) -> Component {
+        let ty = &self.types[id];
+        let imports = self.items(&ty.imports);
+        let exports = self.items(&ty.exports);
+        Component(Rc::new(ComponentItems { imports, exports }))
+    }
+
+    fn instance(&mut self, id: ComponentInstanceTypeId) -> ComponentInstance {
+        ComponentInstance(self.items(&self.types[id].exports).into())
+    }
+
+    fn module(&mut self, id: ComponentCoreModuleTypeId) -> Module {
+        let ty = &self.types[id];
+
+        let imports = ty
+            .imports
+            .iter()
+            .filter_map(|(name, ty)| Some((name.clone(), self.extern_type(ty)?)))
+            .collect();
+        let exports = ty
+            .exports
+            .iter()
+            .filter_map(|(name, ty)| Some((name.clone(), self.extern_type(ty)?)))
+            .collect();
+
+        Module(Rc::new(ModuleItems { imports, exports }))
+    }
+
+    /// Converts the type of a core import or export, tags are not supported.
+    fn extern_type(&self, ty: &EntityType) -> Option<ExternType> {
+        let ty = match ty {
+            EntityType::Func(id) => ExternType::Func(self.core_func(*id)),
+            EntityType::Table(table) => ExternType::Table(crate::TableType::new(
+                ref_type(&table.element_type),
+                table.initial,
+                table.maximum,
+            )),
+            EntityType::Memory(memory) => {
+                // Same flags as in the limits of a memory in the binary format
+                let flags = (u8::from(memory.memory64) << 2) | (u8::from(memory.shared) << 1);
+                ExternType::Memory(MemoryType::from_limits(
+                    memory.initial,
+                    memory.maximum,
+                    flags,
+                ))
+            }
+            EntityType::Global(global) => {
+                let mutability = if global.mutable {
+                    Mutability::Var
+                } else {
+                    Mutability::Const
+                };
+                ExternType::Global(GlobalType::new(val_type(&global.content_type), mutability))
+            }
+            EntityType::Tag(_) => return None,
+        };
+        Some(ty)
+    }
+
+    fn core_func(&self, id: CoreTypeId) -> FuncType {
+        let ty = self.types[id].unwrap_func();
+        FuncType::new(
+            self.engine,
+            ty.params().iter().map(val_type),
+            ty.results().iter().map(val_type),
+        )
+    }
+
+    fn func(&mut self, id: ComponentFuncTypeId) -> ComponentFunc {
+        let ty = &self.types[id];
+
+        let params = ty.params.iter().map(|(_, ty)| self.val(ty)).collect();
+        let results = ty.results.iter().map(|(_, ty)| self.val(ty)).collect();
+
+        ComponentFunc(Rc::new(FuncItems { params, results }))
+    }
+
+    fn val(&mut self, ty: &ComponentValType) -> Type {
+        match ty {
+            ComponentValType::Primitive(ty) => primitive(ty),
+            ComponentValType::Type(id) => self.defined(*id),
+        }
+    }
+
+    fn defined(&mut self, id: ComponentDefinedTypeId) -> Type {
+        if let Some(ty) = self.defined.get(&id) {
+            return ty.clone();
+        }
+
+        let ty = match &self.types[id] {
+            ComponentDefinedType::Primitive(ty) => primitive(ty),
+            ComponentDefinedType::Record(record) => Type::Record(Record(
+                record
+                    .fields
+                    .iter()
+                    .map(|(name, ty)| (name.to_string(), self.val(ty)))
+                    .collect(),
+            )),
+            ComponentDefinedType::Variant(variant) => Type::Variant(Variant(
+                variant
+                    .cases
+                    .iter()
+                    .map(|(name, case)| (name.to_string(), case.ty.map(|ty| self.val(&ty))))
+                    .collect(),
+            )),
+            ComponentDefinedType::List(ty) => Type::List(List(Rc::new(self.val(ty)))),
+            ComponentDefinedType::Tuple(tuple) => {
+                Type::Tuple(Tuple(tuple.types.iter().map(|ty| self.val(ty)).collect()))
+            }
+            ComponentDefinedType::Flags(names) => {
+                Type::Flags(Flags(names.iter().map(|name| name.to_string()).collect()))
+            }
+            ComponentDefinedType::Enum(names) => {
+                Type::Enum(Enum(names.iter().map(|name| name.to_string()).collect()))
+            }
+            ComponentDefinedType::Option(ty) => Type::Option(OptionType(Rc::new(self.val(ty)))),
+            ComponentDefinedType::Result { ok, err } => {
+                let ok = ok.map(|ty| self.val(&ty));
+                let err = err.map(|ty| self.val(&ty));
+                Type::Result(ResultType(Rc::new((ok, err))))
+            }
+            ComponentDefinedType::Own(id) => Type::Own(self.resource(id.resource())),
+            ComponentDefinedType::Borrow(id) => Type::Borrow(self.resource(id.resource())),
+        };
+
+        self.defined.insert(id, ty.clone());
+        ty
+    }
+
+    fn resource(&mut self, id: ResourceId) -> ResourceType {
+        *self
+            .resources
+            .entry(id)
+            .or_insert_with(ResourceType::unique)
+    }
+}
+
+fn primitive(ty: &PrimitiveValType) -> Type {
+    match ty {
+        PrimitiveValType::Bool => Type::Bool,
+        PrimitiveValType::S8 => Type::S8,
+        PrimitiveValType::U8 => Type::U8,
+        PrimitiveValType::S16 => Type::S16,
+        PrimitiveValType::U16 => Type::U16,
+        PrimitiveValType::S32 => Type::S32,
+        PrimitiveValType::U32 => Type::U32,
+        PrimitiveValType::S64 => Type::S64,
+        PrimitiveValType::U64 => Type::U64,
+        PrimitiveValType::F32 => Type::Float32,
+        PrimitiveValType::F64 => Type::Float64,
+        PrimitiveValType::Char => Type::Char,
+        PrimitiveValType::String => Type::String,
+    }
+}
+
+fn val_type(ty: &wasmparser::ValType) -> crate::ValType {
+    match ty {
+        wasmparser::ValType::I32 => crate::ValType::I32,
+        wasmparser::ValType::I64 => crate::ValType::I64,
+        wasmparser::ValType::F32 => crate::ValType::F32,
+        wasmparser::ValType::F64 => crate::ValType::F64,
+        wasmparser::ValType::V128 => crate::ValType::V128,
+        wasmparser::ValType::Ref(ty) => crate::ValType::Ref(ref_type(ty)),
+    }
+}
+
+/// Only `funcref` and `externref` are supported on the web, other references are treated as `externref`.
+fn ref_type(ty: &wasmparser::RefType) -> crate::RefType {
+    if ty.is_func_ref() {
+        crate::RefType::FUNCREF
+    } else {
+        crate::RefType::EXTERNREF
+    }
+}
@@ -1,4 +1,4 @@
-use std::{cell::Cell, marker::PhantomData};
+use std::{cell::RefCell, marker::PhantomData, ops::Deref};
 
 use anyhow::Context;
 use js_sys::Array;
@@ -12,9 +12,47 @@ use crate::{
 
 use super::Func;
 
+/// Maximum number of arguments passed directly in an arguments array
+const MAX_JS_ARGS: usize = 16;
+
+thread_local! {
+    /// Arguments arrays that are not used by a call in progress
+    static ARGS_ARRAYS: RefCell<Vec<Array>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Arguments array of a single call, returned to [`ARGS_ARRAYS`] when dropped.
+///
+/// Each call has its own array, so that an exported function called
+/// from a host import does not overwrite the arguments of the outer call.
+struct ArgsArray(Array);
+
+impl ArgsArray {
+    fn take() -> Self {
+        let array = ARGS_ARRAYS
+            .with(|arrays| arrays.borrow_mut().pop())
+            .unwrap_or_else(|| Array::new_with_length(MAX_JS_ARGS as u32));
+        Self(array)
+    }
+}
+
+impl Deref for ArgsArray {
+    type Target = Array;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Drop for ArgsArray {
+    fn drop(&mut self) {
+        let array = self.0.clone();
+        ARGS_ARRAYS.with(|arrays| arrays.borrow_mut().push(array));
+    }
+}
+
 pub struct TypedFunc<Params, Return> {
     func: Func,
-    post_return_arg: Cell<JsValue>,
+    auto_post_return: bool,
     _phantom: PhantomData<dyn Fn(Params) -> Return>,
 }
 
@@ -22,11 +60,24 @@ impl<Params, Return> TypedFunc<Params, Return> {
     pub fn new(func: Func) -> Self {
         Self {
             func,
-            post_return_arg: Cell::new(JsValue::UNDEFINED),
+            auto_post_return: false,
             _phantom: PhantomData,
         }
     }
 
+    /// Configures whether [`Self::call`] invokes the post-return function by itself,
+    /// right after the results have been lifted. Disabled by default.
+    ///
+    /// The results are copied out of the guest's memory before that, so they stay valid.
+    /// When enabled, [`Self::post_return`] does nothing, so code that calls it
+    /// the same way as in wasmtime keeps working.
+    ///
+    /// This is only available on the web.
+    pub fn auto_post_return(mut self, enable: bool) -> Self {
+        self.auto_post_return = enable;
+        self
+    }
+
     /// # Safety
     /// This function is 100% safe, it just needs to match wasmtime's API.
     pub unsafe fn new_unchecked(func: Func) -> Self {
@@ -37,27 +88,28 @@ impl<Params, Return> TypedFunc<Params, Return> {
         &self.func
     }
 
-    pub fn call(&self, _store: impl AsContextMut, params: Params) -> Result<Return>
+    pub fn call(&self, mut store: impl AsContextMut, params: Params) -> Result<Return>
     where
         Params: Lower,
         Return: Lift,
     {
-        thread_local! {
-            static ARGS_ARRAY: Array = Array::new_with_length(16);
-        }
+        // Releases the store's data if called from a host function
+        store.as_context_mut();
+
+        let entered = self.func.enter()?;
 
         let function = &self.func.function;
         let memory = &self.func.memory;
 
-        let result_js = if Params::NUM_ARGS <= 16 {
-            ARGS_ARRAY.with(|args_array| {
-                let mut args = JsArgsWriter::new(args_array);
-                params.to_js_args(&mut args, &memory)?;
+        let result_js = if Params::NUM_ARGS <= MAX_JS_ARGS {
+            let args_array = ArgsArray::take();
+            let mut args = JsArgsWriter::new(&args_array);
+            params.to_js_args(&mut args, &memory)?;
 
-                function
-                    .apply(&JsValue::UNDEFINED, args_array)
-                    .map_err(map_js_error("Error inside exported function"))
-            })?
+            function
+                .apply(&JsValue::UNDEFINED, &args_array)
+                .map_err(map_js_error("Error inside exported function"))
+                .inspect_err(|error| entered.poison_on_trap(error))?
         } else {
             let mut buffer = memory.allocate(Params::ALIGNMENT, Params::BYTE_SIZE)?;
             params.write_to(&mut buffer, memory)?;
@@ -65,13 +117,18 @@ impl<Params, Return> TypedFunc<Params, Return> {
 
             function
                 .call1(&JsValue::UNDEFINED, &addr.into())
-                .map_err(map_js_error("Error inside exported function"))?
+                .map_err(map_js_error("Error inside exported function"))
+                .inspect_err(|error| entered.poison_on_trap(error))?
         };
 
         let result = Return::from_js_return(&result_js, &self.func.memory)
             .context("Cannot cast return type to correct ABI type")?;
 
-        self.post_return_arg.set(result_js);
+        self.func.leave(entered, result_js);
+
+        if self.auto_post_return {
+            self.func.post_return(&mut store)?;
+        }
 
         Ok(result)
     }
@@ -84,15 +141,12 @@ impl<Params, Return> TypedFunc<Params, Return> {
         self.call(store, params)
     }
 
-    pub fn post_return(&self, _store: impl AsContextMut) -> Result<()> {
-        if let Some(func) = &self.func.post_return {
-            func.call1(
-                &JsValue::UNDEFINED,
-                &self.post_return_arg.replace(JsValue::UNDEFINED),
-            )
-            .map_err(map_js_error("Call post_return"))?;
+    /// Same as [`Func::post_return`], does nothing if [`Self::auto_post_return`] is enabled.
+    pub fn post_return(&self, store: impl AsContextMut) -> Result<()> {
+        if self.auto_post_return {
+            return Ok(());
         }
-        Ok(())
+        self.func.post_return(store)
     }
 
     pub async fn post_return_async(&self, store: impl AsContextMut) -> Result<()> {
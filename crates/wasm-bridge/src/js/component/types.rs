@@ -0,0 +1,365 @@
+//! Types of component model values and functions, mirrors wasmtime's `component::types`.
+//!
+//! On the web, the types are decoded from the component binary when it is loaded.
+
+use std::rc::Rc;
+
+use crate::{Engine, ExternType, FuncType};
+
+pub use super::ResourceType;
+
+/// A `list` interface type
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct List(pub(crate) Rc<Type>);
+
+impl List {
+    /// Retreive the element type of this `list`.
+    pub fn ty(&self) -> Type {
+        (*self.0).clone()
+    }
+}
+
+/// A field declaration belonging to a `record`
+#[derive(Debug)]
+pub struct Field<'a> {
+    /// The name of the field
+    pub name: &'a str,
+    /// The type of the field
+    pub ty: Type,
+}
+
+/// A `record` interface type
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Record(pub(crate) Rc<[(String, Type)]>);
+
+impl Record {
+    /// Retrieve the fields of this `record` in declaration order.
+    pub fn fields(&self) -> impl ExactSizeIterator<Item = Field<'_>> {
+        self.0.iter().map(|(name, ty)| Field {
+            name,
+            ty: ty.clone(),
+        })
+    }
+}
+
+/// A `tuple` interface type
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Tuple(pub(crate) Rc<[Type]>);
+
+impl Tuple {
+    /// Retrieve the types of the fields of this `tuple` in declaration order.
+    pub fn types(&self) -> impl ExactSizeIterator<Item = Type> + '_ {
+        self.0.iter().cloned()
+    }
+}
+
+/// A case declaration belonging to a `variant`
+pub struct Case<'a> {
+    /// The name of the case
+    pub name: &'a str,
+    /// The optional payload type of the case
+    pub ty: Option<Type>,
+}
+
+/// A `variant` interface type
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Variant(pub(crate) Rc<[(String, Option<Type>)]>);
+
+impl Variant {
+    /// Retrieve the cases of this `variant` in declaration order.
+    pub fn cases(&self) -> impl ExactSizeIterator<Item = Case<'_>> {
+        self.0.iter().map(|(name, ty)| Case {
+            name,
+            ty: ty.clone(),
+        })
+    }
+}
+
+/// An `enum` interface type
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Enum(pub(crate) Rc<[String]>);
+
+impl Enum {
+    /// Retrieve the names of the cases of this `enum` in declaration order.
+    pub fn names(&self) -> impl ExactSizeIterator<Item = &str> {
+        self.0.iter().map(String::as_str)
+    }
+}
+
+/// An `option` interface type
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OptionType(pub(crate) Rc<Type>);
+
+impl OptionType {
+    /// Retrieve the type parameter for this `option`.
+    pub fn ty(&self) -> Type {
+        (*self.0).clone()
+    }
+}
+
+/// An `expected` interface type
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResultType(pub(crate) Rc<(Option<Type>, Option<Type>)>);
+
+impl ResultType {
+    /// Retrieve the `ok` type parameter for this `option`.
+    pub fn ok(&self) -> Option<Type> {
+        self.0 .0.clone()
+    }
+
+    /// Retrieve the `err` type parameter for this `option`.
+    pub fn err(&self) -> Option<Type> {
+        self.0 .1.clone()
+    }
+}
+
+/// A `flags` interface type
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Flags(pub(crate) Rc<[String]>);
+
+impl Flags {
+    /// Retrieve the names of the flags of this `flags` type in declaration order.
+    pub fn names(&self) -> impl ExactSizeIterator<Item = &str> {
+        self.0.iter().map(String::as_str)
+    }
+}
+
+/// Represents a component model interface type
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[allow(missing_docs)]
+pub enum Type {
+    Bool,
+    S8,
+    U8,
+    S16,
+    U16,
+    S32,
+    U32,
+    S64,
+    U64,
+    Float32,
+    Float64,
+    Char,
+    String,
+    List(List),
+    Record(Record),
+    Tuple(Tuple),
+    Variant(Variant),
+    Enum(Enum),
+    Option(OptionType),
+    Result(ResultType),
+    Flags(Flags),
+    Own(ResourceType),
+    Borrow(ResourceType),
+}
+
+macro_rules! unwrap_type {
+    ($(($name: ident, $variant: ident, $ty: ty, $desc: literal)),*) => {
+        impl Type {
+            $(
+                #[doc = concat!("Retrieve the inner [`", stringify!($ty), "`] of a [`Type::", stringify!($variant), "`].")]
+                ///
+                /// # Panics
+                ///
+                #[doc = concat!("This will panic if `self` is not a [`Type::", stringify!($variant), "`].")]
+                pub fn $name(&self) -> &$ty {
+                    if let Type::$variant(handle) = self {
+                        handle
+                    } else {
+                        panic!(concat!("attempted to unwrap a {} as ", $desc), self.desc())
+                    }
+                }
+            )*
+        }
+    };
+}
+
+unwrap_type!(
+    (unwrap_list, List, List, "a list"),
+    (unwrap_record, Record, Record, "a record"),
+    (unwrap_tuple, Tuple, Tuple, "a tuple"),
+    (unwrap_variant, Variant, Variant, "a variant"),
+    (unwrap_enum, Enum, Enum, "an enum"),
+    (unwrap_option, Option, OptionType, "an option"),
+    (unwrap_result, Result, ResultType, "a result"),
+    (unwrap_flags, Flags, Flags, "a flags"),
+    (unwrap_own, Own, ResourceType, "an own"),
+    (unwrap_borrow, Borrow, ResourceType, "a borrow")
+);
+
+impl Type {
+    pub(crate) fn desc(&self) -> &'static str {
+        match self {
+            Type::Bool => "bool",
+            Type::S8 => "s8",
+            Type::U8 => "u8",
+            Type::S16 => "s16",
+            Type::U16 => "u16",
+            Type::S32 => "s32",
+            Type::U32 => "u32",
+            Type::S64 => "s64",
+            Type::U64 => "u64",
+            Type::Float32 => "float32",
+            Type::Float64 => "float64",
+            Type::Char => "char",
+            Type::String => "string",
+            Type::List(_) => "list",
+            Type::Record(_) => "record",
+            Type::Tuple(_) => "tuple",
+            Type::Variant(_) => "variant",
+            Type::Enum(_) => "enum",
+            Type::Option(_) => "option",
+            Type::Result(_) => "result",
+            Type::Flags(_) => "flags",
+            Type::Own(_) => "own",
+            Type::Borrow(_) => "borrow",
+        }
+    }
+}
+
+/// Component function type
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ComponentFunc(pub(crate) Rc<FuncItems>);
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct FuncItems {
+    pub(crate) params: Box<[Type]>,
+    pub(crate) results: Box<[Type]>,
+}
+
+impl ComponentFunc {
+    /// Iterates over types of function parameters
+    pub fn params(&self) -> impl ExactSizeIterator<Item = Type> + '_ {
+        self.0.params.iter().cloned()
+    }
+
+    /// Iterates over types of function results
+    pub fn results(&self) -> impl ExactSizeIterator<Item = Type> + '_ {
+        self.0.results.iter().cloned()
+    }
+}
+
+/// Core module type
+#[derive(Clone, Debug)]
+pub struct Module(pub(crate) Rc<ModuleItems>);
+
+#[derive(Debug, Default)]
+pub(crate) struct ModuleItems {
+    pub(crate) imports: Vec<((String, String), ExternType)>,
+    pub(crate) exports: Vec<(String, ExternType)>,
+}
+
+impl Module {
+    /// Iterates over imports of the module
+    pub fn imports<'a>(
+        &'a self,
+        _engine: &'a Engine,
+    ) -> impl ExactSizeIterator<Item = ((&'a str, &'a str), ExternType)> + 'a {
+        self.0
+            .imports
+            .iter()
+            .map(|((module, name), ty)| ((module.as_str(), name.as_str()), ty.clone()))
+    }
+
+    /// Iterates over exports of the module
+    pub fn exports<'a>(
+        &'a self,
+        _engine: &'a Engine,
+    ) -> impl ExactSizeIterator<Item = (&'a str, ExternType)> + 'a {
+        self.0
+            .exports
+            .iter()
+            .map(|(name, ty)| (name.as_str(), ty.clone()))
+    }
+}
+
+/// Component type
+#[derive(Clone, Debug)]
+pub struct Component(pub(crate) Rc<ComponentItems>);
+
+#[derive(Debug, Default)]
+pub(crate) struct ComponentItems {
+    pub(crate) imports: Vec<(String, ComponentItem)>,
+    pub(crate) exports: Vec<(String, ComponentItem)>,
+}
+
+impl Component {
+    /// Returns import associated with `name`, if such exists in the component
+    pub fn get_import(&self, _engine: &Engine, name: &str) -> Option<ComponentItem> {
+        find_item(&self.0.imports, name)
+    }
+
+    /// Iterates over imports of the component
+    pub fn imports<'a>(
+        &'a self,
+        _engine: &'a Engine,
+    ) -> impl ExactSizeIterator<Item = (&'a str, ComponentItem)> + 'a {
+        iter_items(&self.0.imports)
+    }
+
+    /// Returns export associated with `name`, if such exists in the component
+    pub fn get_export(&self, _engine: &Engine, name: &str) -> Option<ComponentItem> {
+        find_item(&self.0.exports, name)
+    }
+
+    /// Iterates over exports of the component
+    pub fn exports<'a>(
+        &'a self,
+        _engine: &'a Engine,
+    ) -> impl ExactSizeIterator<Item = (&'a str, ComponentItem)> + 'a {
+        iter_items(&self.0.exports)
+    }
+}
+
+/// Component instance type
+#[derive(Clone, Debug)]
+pub struct ComponentInstance(pub(crate) Rc<[(String, ComponentItem)]>);
+
+impl ComponentInstance {
+    /// Returns export associated with `name`, if such exists in the component instance
+    pub fn get_export(&self, _engine: &Engine, name: &str) -> Option<ComponentItem> {
+        find_item(&self.0, name)
+    }
+
+    /// Iterates over exports of the component instance
+    pub fn exports<'a>(
+        &'a self,
+        _engine: &'a Engine,
+    ) -> impl ExactSizeIterator<Item = (&'a str, ComponentItem)> + 'a {
+        iter_items(&self.0)
+    }
+}
+
+fn find_item(items: &[(String, ComponentItem)], name: &str) -> Option<ComponentItem> {
+    items
+        .iter()
+        .find(|(item_name, _)| item_name == name)
+        .map(|(_, item)| item.clone())
+}
+
+fn iter_items(
+    items: &[(String, ComponentItem)],
+) -> impl ExactSizeIterator<Item = (&str, ComponentItem)> {
+    items
+        .iter()
+        .map(|(name, item)| (name.as_str(), item.clone()))
+}
+
+/// Type of an item contained within the component
+#[derive(Clone, Debug)]
+pub enum ComponentItem {
+    /// Component function item
+    ComponentFunc(ComponentFunc),
+    /// Core function item
+    CoreFunc(FuncType),
+    /// Core module item
+    Module(Module),
+    /// Component item
+    Component(Component),
+    /// Component instance item
+    ComponentInstance(ComponentInstance),
+    /// Interface type item
+    Type(Type),
+    /// Resource item
+    Resource(ResourceType),
+}
@@ -1,9 +1,18 @@
-#[derive(Debug, Default)]
-pub struct Config {}
+use std::rc::Rc;
+
+use crate::*;
+
+#[derive(Clone, Debug, Default)]
+pub struct Config {
+    pub(crate) consume_fuel: bool,
+    pub(crate) epoch_interruption: bool,
+    pub(crate) resource_limits: bool,
+    pub(crate) module_cache: Option<SharedModuleCache>,
+}
 
 impl Config {
     pub fn new() -> Self {
-        Self {}
+        Self::default()
     }
 
     #[cfg(feature = "component-model")]
@@ -15,4 +24,48 @@ impl Config {
     pub fn async_support(&mut self, _: bool) -> &mut Self {
         self
     }
+
+    /// Configures whether execution of WebAssembly will consume fuel.
+    ///
+    /// On the web, core modules compiled with this engine are instrumented to
+    /// count the executed instructions, which makes them larger and slower.
+    /// Components are not instrumented.
+    pub fn consume_fuel(&mut self, enable: bool) -> &mut Self {
+        self.consume_fuel = enable;
+        self
+    }
+
+    /// Enables epoch-based interruption of WebAssembly code.
+    ///
+    /// On the web, core modules compiled with this engine are instrumented to
+    /// compare the engine's epoch with the store's deadline at function entries and loop headers.
+    /// Components are not instrumented.
+    ///
+    /// Wasm code runs on the same thread as the host, so the epoch can only be incremented
+    /// from host functions called by the guest, or between calls into the guest.
+    /// Use [`Config::consume_fuel`] to interrupt long computations that never call the host.
+    pub fn epoch_interruption(&mut self, enable: bool) -> &mut Self {
+        self.epoch_interruption = enable;
+        self
+    }
+
+    /// Lets the store's [`ResourceLimiter`] limit the memories and tables defined by core modules,
+    /// only available on the web.
+    ///
+    /// Core modules compiled with this engine are instrumented to import the memories and tables
+    /// they define, so that the store can create and count them. Without this setting,
+    /// only the memories and tables created by the host are limited.
+    pub fn resource_limits(&mut self, enable: bool) -> &mut Self {
+        self.resource_limits = enable;
+        self
+    }
+
+    /// Sets the cache of compiled modules, only available on the web.
+    ///
+    /// Modules and components compiled with this engine are looked up in the cache first,
+    /// so loading the same bytes again skips compilation. See [`ModuleCache`].
+    pub fn module_cache(&mut self, cache: impl ModuleCache + 'static) -> &mut Self {
+        self.module_cache = Some(SharedModuleCache(Rc::new(cache)));
+        self
+    }
 }
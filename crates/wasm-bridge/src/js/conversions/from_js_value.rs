@@ -128,6 +128,14 @@ impl FromJsValue for Val {
         } else if value.is_bigint() {
             // TODO: u64 is used, because it's more "robust" ... make i64 robust as well instead?
             Ok(Val::I64(u64::from_js_value(value)? as _))
+        } else if value.is_function() {
+            Ok(Val::FuncRef(Some(Func::from_exported(
+                value.clone().into(),
+                Default::default(),
+                None,
+            ))))
+        } else if value.is_object() {
+            Ok(Val::ExternRef(Some(ExternRef::from_js_value(value)?)))
         } else {
             Err(map_js_error("Unsupported 'Val' value")(value))
         }
@@ -8,7 +8,7 @@ use wasm_bindgen::{
 
 use crate::*;
 
-pub(crate) type MakeClosure<T> = Box<dyn Fn(DataHandle<T>) -> (JsValue, DropHandle)>;
+pub(crate) type MakeClosure<T> = Box<dyn Fn(DataHandle<T>, LazyExports) -> (JsValue, DropHandle)>;
 
 pub trait IntoMakeClosure<T, Params, Results> {
     fn into_make_closure(self) -> MakeClosure<T>;
@@ -24,8 +24,8 @@ where
     fn into_make_closure(self) -> MakeClosure<T> {
         let self_rc = Rc::new(self);
 
-        let make_closure = move |handle: DataHandle<T>| {
-            let caller = Caller::new(handle);
+        let make_closure = move |handle: DataHandle<T>, exports: LazyExports| {
+            let caller = Caller::new(handle, exports);
             let self_clone = self_rc.clone();
 
             let closure = Closure::<dyn Fn() -> Result<R::ReturnAbi, JsValue>>::new(move || {
@@ -51,8 +51,8 @@ macro_rules! into_make_closure_single {
             fn into_make_closure(self) -> MakeClosure<T> {
                 let self_rc = Rc::new(self);
 
-                let make_closure = move |handle: DataHandle<T>| {
-                    let caller = Caller::new(handle);
+                let make_closure = move |handle: DataHandle<T>, exports: LazyExports| {
+                    let caller = Caller::new(handle, exports);
                     let self_clone = self_rc.clone();
 
                     let closure = Closure::<dyn Fn($ty) -> Result<R::ReturnAbi, JsValue>>::new(
@@ -87,8 +87,8 @@ where
     fn into_make_closure(self) -> MakeClosure<T> {
         let self_rc = Rc::new(self);
 
-        let make_closure = move |handle: DataHandle<T>| {
-            let caller = Caller::new(handle);
+        let make_closure = move |handle: DataHandle<T>, exports: LazyExports| {
+            let caller = Caller::new(handle, exports);
             let self_clone = self_rc.clone();
 
             let closure = Closure::<dyn Fn(P0, P1) -> Result<R::ReturnAbi, JsValue>>::new(
@@ -115,8 +115,8 @@ macro_rules! into_make_closure_many {
             fn into_make_closure(self) -> MakeClosure<T> {
                 let self_rc = Rc::new(self);
 
-                let make_closure = move |handle: DataHandle<T>| {
-                    let caller = Caller::new(handle);
+                let make_closure = move |handle: DataHandle<T>, exports: LazyExports| {
+                    let caller = Caller::new(handle, exports);
                     let self_clone = self_rc.clone();
 
                     let closure =
@@ -1,6 +1,7 @@
 mod from_js_value;
 mod into_closure;
 mod to_js_value;
+mod wasm_ty;
 
 #[cfg(test)]
 mod from_js_value_tests;
@@ -8,3 +9,4 @@ mod from_js_value_tests;
 pub use from_js_value::*;
 pub use into_closure::*;
 pub use to_js_value::*;
+pub use wasm_ty::*;
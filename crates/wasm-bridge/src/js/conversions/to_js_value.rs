@@ -83,6 +83,11 @@ impl ToJsValue for Val {
             Val::I64(val) => val.to_js_value(),
             Val::F32(bits) => f32::from_bits(*bits).to_js_value(),
             Val::F64(bits) => f64::from_bits(*bits).to_js_value(),
+            // JS throws a TypeError when a v128 is passed to wasm, calls check for this first
+            Val::V128(_) => JsValue::UNDEFINED,
+            Val::FuncRef(Some(func)) => func.function.clone().into(),
+            Val::ExternRef(Some(externref)) => externref.to_js_value(),
+            Val::FuncRef(None) | Val::ExternRef(None) => JsValue::NULL,
         }
     }
 
@@ -0,0 +1,70 @@
+use crate::ValType;
+
+/// Rust type that can be passed to or returned from a typed wasm function.
+pub trait WasmTy {
+    fn valtype() -> ValType;
+}
+
+macro_rules! wasm_ty {
+    ($ty: ty, $val_type: expr) => {
+        impl WasmTy for $ty {
+            fn valtype() -> ValType {
+                $val_type
+            }
+        }
+    };
+}
+
+wasm_ty!(i32, ValType::I32);
+wasm_ty!(u32, ValType::I32);
+wasm_ty!(i64, ValType::I64);
+wasm_ty!(u64, ValType::I64);
+wasm_ty!(f32, ValType::F32);
+wasm_ty!(f64, ValType::F64);
+
+/// Parameters or results of a typed wasm function, used to check the function's type.
+pub trait WasmTyList {
+    fn valtypes() -> Vec<ValType>;
+}
+
+impl WasmTyList for () {
+    fn valtypes() -> Vec<ValType> {
+        vec![]
+    }
+}
+
+macro_rules! wasm_ty_list_single {
+    ($ty: ty) => {
+        impl WasmTyList for $ty {
+            fn valtypes() -> Vec<ValType> {
+                vec![<$ty>::valtype()]
+            }
+        }
+    };
+}
+
+wasm_ty_list_single!(i32);
+wasm_ty_list_single!(u32);
+wasm_ty_list_single!(i64);
+wasm_ty_list_single!(u64);
+wasm_ty_list_single!(f32);
+wasm_ty_list_single!(f64);
+
+macro_rules! wasm_ty_list_many {
+    ($($name: ident),*) => {
+        impl<$($name: WasmTy),*> WasmTyList for ($($name, )*) {
+            fn valtypes() -> Vec<ValType> {
+                vec![$($name::valtype()),*]
+            }
+        }
+    };
+}
+
+wasm_ty_list_many!(T0);
+wasm_ty_list_many!(T0, T1);
+wasm_ty_list_many!(T0, T1, T2);
+wasm_ty_list_many!(T0, T1, T2, T3);
+wasm_ty_list_many!(T0, T1, T2, T3, T4);
+wasm_ty_list_many!(T0, T1, T2, T3, T4, T5);
+wasm_ty_list_many!(T0, T1, T2, T3, T4, T5, T6);
+wasm_ty_list_many!(T0, T1, T2, T3, T4, T5, T6, T7);
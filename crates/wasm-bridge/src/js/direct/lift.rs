@@ -22,29 +22,33 @@ pub trait Lift: SizeDescription + Sized {
         let addr = u32::from_js_value(value)? as usize;
         if Self::BYTE_SIZE <= 16 {
             let mut data = [0u8; 16];
-            memory.read_to_slice(addr, &mut data[..Self::BYTE_SIZE]);
+            memory.read_to_slice(addr, &mut data[..Self::BYTE_SIZE])?;
             Self::read_from(&data[..Self::BYTE_SIZE], memory)
         } else {
-            let data = memory.read_to_vec(addr, Self::BYTE_SIZE);
+            let data = memory.read_to_vec(addr, Self::BYTE_SIZE)?;
             Self::read_from(&data, memory)
         }
     }
 }
 
 pub trait ReadableMemory {
-    fn read_to_slice(&self, addr: usize, target: &mut [u8]);
+    fn read_to_slice(&self, addr: usize, target: &mut [u8]) -> Result<()>;
 
-    fn read_to_vec(&self, addr: usize, len: usize) -> Vec<u8> {
+    fn read_to_vec(&self, addr: usize, len: usize) -> Result<Vec<u8>> {
         let mut vec = vec![0; len];
-        self.read_to_slice(addr, &mut vec);
-        vec
+        self.read_to_slice(addr, &mut vec)?;
+        Ok(vec)
     }
 }
 
 impl<M: ReadableMemory> ReadableMemory for &M {
-    fn read_to_slice(&self, addr: usize, target: &mut [u8]) {
+    fn read_to_slice(&self, addr: usize, target: &mut [u8]) -> Result<()> {
         M::read_to_slice(self, addr, target)
     }
+
+    fn read_to_vec(&self, addr: usize, len: usize) -> Result<Vec<u8>> {
+        M::read_to_vec(self, addr, len)
+    }
 }
 
 pub struct JsArgsReader {
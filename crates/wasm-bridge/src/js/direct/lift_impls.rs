@@ -85,7 +85,7 @@ impl<T: Lift> Lift for Vec<T> {
         let addr = u32::from_js_value(value)? as usize;
 
         let mut addr_and_len = [0u8; 8];
-        memory.read_to_slice(addr, &mut addr_and_len);
+        memory.read_to_slice(addr, &mut addr_and_len)?;
 
         Self::read_from(&addr_and_len, memory)
     }
@@ -121,7 +121,8 @@ fn read_vec_from<T: Lift, M: ReadableMemory>(
     memory: &M,
 ) -> Result<Vec<T>> {
     let size = T::BYTE_SIZE;
-    let data = memory.read_to_vec(addr, size * len);
+    let byte_len = size.checked_mul(len).context("List is too long")?;
+    let data = memory.read_to_vec(addr, byte_len)?;
 
     let mut result = Vec::with_capacity(len);
     for i in 0..len {
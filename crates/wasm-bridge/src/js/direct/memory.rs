@@ -64,10 +64,13 @@ impl WriteableMemory for ModuleMemory {
 }
 
 impl ReadableMemory for ModuleMemory {
-    fn read_to_slice(&self, addr: usize, target: &mut [u8]) {
-        self.memory
-            .read_impl(addr, target)
-            .expect("read bytes from memory")
+    fn read_to_slice(&self, addr: usize, target: &mut [u8]) -> Result<()> {
+        Ok(self.memory.read_impl(addr, target)?)
+    }
+
+    fn read_to_vec(&self, addr: usize, len: usize) -> Result<Vec<u8>> {
+        // Checks the bounds before allocating, the length can come from the guest
+        Ok(self.memory.read_vec_impl(addr, len)?)
     }
 }
 
@@ -105,12 +108,19 @@ impl WriteableMemory for LazyModuleMemory {
 }
 
 impl ReadableMemory for LazyModuleMemory {
-    fn read_to_slice(&self, addr: usize, target: &mut [u8]) {
+    fn read_to_slice(&self, addr: usize, target: &mut [u8]) -> Result<()> {
         self.get()
             .as_ref()
             .expect("initialized lazy memory")
             .read_to_slice(addr, target)
     }
+
+    fn read_to_vec(&self, addr: usize, len: usize) -> Result<Vec<u8>> {
+        self.get()
+            .as_ref()
+            .expect("initialized lazy memory")
+            .read_to_vec(addr, len)
+    }
 }
 
 pub struct ByteBuffer {
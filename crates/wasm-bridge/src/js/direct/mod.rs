@@ -4,8 +4,10 @@ mod lower;
 mod lower_impls;
 mod memory;
 mod size_description;
+mod typecheck;
 
 pub use lift::*;
 pub use lower::*;
 pub use memory::*;
 pub use size_description::*;
+pub use typecheck::*;
@@ -1,4 +1,11 @@
-use crate::component::{Resource, ResourceAny};
+use anyhow::bail;
+
+use crate::{
+    component::{Resource, ResourceAny, Type},
+    Result,
+};
+
+use super::*;
 
 pub trait SizeDescription {
     /// Alignment in bytes
@@ -17,6 +24,9 @@ pub trait SizeDescription {
     /// 2*n is start n-th field, 2*n + 1 is end n-th field.
     /// 2*field_count is end on the entire struct.
     fn layout() -> Self::StructLayout;
+
+    /// Checks that this type can represent values of the component model type `ty`.
+    fn typecheck(ty: &Type) -> Result<()>;
 }
 
 type SimpleStructLayout = [usize; 3];
@@ -26,7 +36,7 @@ fn simple_layout(byte_size: usize) -> SimpleStructLayout {
 }
 
 macro_rules! size_description_primitive {
-    ($ty: ty, $bytes: literal) => {
+    ($ty: ty, $bytes: literal, $component_ty: ident) => {
         impl SizeDescription for $ty {
             const ALIGNMENT: usize = $bytes;
             const BYTE_SIZE: usize = $bytes;
@@ -38,28 +48,32 @@ macro_rules! size_description_primitive {
             fn layout() -> Self::StructLayout {
                 simple_layout(Self::BYTE_SIZE)
             }
+
+            fn typecheck(ty: &Type) -> Result<()> {
+                typecheck_primitive(ty, Type::$component_ty)
+            }
         }
     };
 }
 
-size_description_primitive!(u8, 1);
-size_description_primitive!(u16, 2);
-size_description_primitive!(u32, 4);
-size_description_primitive!(u64, 8);
+size_description_primitive!(u8, 1, U8);
+size_description_primitive!(u16, 2, U16);
+size_description_primitive!(u32, 4, U32);
+size_description_primitive!(u64, 8, U64);
 
-size_description_primitive!(i8, 1);
-size_description_primitive!(i16, 2);
-size_description_primitive!(i32, 4);
-size_description_primitive!(i64, 8);
+size_description_primitive!(i8, 1, S8);
+size_description_primitive!(i16, 2, S16);
+size_description_primitive!(i32, 4, S32);
+size_description_primitive!(i64, 8, S64);
 
-size_description_primitive!(f32, 4);
-size_description_primitive!(f64, 8);
+size_description_primitive!(f32, 4, Float32);
+size_description_primitive!(f64, 8, Float64);
 
-size_description_primitive!(bool, 1);
-size_description_primitive!(char, 4);
+size_description_primitive!(bool, 1, Bool);
+size_description_primitive!(char, 4, Char);
 
 macro_rules! size_description_fat_ptr {
-    ([$($name: ident),*], $ty: ty) => {
+    ([$($name: ident),*], $ty: ty, |$arg: ident| $typecheck: expr) => {
         impl<$($name: SizeDescription),*> SizeDescription for $ty {
             const ALIGNMENT: usize = 4;
             const BYTE_SIZE: usize = 8;
@@ -71,14 +85,18 @@ macro_rules! size_description_fat_ptr {
             fn layout() -> Self::StructLayout {
                 simple_layout(Self::BYTE_SIZE)
             }
+
+            fn typecheck($arg: &Type) -> Result<()> {
+                $typecheck
+            }
         }
     };
 }
 
-size_description_fat_ptr!([T], &[T]);
-size_description_fat_ptr!([T], Vec<T>);
-size_description_fat_ptr!([], &str);
-size_description_fat_ptr!([], String);
+size_description_fat_ptr!([T], &[T], |ty| T::typecheck(&typecheck_list(ty)?));
+size_description_fat_ptr!([T], Vec<T>, |ty| T::typecheck(&typecheck_list(ty)?));
+size_description_fat_ptr!([], &str, |ty| typecheck_primitive(ty, Type::String));
+size_description_fat_ptr!([], String, |ty| typecheck_primitive(ty, Type::String));
 
 impl<T: SizeDescription> SizeDescription for &T {
     const ALIGNMENT: usize = T::ALIGNMENT;
@@ -90,6 +108,10 @@ impl<T: SizeDescription> SizeDescription for &T {
     fn layout() -> Self::StructLayout {
         T::layout()
     }
+
+    fn typecheck(ty: &Type) -> Result<()> {
+        T::typecheck(ty)
+    }
 }
 
 impl<T: SizeDescription> SizeDescription for Option<T> {
@@ -103,6 +125,13 @@ impl<T: SizeDescription> SizeDescription for Option<T> {
     fn layout() -> Self::StructLayout {
         simple_layout(Self::BYTE_SIZE)
     }
+
+    fn typecheck(ty: &Type) -> Result<()> {
+        match ty {
+            Type::Option(option) => T::typecheck(&option.ty()),
+            other => bail!("expected `option`, found `{}`", other.desc()),
+        }
+    }
 }
 
 impl<T: SizeDescription, E: SizeDescription> SizeDescription for Result<T, E> {
@@ -116,6 +145,15 @@ impl<T: SizeDescription, E: SizeDescription> SizeDescription for Result<T, E> {
     fn layout() -> Self::StructLayout {
         simple_layout(Self::BYTE_SIZE)
     }
+
+    fn typecheck(ty: &Type) -> Result<()> {
+        let Type::Result(result) = ty else {
+            bail!("expected `result`, found `{}`", ty.desc());
+        };
+
+        T::typecheck(&result.ok().unwrap_or_else(unit_type))?;
+        E::typecheck(&result.err().unwrap_or_else(unit_type))
+    }
 }
 
 impl<T> SizeDescription for Resource<T> {
@@ -129,6 +167,10 @@ impl<T> SizeDescription for Resource<T> {
     fn layout() -> Self::StructLayout {
         simple_layout(Self::BYTE_SIZE)
     }
+
+    fn typecheck(ty: &Type) -> Result<()> {
+        typecheck_resource(ty)
+    }
 }
 
 impl SizeDescription for ResourceAny {
@@ -142,6 +184,10 @@ impl SizeDescription for ResourceAny {
     fn layout() -> Self::StructLayout {
         simple_layout(Self::BYTE_SIZE)
     }
+
+    fn typecheck(ty: &Type) -> Result<()> {
+        typecheck_resource(ty)
+    }
 }
 
 impl SizeDescription for anyhow::Error {
@@ -155,6 +201,11 @@ impl SizeDescription for anyhow::Error {
     fn layout() -> Self::StructLayout {
         simple_layout(Self::BYTE_SIZE)
     }
+
+    // Stands in for a resource handle, see its `Lower` implementation
+    fn typecheck(ty: &Type) -> Result<()> {
+        typecheck_resource(ty)
+    }
 }
 
 impl SizeDescription for () {
@@ -167,6 +218,11 @@ impl SizeDescription for () {
     fn layout() -> Self::StructLayout {
         [0]
     }
+
+    fn typecheck(ty: &Type) -> Result<()> {
+        typecheck_tuple(ty, 0)?;
+        Ok(())
+    }
 }
 
 impl<T: SizeDescription> SizeDescription for (T,) {
@@ -180,6 +236,10 @@ impl<T: SizeDescription> SizeDescription for (T,) {
     fn layout() -> Self::StructLayout {
         T::layout()
     }
+
+    fn typecheck(ty: &Type) -> Result<()> {
+        T::typecheck(&typecheck_tuple(ty, 1)?[0])
+    }
 }
 
 wasm_bridge_macros::size_description_tuple!(2);
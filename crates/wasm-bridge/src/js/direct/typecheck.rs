@@ -0,0 +1,122 @@
+use std::rc::Rc;
+
+use anyhow::bail;
+
+use crate::{
+    component::types::{Tuple, Type},
+    Result,
+};
+
+/// Checks that the component type is the primitive type `expected`.
+pub fn typecheck_primitive(ty: &Type, expected: Type) -> Result<()> {
+    if *ty != expected {
+        bail!("expected `{}`, found `{}`", expected.desc(), ty.desc());
+    }
+    Ok(())
+}
+
+/// Checks that the component type is a list, returns the type of its elements.
+pub fn typecheck_list(ty: &Type) -> Result<Type> {
+    match ty {
+        Type::List(list) => Ok(list.ty()),
+        other => bail!("expected `list`, found `{}`", other.desc()),
+    }
+}
+
+/// Checks that the component type is a record with `count` fields, returns the types of the fields.
+pub fn typecheck_record(ty: &Type, count: usize) -> Result<Vec<Type>> {
+    let Type::Record(record) = ty else {
+        bail!("expected `record`, found `{}`", ty.desc());
+    };
+
+    if record.fields().len() != count {
+        bail!(
+            "expected a record with {count} fields, found {} fields",
+            record.fields().len()
+        );
+    }
+
+    Ok(record.fields().map(|field| field.ty).collect())
+}
+
+/// Checks that the component type is a tuple with `count` types, returns those types.
+pub fn typecheck_tuple(ty: &Type, count: usize) -> Result<Vec<Type>> {
+    let Type::Tuple(tuple) = ty else {
+        bail!("expected `tuple`, found `{}`", ty.desc());
+    };
+
+    if tuple.types().len() != count {
+        bail!(
+            "expected a tuple with {count} types, found {} types",
+            tuple.types().len()
+        );
+    }
+
+    Ok(tuple.types().collect())
+}
+
+/// Checks that the component type is a variant with `count` cases, returns the payload types of the cases.
+pub fn typecheck_variant(ty: &Type, count: usize) -> Result<Vec<Option<Type>>> {
+    let Type::Variant(variant) = ty else {
+        bail!("expected `variant`, found `{}`", ty.desc());
+    };
+
+    if variant.cases().len() != count {
+        bail!(
+            "expected a variant with {count} cases, found {} cases",
+            variant.cases().len()
+        );
+    }
+
+    Ok(variant.cases().map(|case| case.ty).collect())
+}
+
+/// Checks the payload of a variant case, `check` is `None` for cases without a payload.
+pub fn typecheck_case(
+    payload: &Option<Type>,
+    check: Option<fn(&Type) -> Result<()>>,
+) -> Result<()> {
+    match (payload, check) {
+        (Some(ty), Some(check)) => check(ty),
+        (None, None) => Ok(()),
+        (Some(_), None) => bail!("expected a case without a payload, found one with a payload"),
+        (None, Some(_)) => bail!("expected a case with a payload, found one without a payload"),
+    }
+}
+
+/// Checks that the component type is an enum with `count` cases.
+pub fn typecheck_enum(ty: &Type, count: usize) -> Result<()> {
+    let Type::Enum(names) = ty else {
+        bail!("expected `enum`, found `{}`", ty.desc());
+    };
+
+    if names.names().len() != count {
+        bail!(
+            "expected an enum with {count} cases, found {} cases",
+            names.names().len()
+        );
+    }
+
+    Ok(())
+}
+
+/// Checks that the component type is flags.
+pub fn typecheck_flags(ty: &Type) -> Result<()> {
+    match ty {
+        Type::Flags(_) => Ok(()),
+        other => bail!("expected `flags`, found `{}`", other.desc()),
+    }
+}
+
+/// Checks that the component type is an owned or borrowed resource handle.
+pub fn typecheck_resource(ty: &Type) -> Result<()> {
+    match ty {
+        Type::Own(_) | Type::Borrow(_) => Ok(()),
+        other => bail!("expected `own` or `borrow`, found `{}`", other.desc()),
+    }
+}
+
+/// The empty tuple, stands for a missing type, like the `ok` type of `result<_, E>`.
+pub(crate) fn unit_type() -> Type {
+    Type::Tuple(Tuple(Rc::from([])))
+}
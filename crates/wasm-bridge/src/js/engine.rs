@@ -1,10 +1,54 @@
 use crate::*;
 
 #[derive(Clone, Debug, Default)]
-pub struct Engine {}
+pub struct Engine {
+    config: Config,
+    /// Current epoch, shared by all clones and imported as a global by instrumented modules
+    epoch: Option<Global>,
+}
 
 impl Engine {
-    pub fn new(_: &Config) -> Result<Self> {
-        Ok(Self {})
+    pub fn new(config: &Config) -> Result<Self> {
+        let epoch = if config.epoch_interruption {
+            Some(Global::from_type(
+                GlobalType::new(ValType::I64, Mutability::Var),
+                Val::I64(0),
+            )?)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            config: config.clone(),
+            epoch,
+        })
+    }
+
+    pub(crate) fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Increments the epoch, interrupting wasm code in stores whose deadline was reached.
+    ///
+    /// Does nothing if epoch interruption is not enabled via [`Config::epoch_interruption`].
+    pub fn increment_epoch(&self) {
+        if let Some(epoch) = &self.epoch {
+            let next = self.current_epoch().wrapping_add(1);
+            epoch
+                .set_value(Val::I64(next as i64))
+                .expect("epoch global is mutable");
+        }
+    }
+
+    /// Returns the global with the current epoch, imported by instrumented modules.
+    pub(crate) fn epoch(&self) -> Option<&Global> {
+        self.epoch.as_ref()
+    }
+
+    pub(crate) fn current_epoch(&self) -> u64 {
+        self.epoch
+            .as_ref()
+            .and_then(|epoch| epoch.value().i64())
+            .unwrap_or_default() as u64
     }
 }
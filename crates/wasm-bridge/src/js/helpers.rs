@@ -1,29 +1,69 @@
-use std::fmt::Debug;
+use std::{cell::RefCell, fmt::Debug};
 
-use js_sys::Function;
-use wasm_bindgen::JsValue;
+use anyhow::{bail, Context};
+use js_sys::{Function, Reflect, Uint8Array};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{ReadableStream, ReadableStreamDefaultReader, Response};
 
-use crate::Error;
+use crate::{js_error_with_stack, Error, Result, Trap, WasmBacktrace};
 
 pub(crate) fn map_js_error<T: Debug + AsRef<JsValue>>(hint: &'static str) -> impl Fn(T) -> Error {
     move |value: T| {
-        if cfg!(feature = "error-logging") {
-            log_js_value_error(hint, value.as_ref());
-            anyhow::anyhow!(
-                "{}, error value: {:?}, see console.error log for detail.",
-                hint,
-                value
-            )
-        } else {
-            anyhow::anyhow!(
-                "{}, error value: {:?}, enable 'error-logging' feature to log the value to console.error.",
-                hint,
-                value
-            )
+        let error = take_host_error(value.as_ref()).unwrap_or_else(|| js_error(hint, &value));
+
+        match WasmBacktrace::from_js_error(value.as_ref()) {
+            Some(backtrace) => error.context(backtrace),
+            None => error,
         }
     }
 }
 
+fn js_error<T: Debug + AsRef<JsValue>>(hint: &'static str, value: &T) -> Error {
+    let message = if cfg!(feature = "error-logging") {
+        log_js_value_error(hint, value.as_ref());
+        format!(
+            "{}, error value: {:?}, see console.error log for detail.",
+            hint, value
+        )
+    } else {
+        format!(
+            "{}, error value: {:?}, enable 'error-logging' feature to log the value to console.error.",
+            hint, value
+        )
+    };
+
+    match Trap::from_js_error(value.as_ref()) {
+        Some(trap) => Error::new(trap).context(message),
+        None => anyhow::anyhow!(message),
+    }
+}
+
+thread_local! {
+    // The last error returned by a host function, together with the JS value thrown in its place
+    static HOST_ERROR: RefCell<Option<(JsValue, Error)>> = const { RefCell::new(None) };
+}
+
+/// Converts an error returned by a host function to a JS value to throw.
+///
+/// The original error is kept, so that it can be returned from the exported
+/// function call, just like on sys.
+pub(crate) fn host_error_to_js(error: Error) -> JsValue {
+    let js_error = js_error_with_stack(&format!("Error in imported function: {error:?}"));
+    HOST_ERROR.with(|host_error| *host_error.borrow_mut() = Some((js_error.clone(), error)));
+    js_error
+}
+
+fn take_host_error(value: &JsValue) -> Option<Error> {
+    HOST_ERROR.with(|host_error| {
+        let mut host_error = host_error.borrow_mut();
+        match host_error.as_ref() {
+            Some((js_error, _)) if js_error == value => host_error.take().map(|(_, error)| error),
+            _ => None,
+        }
+    })
+}
+
 fn log_js_value_error(name: &str, value: &JsValue) {
     let console_error: Function = js_sys::eval("console.error").unwrap().into();
 
@@ -49,7 +89,6 @@ pub fn eprintln(value: impl Debug) {
 }
 
 pub fn static_str_to_js(s: &'static str) -> &'static JsValue {
-    use std::cell::RefCell;
     use std::collections::HashMap;
 
     thread_local! {
@@ -72,3 +111,55 @@ pub fn static_str_to_js(s: &'static str) -> &'static JsValue {
         *reference
     })
 }
+
+/// Returns the body of a successful response.
+pub(crate) fn response_body(response: &Response) -> Result<ReadableStream> {
+    if !response.ok() {
+        bail!(
+            "Response from '{}' has status {} {}",
+            response.url(),
+            response.status(),
+            response.status_text()
+        );
+    }
+
+    response.body().context("Response has no body")
+}
+
+pub(crate) fn stream_reader(stream: &ReadableStream) -> ReadableStreamDefaultReader {
+    stream.get_reader().unchecked_into()
+}
+
+/// Reads the next chunk of bytes from the stream, returns `None` at the end of the stream.
+pub(crate) async fn read_chunk(reader: &ReadableStreamDefaultReader) -> Result<Option<Uint8Array>> {
+    let result = JsFuture::from(reader.read())
+        .await
+        .map_err(map_js_error("Read a chunk from the stream"))?;
+
+    let done = Reflect::get(&result, static_str_to_js("done"))
+        .map_err(map_js_error("Get the done flag of a stream chunk"))?;
+    if done.is_truthy() {
+        return Ok(None);
+    }
+
+    let value = Reflect::get(&result, static_str_to_js("value"))
+        .map_err(map_js_error("Get the value of a stream chunk"))?;
+    match value.dyn_into() {
+        Ok(chunk) => Ok(Some(chunk)),
+        Err(value) => bail!("Stream chunks must be Uint8Array, got {value:?}"),
+    }
+}
+
+/// Reads the whole stream into memory.
+pub(crate) async fn read_stream(stream: &ReadableStream) -> Result<Vec<u8>> {
+    let reader = stream_reader(stream);
+
+    let mut bytes = Vec::new();
+    while let Some(chunk) = read_chunk(&reader).await? {
+        let start = bytes.len();
+        bytes.resize(start + chunk.length() as usize, 0);
+        chunk.copy_to(&mut bytes[start..]);
+    }
+
+    Ok(bytes)
+}
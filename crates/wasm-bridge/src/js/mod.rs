@@ -4,6 +4,12 @@ pub use engine::*;
 mod store;
 pub use store::*;
 
+mod limits;
+pub use limits::*;
+
+mod module_cache;
+pub use module_cache::*;
+
 mod no_bindgen;
 pub use no_bindgen::*;
 
@@ -19,6 +25,9 @@ pub use config::*;
 mod context;
 pub use context::*;
 
+mod trap;
+pub use trap::*;
+
 pub type Error = anyhow::Error;
 pub type Result<T, E = Error> = anyhow::Result<T, E>;
 pub use anyhow::bail;
@@ -40,4 +49,5 @@ pub use direct::usize_max;
 pub use wasm_bridge_macros::async_trait;
 
 pub use js_sys;
+pub use web_sys;
 pub use wasm_bindgen;
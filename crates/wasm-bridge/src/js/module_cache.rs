@@ -0,0 +1,202 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fmt::{self, Debug, Formatter},
+    rc::Rc,
+};
+
+use js_sys::{Map, Uint8Array, WebAssembly};
+use sha2::{Digest, Sha256};
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+
+use crate::{helpers::map_js_error, Engine, Result};
+
+/// Storage for compiled modules, set with [`Config::module_cache`](crate::Config::module_cache).
+///
+/// Compilation is the slowest part of loading a plugin, so bytes that were compiled before
+/// are taken from the cache instead. The key is the SHA-256 hash in lowercase hex of the
+/// bytes given to [`Module::new`](crate::Module::new), parsed first if they are text,
+/// so it stays the same across page loads.
+///
+/// The module is looked up before it is instrumented. The key ends with `-fuel`, `-epoch`
+/// and `-limits` when [`Config::consume_fuel`](crate::Config::consume_fuel),
+/// [`Config::epoch_interruption`](crate::Config::epoch_interruption) or
+/// [`Config::resource_limits`](crate::Config::resource_limits) is set, because
+/// the compiled module is different then. Backtraces of such modules taken from the cache
+/// have no function names, because the layout of the instrumented binary is not known.
+///
+/// On sys, use `Module::serialize` and `Module::deserialize` instead.
+pub trait ModuleCache {
+    /// Returns the compiled module stored under `key`, if any.
+    fn get(&self, key: &str) -> Option<WebAssembly::Module>;
+
+    /// Stores a compiled module under `key`.
+    fn insert(&self, key: &str, module: &WebAssembly::Module);
+}
+
+/// Module cache that keeps the compiled modules in a Rust map for the lifetime of the cache.
+#[derive(Debug, Default)]
+pub struct InMemoryModuleCache {
+    modules: RefCell<HashMap<String, WebAssembly::Module>>,
+}
+
+impl InMemoryModuleCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of cached modules.
+    pub fn len(&self) -> usize {
+        self.modules.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.modules.borrow().is_empty()
+    }
+
+    /// Removes all cached modules.
+    pub fn clear(&self) {
+        self.modules.borrow_mut().clear();
+    }
+}
+
+impl ModuleCache for InMemoryModuleCache {
+    fn get(&self, key: &str) -> Option<WebAssembly::Module> {
+        self.modules.borrow().get(key).cloned()
+    }
+
+    fn insert(&self, key: &str, module: &WebAssembly::Module) {
+        self.modules
+            .borrow_mut()
+            .insert(key.to_owned(), module.clone());
+    }
+}
+
+/// Module cache backed by a JS `Map` from keys to `WebAssembly.Module` objects.
+///
+/// Compiled modules can be structured cloned, so the map can be posted to a web worker
+/// with `postMessage`, or stored in IndexedDB where the browser supports it,
+/// and used there with [`JsModuleCache::from_map`].
+#[derive(Clone, Debug)]
+pub struct JsModuleCache {
+    map: Map,
+}
+
+impl JsModuleCache {
+    pub fn new() -> Self {
+        Self::from_map(Map::new())
+    }
+
+    /// Uses an existing map, for example one received from another worker.
+    pub fn from_map(map: Map) -> Self {
+        Self { map }
+    }
+
+    /// Returns the underlying map.
+    pub fn map(&self) -> &Map {
+        &self.map
+    }
+}
+
+impl Default for JsModuleCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ModuleCache for JsModuleCache {
+    fn get(&self, key: &str) -> Option<WebAssembly::Module> {
+        self.map.get(&key.into()).dyn_into().ok()
+    }
+
+    fn insert(&self, key: &str, module: &WebAssembly::Module) {
+        self.map.set(&key.into(), module);
+    }
+}
+
+/// Module cache shared by the clones of a config.
+#[derive(Clone)]
+pub(crate) struct SharedModuleCache(pub(crate) Rc<dyn ModuleCache>);
+
+impl Debug for SharedModuleCache {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("ModuleCache")
+    }
+}
+
+/// Entry of a binary in the engine's module cache, does nothing if the engine has no cache.
+pub(crate) struct CacheEntry<'a>(Option<(&'a dyn ModuleCache, String)>);
+
+impl<'a> CacheEntry<'a> {
+    /// The key is the SHA-256 hash of the bytes in lowercase hex, followed by `suffix`.
+    pub(crate) fn new(engine: &'a Engine, bytes: &[u8], suffix: &str) -> Self {
+        Self(engine.config().module_cache.as_ref().map(|cache| {
+            let hash: String = Sha256::digest(bytes)
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect();
+            (&*cache.0, hash + suffix)
+        }))
+    }
+
+    pub(crate) fn get(&self) -> Option<WebAssembly::Module> {
+        let (cache, key) = self.0.as_ref()?;
+        cache.get(key)
+    }
+
+    pub(crate) fn insert(&self, module: &WebAssembly::Module) {
+        if let Some((cache, key)) = &self.0 {
+            cache.insert(key, module);
+        }
+    }
+}
+
+/// Synchronously compiles the bytes, or takes the compiled module from the engine's cache.
+pub(crate) fn compile_module(
+    engine: &Engine,
+    bytes: &[u8],
+    context: &'static str,
+) -> Result<WebAssembly::Module> {
+    let entry = CacheEntry::new(engine, bytes, "");
+    if let Some(module) = entry.get() {
+        return Ok(module);
+    }
+
+    let module = compile_bytes(bytes, context)?;
+    entry.insert(&module);
+    Ok(module)
+}
+
+/// Asynchronously compiles the bytes, or takes the compiled module from the engine's cache.
+pub(crate) async fn compile_module_async(
+    engine: &Engine,
+    bytes: &[u8],
+    context: &'static str,
+) -> Result<WebAssembly::Module> {
+    let entry = CacheEntry::new(engine, bytes, "");
+    if let Some(module) = entry.get() {
+        return Ok(module);
+    }
+
+    let module = compile_bytes_async(bytes, context).await?;
+    entry.insert(&module);
+    Ok(module)
+}
+
+/// Synchronously compiles the bytes, without using the cache.
+pub(crate) fn compile_bytes(bytes: &[u8], context: &'static str) -> Result<WebAssembly::Module> {
+    WebAssembly::Module::new(&Uint8Array::from(bytes).into()).map_err(map_js_error(context))
+}
+
+/// Asynchronously compiles the bytes, without using the cache.
+pub(crate) async fn compile_bytes_async(
+    bytes: &[u8],
+    context: &'static str,
+) -> Result<WebAssembly::Module> {
+    let promise = WebAssembly::compile(&Uint8Array::from(bytes).into());
+    Ok(JsFuture::from(promise)
+        .await
+        .map_err(map_js_error(context))?
+        .into())
+}
@@ -0,0 +1,187 @@
+use std::{
+    collections::HashMap,
+    fmt::{self, Display, Formatter},
+};
+
+use js_sys::Reflect;
+use wasm_bindgen::JsValue;
+
+use super::module_info::ModuleInfo;
+use crate::{helpers::static_str_to_js, AsContext};
+
+/// Stack trace of WebAssembly frames, attached as context to errors from wasm calls.
+///
+/// The frames are parsed from the JS error's stack and symbolized using the name
+/// sections of the loaded modules.
+#[derive(Debug, Clone)]
+pub struct WasmBacktrace {
+    frames: Vec<FrameInfo>,
+}
+
+impl WasmBacktrace {
+    /// Captures the current wasm stack, for example in an imported function.
+    pub fn capture(_store: impl AsContext) -> Self {
+        let stack = js_stack(&js_error_with_stack("")).unwrap_or_default();
+        Self::from_stack(&stack)
+    }
+
+    pub fn force_capture(store: impl AsContext) -> Self {
+        Self::capture(store)
+    }
+
+    pub fn frames(&self) -> &[FrameInfo] {
+        &self.frames
+    }
+
+    /// Reads the backtrace from a thrown JS error, returns `None` if there are no wasm frames.
+    pub(crate) fn from_js_error(error: &JsValue) -> Option<Self> {
+        let stack = js_stack(error)?;
+        let backtrace = Self::from_stack(&stack);
+        (!backtrace.frames.is_empty()).then_some(backtrace)
+    }
+
+    fn from_stack(stack: &str) -> Self {
+        let parsed = stack.lines().filter_map(parse_frame).collect::<Vec<_>>();
+
+        // The host code runs in wasm as well, only keep frames of the loaded modules.
+        // Frames from the same JS module must all be found in the same loaded module,
+        // which makes accidental matches with the host's own functions very unlikely.
+        let mut modules = HashMap::new();
+        let mut frames = Vec::with_capacity(parsed.len());
+        for (url, func_index, module_offset) in parsed.iter() {
+            let module = modules.entry(*url).or_insert_with(|| {
+                let module_frames = parsed
+                    .iter()
+                    .filter(|(other_url, _, _)| other_url == url)
+                    .map(|(_, index, offset)| (*index, *offset))
+                    .collect::<Vec<_>>();
+                ModuleInfo::find_module(url, &module_frames)
+            });
+            let Some(module) = module else {
+                continue;
+            };
+
+            let func_offset = module
+                .func_body(*func_index)
+                .map(|body| module_offset - body.start);
+
+            frames.push(FrameInfo {
+                func_index: *func_index,
+                func_name: module.func_name(*func_index).map(Into::into),
+                module_name: module.name.clone(),
+                module_offset: Some(*module_offset),
+                func_offset,
+            });
+        }
+
+        Self { frames }
+    }
+}
+
+/// Creates a JS error with a long enough stack to reach the wasm frames.
+///
+/// The stack starts with the host's own frames, which could use up the
+/// default limit of 10 frames in V8.
+pub(crate) fn js_error_with_stack(message: &str) -> JsValue {
+    const STACK_TRACE_LIMIT: u32 = 100;
+
+    let limit_name = static_str_to_js("stackTraceLimit");
+    let constructor = Reflect::get(&js_sys::global(), static_str_to_js("Error"))
+        .expect("global object has the Error constructor");
+    let old_limit = Reflect::get(&constructor, limit_name).unwrap_or(JsValue::UNDEFINED);
+
+    Reflect::set(&constructor, limit_name, &STACK_TRACE_LIMIT.into()).ok();
+    let error = js_sys::Error::new(message).into();
+    Reflect::set(&constructor, limit_name, &old_limit).ok();
+
+    error
+}
+
+fn js_stack(error: &JsValue) -> Option<String> {
+    Reflect::get(error, static_str_to_js("stack"))
+        .ok()?
+        .as_string()
+}
+
+/// Parses a frame like `at name (wasm://wasm/1a2b3c4d:wasm-function[5]:0x1f3)` (V8)
+/// or `name@wasm://wasm/1a2b3c4d:wasm-function[5]:0x1f3` (SpiderMonkey).
+///
+/// Returns the module's url, the function index and the offset in the module.
+fn parse_frame(line: &str) -> Option<(&str, u32, usize)> {
+    const MARKER: &str = ":wasm-function[";
+
+    let marker = line.find(MARKER)?;
+    let url_start = line[..marker].find("wasm://").unwrap_or(0);
+    let url = &line[url_start..marker];
+
+    let rest = &line[marker + MARKER.len()..];
+    let (index, rest) = rest.split_once(']')?;
+    let offset = rest.strip_prefix(":0x")?;
+    let offset_end = offset
+        .find(|c: char| !c.is_ascii_hexdigit())
+        .unwrap_or(offset.len());
+
+    Some((
+        url,
+        index.parse().ok()?,
+        usize::from_str_radix(&offset[..offset_end], 16).ok()?,
+    ))
+}
+
+impl Display for WasmBacktrace {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "error while executing at wasm backtrace:")?;
+
+        for (i, frame) in self.frames.iter().enumerate() {
+            // Avoid putting a trailing newline on the output
+            if i > 0 {
+                writeln!(f)?;
+            }
+
+            write!(f, "  {:>3}: ", i)?;
+            if let Some(offset) = frame.module_offset {
+                write!(f, "{:#6x} - ", offset)?;
+            }
+
+            let module_name = frame.module_name.as_deref().unwrap_or("<unknown>");
+            match &frame.func_name {
+                Some(name) => write!(f, "{module_name}!{name}")?,
+                None => write!(f, "{module_name}!<wasm function {}>", frame.func_index)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Description of a frame in a [`WasmBacktrace`].
+#[derive(Debug, Clone)]
+pub struct FrameInfo {
+    func_index: u32,
+    func_name: Option<String>,
+    module_name: Option<String>,
+    module_offset: Option<usize>,
+    func_offset: Option<usize>,
+}
+
+impl FrameInfo {
+    /// Index of the function in the module's function index space.
+    pub fn func_index(&self) -> u32 {
+        self.func_index
+    }
+
+    /// Name of the function from the module's name section, if there is one.
+    pub fn func_name(&self) -> Option<&str> {
+        self.func_name.as_deref()
+    }
+
+    /// Offset of the instruction in the module's binary.
+    pub fn module_offset(&self) -> Option<usize> {
+        self.module_offset
+    }
+
+    /// Offset of the instruction from the start of the function's body.
+    pub fn func_offset(&self) -> Option<usize> {
+        self.func_offset
+    }
+}
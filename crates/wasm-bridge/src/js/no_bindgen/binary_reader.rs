@@ -0,0 +1,188 @@
+use anyhow::{bail, Context};
+
+use crate::*;
+
+/// Minimal reader of the WebAssembly binary format.
+pub(crate) struct BinaryReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BinaryReader<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    pub(crate) fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Returns the bytes read since the `start` position.
+    pub(crate) fn read_since(&self, start: usize) -> &'a [u8] {
+        &self.data[start..self.position]
+    }
+
+    /// Returns all the bytes that were not read yet.
+    pub(crate) fn read_rest(&mut self) -> &'a [u8] {
+        let rest = &self.data[self.position..];
+        self.position = self.data.len();
+        rest
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.position >= self.data.len()
+    }
+
+    pub(crate) fn read_u8(&mut self) -> Result<u8> {
+        let byte = *self
+            .data
+            .get(self.position)
+            .context("Unexpected end of WebAssembly binary")?;
+        self.position += 1;
+        Ok(byte)
+    }
+
+    pub(crate) fn read_bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self
+            .position
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .context("Unexpected end of WebAssembly binary")?;
+
+        let bytes = &self.data[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    pub(crate) fn read_var_u32(&mut self) -> Result<u32> {
+        let value = self.read_var_u64()?;
+        u32::try_from(value).context("LEB128 value does not fit into u32")
+    }
+
+    pub(crate) fn read_var_u64(&mut self) -> Result<u64> {
+        let mut result = 0u64;
+        let mut shift = 0;
+
+        loop {
+            let byte = self.read_u8()?;
+            result |= ((byte & 0x7f) as u64) << shift;
+
+            if byte & 0x80 == 0 {
+                return Ok(result);
+            }
+
+            shift += 7;
+            if shift >= 64 {
+                bail!("LEB128 value is too long");
+            }
+        }
+    }
+
+    pub(crate) fn read_var_i64(&mut self) -> Result<i64> {
+        let mut result = 0i64;
+        let mut shift = 0;
+
+        loop {
+            let byte = self.read_u8()?;
+            result |= ((byte & 0x7f) as i64) << shift;
+            shift += 7;
+
+            if byte & 0x80 == 0 {
+                // Sign-extend the value
+                if shift < 64 && byte & 0x40 != 0 {
+                    result |= -1i64 << shift;
+                }
+                return Ok(result);
+            }
+
+            if shift >= 64 {
+                bail!("LEB128 value is too long");
+            }
+        }
+    }
+
+    pub(crate) fn read_name(&mut self) -> Result<&'a str> {
+        let len = self.read_var_u32()? as usize;
+        let bytes = self.read_bytes(len)?;
+        std::str::from_utf8(bytes).context("Name is not valid UTF-8")
+    }
+
+    pub(crate) fn read_val_type(&mut self) -> Result<ValType> {
+        Ok(match self.read_u8()? {
+            0x7f => ValType::I32,
+            0x7e => ValType::I64,
+            0x7d => ValType::F32,
+            0x7c => ValType::F64,
+            0x7b => ValType::V128,
+            byte => ValType::Ref(Self::ref_type(byte)?),
+        })
+    }
+
+    pub(crate) fn read_ref_type(&mut self) -> Result<RefType> {
+        Self::ref_type(self.read_u8()?)
+    }
+
+    fn ref_type(byte: u8) -> Result<RefType> {
+        Ok(match byte {
+            0x70 => RefType::FUNCREF,
+            0x6f => RefType::EXTERNREF,
+            byte => bail!("Unsupported value type 0x{byte:02x}"),
+        })
+    }
+
+    /// Reads limits, returns the minimum, the maximum and the flags.
+    pub(crate) fn read_limits(&mut self) -> Result<(u64, Option<u64>, u8)> {
+        let flags = self.read_u8()?;
+        let minimum = self.read_var_u64()?;
+        let maximum = if flags & 0x01 != 0 {
+            Some(self.read_var_u64()?)
+        } else {
+            None
+        };
+
+        Ok((minimum, maximum, flags))
+    }
+
+    /// Skips a constant expression, like the initializer of a global.
+    pub(crate) fn skip_const_expr(&mut self) -> Result<()> {
+        loop {
+            match self.read_u8()? {
+                0x0b => return Ok(()),
+                0x41 | 0x42 => {
+                    self.read_var_i64()?;
+                }
+                0x43 => {
+                    self.read_bytes(4)?;
+                }
+                0x44 => {
+                    self.read_bytes(8)?;
+                }
+                0x23 | 0xd2 => {
+                    self.read_var_u32()?;
+                }
+                0xd0 => {
+                    self.read_u8()?;
+                }
+                // Extended constant expressions: add, sub and mul
+                0x6a..=0x6c | 0x7c..=0x7e => {}
+                0xfd => {
+                    // Only v128.const is constant
+                    self.read_var_u32()?;
+                    self.read_bytes(16)?;
+                }
+                opcode => bail!("Unsupported opcode 0x{opcode:02x} in a constant expression"),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[wasm_bindgen_test::wasm_bindgen_test]
+fn read_bytes_past_end() {
+    let mut reader = BinaryReader::new(&[1, 2, 3]);
+    assert_eq!(reader.read_u8().unwrap(), 1);
+
+    assert!(reader.read_bytes(3).is_err());
+    assert!(reader.read_bytes(usize::MAX).is_err());
+    assert_eq!(reader.read_bytes(2).unwrap(), &[2, 3]);
+}
@@ -0,0 +1,165 @@
+use std::{
+    any::Any,
+    cell::{Ref, RefCell, RefMut},
+    collections::HashMap,
+    fmt::{self, Debug, Formatter},
+    ops::{Deref, DerefMut},
+    rc::Rc,
+};
+
+use anyhow::{bail, Context};
+use js_sys::{Function, Object, Reflect};
+use wasm_bindgen::{prelude::*, JsValue};
+
+use crate::{helpers::static_str_to_js, AsContext, AsContextMut, Result};
+
+type ExternRefData = Rc<RefCell<Box<dyn Any + Send + Sync>>>;
+
+#[wasm_bindgen]
+extern "C" {
+    type FinalizationRegistry;
+
+    #[wasm_bindgen(constructor)]
+    fn new(cleanup: &Function) -> FinalizationRegistry;
+
+    #[wasm_bindgen(method)]
+    fn register(this: &FinalizationRegistry, target: &JsValue, held_value: &JsValue);
+}
+
+#[derive(Default)]
+struct HandleTable {
+    next_id: u32,
+    entries: HashMap<u32, ExternRefData>,
+}
+
+thread_local! {
+    // Host values of extern refs that can still be reached from Rust, JS or wasm
+    static EXTERN_REFS: RefCell<HandleTable> = RefCell::new(HandleTable::default());
+
+    // Removes a host value once the JS object representing it is garbage collected
+    static REGISTRY: FinalizationRegistry = {
+        let cleanup = Closure::<dyn Fn(JsValue)>::new(|id: JsValue| {
+            if let Some(id) = id.as_f64() {
+                EXTERN_REFS.with(|table| table.borrow_mut().entries.remove(&(id as u32)));
+            }
+        });
+
+        // The registry lives for the whole lifetime of the thread, so the cleanup closure can leak
+        FinalizationRegistry::new(cleanup.into_js_value().unchecked_ref())
+    };
+}
+
+/// Reference to an arbitrary host value that can be passed to wasm, mirrors wasmtime's `ExternRef`.
+///
+/// On the web, the value is represented by an empty JS object, and the host value is kept
+/// in a handle table until that object is garbage collected.
+#[derive(Clone)]
+pub struct ExternRef {
+    object: Object,
+    id: u32,
+    data: ExternRefData,
+}
+
+impl ExternRef {
+    /// Creates a new `ExternRef` wrapping the given value.
+    pub fn new<T>(_store: impl AsContextMut, value: T) -> Result<Rooted<ExternRef>>
+    where
+        T: 'static + Any + Send + Sync,
+    {
+        let data: ExternRefData = Rc::new(RefCell::new(Box::new(value)));
+
+        let id = EXTERN_REFS.with(|table| {
+            let mut table = table.borrow_mut();
+            let id = table.next_id;
+            table.next_id = table.next_id.wrapping_add(1);
+            table.entries.insert(id, data.clone());
+            id
+        });
+
+        let object = Object::new();
+        Reflect::set(&object, static_str_to_js(ID_KEY), &id.into()).expect("object is an object");
+        REGISTRY.with(|registry| registry.register(&object, &id.into()));
+
+        Ok(Rooted(Self { object, id, data }))
+    }
+
+    /// Get a shared borrow of the underlying data for this `ExternRef`.
+    pub fn data(
+        &self,
+        _store: impl AsContext,
+    ) -> Result<impl Deref<Target = dyn Any + Send + Sync> + '_> {
+        let data = self
+            .data
+            .try_borrow()
+            .ok()
+            .context("Extern ref data is already borrowed mutably")?;
+
+        Ok(Ref::map(data, |data| &**data))
+    }
+
+    /// Get an exclusive borrow of the underlying data for this `ExternRef`.
+    pub fn data_mut(
+        &self,
+        _store: impl AsContextMut,
+    ) -> Result<impl DerefMut<Target = dyn Any + Send + Sync> + '_> {
+        let data = self
+            .data
+            .try_borrow_mut()
+            .ok()
+            .context("Extern ref data is already borrowed")?;
+
+        Ok(RefMut::map(data, |data| &mut **data))
+    }
+
+    pub(crate) fn to_js_value(&self) -> JsValue {
+        self.object.clone().into()
+    }
+
+    /// Finds the host value of an extern ref passed back from JS or wasm.
+    pub(crate) fn from_js_value(value: &JsValue) -> Result<Rooted<ExternRef>> {
+        let id = if value.is_object() {
+            Reflect::get(value, static_str_to_js(ID_KEY))
+                .ok()
+                .and_then(|id| id.as_f64())
+        } else {
+            None
+        };
+
+        let Some(id) = id.map(|id| id as u32) else {
+            bail!("Value {value:?} is not an extern ref created by wasm-bridge");
+        };
+
+        let data = EXTERN_REFS
+            .with(|table| table.borrow().entries.get(&id).cloned())
+            .with_context(|| format!("Extern ref with id {id} no longer exists"))?;
+
+        Ok(Rooted(Self {
+            object: value.clone().into(),
+            id,
+            data,
+        }))
+    }
+}
+
+impl Debug for ExternRef {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExternRef").field("id", &self.id).finish()
+    }
+}
+
+const ID_KEY: &str = "__wasm_bridge_extern_ref_id";
+
+/// A rooted reference to a GC-managed object, mirrors wasmtime's `Rooted`.
+///
+/// On the web, objects are managed by the JS garbage collector, so this just keeps
+/// the object alive for as long as it is used.
+#[derive(Clone, Debug)]
+pub struct Rooted<T>(T);
+
+impl<T> Deref for Rooted<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
@@ -0,0 +1,137 @@
+use anyhow::Context;
+use js_sys::WebAssembly;
+use wasm_bindgen::{JsCast, JsValue};
+
+use crate::*;
+
+#[derive(Clone, Debug)]
+pub enum Extern {
+    Func(Func),
+    Global(Global),
+    Table(Table),
+    Memory(Memory),
+}
+
+impl Extern {
+    /// Wraps an exported JS value, `ty` is the export's type if it is known.
+    ///
+    /// Returns `None` for values that are not functions, memories, globals or tables,
+    /// and an error for a global whose type is not known, because JS doesn't expose it.
+    pub(crate) fn from_js_value(
+        value: &JsValue,
+        closures: DropHandles,
+        ty: Option<&ExternType>,
+    ) -> Result<Option<Self>> {
+        let item = if value.is_function() {
+            Self::Func(Func::from_exported(
+                value.clone().into(),
+                closures,
+                ty.and_then(ExternType::func).cloned(),
+            ))
+        } else if value.is_instance_of::<WebAssembly::Memory>() {
+            Self::Memory(Memory::from_exported(
+                value.clone().unchecked_into(),
+                ty.and_then(ExternType::memory).cloned(),
+            ))
+        } else if value.is_instance_of::<WebAssembly::Global>() {
+            let ty = ty
+                .and_then(ExternType::global)
+                .context("The type of the exported global is not known")?;
+            Self::Global(Global::from_exported(
+                value.clone().unchecked_into(),
+                ty.clone(),
+            ))
+        } else if value.is_instance_of::<WebAssembly::Table>() {
+            Self::Table(Table::from_exported(
+                value.clone().unchecked_into(),
+                closures,
+                ty.and_then(ExternType::table).cloned(),
+            ))
+        } else {
+            return Ok(None);
+        };
+
+        Ok(Some(item))
+    }
+
+    pub(crate) fn to_js_value(&self) -> JsValue {
+        match self {
+            Self::Func(func) => func.function.clone().into(),
+            Self::Global(global) => global.to_js_value(),
+            Self::Table(table) => table.to_js_value(),
+            Self::Memory(memory) => memory.memory.clone().into(),
+        }
+    }
+
+    /// Kind of the extern in the JS API
+    pub(crate) fn kind_js_str(&self) -> &'static str {
+        match self {
+            Self::Func(_) => "function",
+            Self::Global(_) => "global",
+            Self::Table(_) => "table",
+            Self::Memory(_) => "memory",
+        }
+    }
+
+    /// Closures that must be kept alive for as long as this extern is used
+    pub(crate) fn closures(&self) -> Option<DropHandle> {
+        match self {
+            Self::Func(func) => Some(DropHandle::new(func.closures.clone())),
+            Self::Global(global) => Some(global.drop_handle()),
+            Self::Table(table) => Some(table.drop_handle()),
+            _ => None,
+        }
+    }
+
+    pub fn into_func(self) -> Option<Func> {
+        match self {
+            Self::Func(func) => Some(func),
+            _ => None,
+        }
+    }
+
+    pub fn into_global(self) -> Option<Global> {
+        match self {
+            Self::Global(global) => Some(global),
+            _ => None,
+        }
+    }
+
+    pub fn into_table(self) -> Option<Table> {
+        match self {
+            Self::Table(table) => Some(table),
+            _ => None,
+        }
+    }
+
+    pub fn into_memory(self) -> Option<Memory> {
+        match self {
+            Self::Memory(memory) => Some(memory),
+            _ => None,
+        }
+    }
+}
+
+impl From<Func> for Extern {
+    fn from(func: Func) -> Self {
+        Self::Func(func)
+    }
+}
+
+impl From<Global> for Extern {
+    fn from(global: Global) -> Self {
+        Self::Global(global)
+    }
+}
+
+impl From<Table> for Extern {
+    fn from(table: Table) -> Self {
+        Self::Table(table)
+    }
+}
+
+impl From<Memory> for Extern {
+    fn from(memory: Memory) -> Self {
+        Self::Memory(memory)
+    }
+}
@@ -4,43 +4,174 @@ use anyhow::bail;
 use js_sys::{Array, Function, Reflect};
 use wasm_bindgen::JsValue;
 
+use super::linker::make_dynamic_closure;
 use crate::{helpers::map_js_error, *};
 
+#[derive(Clone, Debug)]
 pub struct Func {
-    function: Function,
-    _closures: Rc<Vec<DropHandle>>,
+    pub(crate) function: Function,
+    pub(crate) closures: Rc<Vec<DropHandle>>,
+    ty: Option<FuncType>,
+    /// Exports seen by the [`Caller`] of a host function, `None` for exported functions
+    caller_exports: Option<LazyExports>,
 }
 
 impl Func {
-    pub(crate) fn new(function: Function, closures: Rc<Vec<DropHandle>>) -> Self {
+    /// Creates a host function with a dynamic signature, to be used outside of a [`Linker`].
+    pub fn new<T: 'static>(
+        store: impl AsContextMut<Data = T>,
+        ty: FuncType,
+        func: impl Fn(Caller<T>, &[Val], &mut [Val]) -> Result<()> + 'static,
+    ) -> Self {
+        let creator = make_dynamic_closure(ty.clone(), func);
+        Self::from_make_closure(store, &creator, ty)
+    }
+
+    /// Creates a host function from a Rust closure, to be used outside of a [`Linker`].
+    pub fn wrap<T, Params: WasmTyList, Results: WasmTyList>(
+        store: impl AsContextMut<Data = T>,
+        func: impl IntoMakeClosure<T, Params, Results>,
+    ) -> Self {
+        let ty = FuncType::new(&Engine::default(), Params::valtypes(), Results::valtypes());
+        Self::from_make_closure(store, &func.into_make_closure(), ty)
+    }
+
+    pub(crate) fn from_make_closure<T>(
+        store: impl AsContextMut<Data = T>,
+        creator: &MakeClosure<T>,
+        ty: FuncType,
+    ) -> Self {
+        let handle = store.as_context().data_handle().clone();
+
+        // Filled in when an instance importing this function is created
+        let exports = LazyExports::new();
+        let (js_func, drop_handle) = creator(handle, exports.clone());
+
+        Self {
+            function: js_func.into(),
+            closures: Rc::new(vec![drop_handle]),
+            ty: Some(ty),
+            caller_exports: Some(exports),
+        }
+    }
+
+    /// Wraps an exported JS function, `ty` is `None` when the type is not known.
+    pub(crate) fn from_exported(
+        function: Function,
+        closures: Rc<Vec<DropHandle>>,
+        ty: Option<FuncType>,
+    ) -> Self {
         Self {
             function,
-            _closures: closures,
+            closures,
+            ty,
+            caller_exports: None,
         }
     }
 
-    pub fn call(&self, _store: impl AsContextMut, args: &[Val], rets: &mut [Val]) -> Result<()> {
-        if self.function.length() != args.len() as u32 {
-            bail!(
-                "Exported function takes {} arguments, but {} arguments were provided instead",
-                self.function.length(),
-                args.len()
-            );
+    /// Makes the exports of `instance` available to the [`Caller`] of this host function.
+    ///
+    /// JS does not tell which instance calls an imported function, so when a function
+    /// is imported by several instances, the caller sees the one created last.
+    pub(crate) fn set_caller(&self, instance: &Instance) {
+        if let Some(exports) = &self.caller_exports {
+            exports.set(instance);
+        }
+    }
+
+    /// Returns the type of this function.
+    ///
+    /// Functions taken out of a table have no known type on the web. Their type is guessed
+    /// from the number of arguments, assuming they take `i32`s and return nothing.
+    pub fn ty(&self, _store: impl AsContext) -> FuncType {
+        match &self.ty {
+            Some(ty) => ty.clone(),
+            None => FuncType::new(
+                &Engine::default(),
+                (0..self.function.length()).map(|_| ValType::I32),
+                [],
+            ),
         }
+    }
+
+    /// Converts this function into a [`TypedFunc`], checking that the signature matches.
+    pub fn typed<Params, Results>(
+        &self,
+        _store: impl AsContext,
+    ) -> Result<TypedFunc<Params, Results>>
+    where
+        Params: ToJsValue + WasmTyList,
+        Results: FromJsValue + WasmTyList,
+    {
+        match &self.ty {
+            Some(ty) => {
+                check_types("parameters", Params::valtypes(), ty.params())?;
+                check_types("results", Results::valtypes(), ty.results())?;
+            }
+            None => {
+                if self.function.length() != Params::number_of_args() {
+                    bail!(
+                        "Function should have {} arguments, but it has {} instead",
+                        Params::number_of_args(),
+                        self.function.length(),
+                    );
+                }
+            }
+        }
+
+        Ok(TypedFunc::new(self.function.clone(), self.closures.clone()))
+    }
+
+    /// Calls the function with dynamically typed arguments.
+    ///
+    /// The results are converted using the function's type. When the type is not known,
+    /// the types of the values already in `rets` are used instead.
+    pub fn call(&self, store: impl AsContextMut, args: &[Val], rets: &mut [Val]) -> Result<()> {
+        if args.iter().any(|arg| matches!(arg, Val::V128(_))) {
+            bail!("V128 values cannot be passed to or from JS");
+        }
+
+        let ret_types = match &self.ty {
+            Some(ty) => {
+                check_vals("argument", args, ty.params())?;
+
+                if ty.results().len() != rets.len() {
+                    bail!(
+                        "Function returns {} values, but {} result slots were provided",
+                        ty.results().len(),
+                        rets.len()
+                    );
+                }
+
+                ty.results().collect::<Vec<_>>()
+            }
+            None => {
+                if self.function.length() != args.len() as u32 {
+                    bail!(
+                        "Exported function takes {} arguments, but {} arguments were provided instead",
+                        self.function.length(),
+                        args.len()
+                    );
+                }
+
+                rets.iter().map(Val::ty).collect()
+            }
+        };
 
         let js_args: Array = args.iter().map(Val::to_js_value).collect();
 
         let js_rets = self
             .function
             .apply(&JsValue::UNDEFINED, &js_args)
-            .map_err(map_js_error("call untyped exported function"))?;
+            .map_err(map_js_error("call untyped exported function"))
+            .map_err(|error| store.as_context().map_instrumented_trap(error))?;
 
         match rets.len() {
             0 => {
                 <()>::from_js_value(&js_rets)?;
             }
             1 => {
-                rets[0] = Val::from_js_value(&js_rets)?;
+                rets[0] = Val::from_js_typed(&js_rets, &ret_types[0])?;
             }
             n => {
                 if !js_rets.is_array() {
@@ -61,7 +192,7 @@ impl Func {
                     let js_val = Reflect::get_u32(&js_array, index as _)
                         .map_err(map_js_error("set rets at index"))?;
 
-                    *ret = Val::from_js_value(&js_val)?;
+                    *ret = Val::from_js_typed(&js_val, &ret_types[index])?;
                 }
             }
         }
@@ -69,3 +200,41 @@ impl Func {
         Ok(())
     }
 }
+
+/// Checks that the values match the expected types, `what` is used in the error message.
+pub(crate) fn check_vals(
+    what: &str,
+    vals: &[Val],
+    expected: impl ExactSizeIterator<Item = ValType>,
+) -> Result<()> {
+    if vals.len() != expected.len() {
+        bail!(
+            "expected {} {what}s, but {} were provided",
+            expected.len(),
+            vals.len()
+        );
+    }
+
+    for (index, (val, expected)) in vals.iter().zip(expected).enumerate() {
+        if val.ty() != expected {
+            bail!(
+                "{what} at index {index} has type {:?}, but {expected:?} was expected",
+                val.ty()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn check_types(
+    what: &str,
+    expected: Vec<ValType>,
+    actual: impl ExactSizeIterator<Item = ValType>,
+) -> Result<()> {
+    let actual = actual.collect::<Vec<_>>();
+    if expected != actual {
+        bail!("type mismatch with {what}: expected {expected:?}, found {actual:?}");
+    }
+    Ok(())
+}
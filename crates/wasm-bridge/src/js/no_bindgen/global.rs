@@ -0,0 +1,125 @@
+use std::{cell::RefCell, rc::Rc};
+
+use anyhow::bail;
+use js_sys::{Object, Reflect, WebAssembly};
+use wasm_bindgen::JsValue;
+
+use crate::{
+    helpers::{map_js_error, static_str_to_js},
+    *,
+};
+
+#[derive(Clone, Debug)]
+pub struct Global {
+    global: WebAssembly::Global,
+    ty: GlobalType,
+    /// Closures of the function stored in the global, shared by all clones of this global
+    closures: Rc<RefCell<Option<DropHandles>>>,
+}
+
+impl Global {
+    pub fn new(_store: impl AsContextMut, ty: GlobalType, val: Val) -> Result<Self> {
+        Self::from_type(ty, val)
+    }
+
+    pub(crate) fn from_type(ty: GlobalType, val: Val) -> Result<Self> {
+        if val.ty() != *ty.content() {
+            bail!(
+                "Global of type {:?} cannot be initialized with {val:?}",
+                ty.content()
+            );
+        }
+
+        let descriptor = Object::new();
+        Reflect::set(
+            &descriptor,
+            static_str_to_js("value"),
+            &ty.content().to_js_str().into(),
+        )
+        .expect("descriptor is an object");
+        Reflect::set(
+            &descriptor,
+            static_str_to_js("mutable"),
+            &(ty.mutability() == Mutability::Var).into(),
+        )
+        .expect("descriptor is an object");
+
+        let global = WebAssembly::Global::new(&descriptor, &val.to_js_value())
+            .map_err(map_js_error("Create WebAssembly global"))?;
+
+        Ok(Self {
+            global,
+            ty,
+            closures: Rc::new(RefCell::new(func_closures(&val))),
+        })
+    }
+
+    /// Wraps an exported global, `ty` is its type read from the module binary.
+    pub(crate) fn from_exported(global: WebAssembly::Global, ty: GlobalType) -> Self {
+        Self {
+            global,
+            ty,
+            closures: Default::default(),
+        }
+    }
+
+    pub(crate) fn to_js_value(&self) -> JsValue {
+        self.global.clone().into()
+    }
+
+    /// Keeps the closures of the stored function alive, including one stored later.
+    pub(crate) fn drop_handle(&self) -> DropHandle {
+        DropHandle::new(self.closures.clone())
+    }
+
+    pub fn ty(&self, _store: impl AsContext) -> GlobalType {
+        self.ty.clone()
+    }
+
+    pub fn get(&self, _store: impl AsContextMut) -> Val {
+        self.value()
+    }
+
+    pub(crate) fn value(&self) -> Val {
+        let value = self.global.value();
+        match self.ty.content() {
+            ValType::Ref(ty) => {
+                let closures = self.closures.borrow().clone().unwrap_or_default();
+                Ref::from_js_typed(&value, ty, closures).map(Val::from)
+            }
+            ty => Val::from_js_typed(&value, ty),
+        }
+        .expect("global value has the global's type")
+    }
+
+    pub fn set(&self, _store: impl AsContextMut, val: Val) -> Result<()> {
+        if self.ty.mutability() != Mutability::Var {
+            bail!("Cannot set an immutable global");
+        }
+
+        if val.ty() != *self.ty.content() {
+            bail!(
+                "Global of type {:?} cannot be set to {val:?}",
+                self.ty.content()
+            );
+        }
+
+        self.set_value(val)
+    }
+
+    pub(crate) fn set_value(&self, val: Val) -> Result<()> {
+        Reflect::set(&self.global, static_str_to_js("value"), &val.to_js_value())
+            .map_err(map_js_error("Set global value"))?;
+
+        // The global can call the function after the caller's `Func` is dropped
+        *self.closures.borrow_mut() = func_closures(&val);
+        Ok(())
+    }
+}
+
+fn func_closures(val: &Val) -> Option<DropHandles> {
+    match val {
+        Val::FuncRef(Some(func)) => Some(func.closures.clone()),
+        _ => None,
+    }
+}
@@ -1,20 +1,26 @@
-use std::{collections::HashMap, rc::Rc};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    rc::{Rc, Weak},
+};
 
+use super::{instrument::add_instrument_imports, module_info::ModuleInfo};
 use crate::{
     helpers::{map_js_error, static_str_to_js},
     *,
 };
 use anyhow::{bail, Context};
 use js_sys::{
-    Function, Object, Reflect,
+    Object, Reflect,
     WebAssembly::{self},
 };
 use wasm_bindgen::JsValue;
 use wasm_bindgen_futures::JsFuture;
 
 pub struct Instance {
-    exports: HashMap<String, JsValue>,
+    exports: Rc<HashMap<String, JsValue>>,
     closures: Rc<Vec<DropHandle>>,
+    info: Rc<ModuleInfo>,
 }
 
 impl Instance {
@@ -22,18 +28,86 @@ impl Instance {
         since = "0.4.0",
         note = "Instantiating a module synchronously can panic on the web, please use `new_safe` instead."
     )]
-    pub fn new(_store: impl AsContextMut, module: &Module, _imports: &[()]) -> Result<Self> {
-        let imports = Object::new();
-        Self::new_with_imports(module, &imports, vec![])
+    pub fn new(mut store: impl AsContextMut, module: &Module, imports: &[Extern]) -> Result<Self> {
+        let store = store.as_context_mut();
+        let (imports_obj, closures) = Self::collect_imports(module, imports)?;
+        store.add_instance()?;
+
+        let instance = add_instrument_imports(store, module, &imports_obj)
+            .and_then(|()| {
+                Self::new_with_imports(module, &imports_obj, closures)
+                    .map_err(|error| store.map_instrumented_trap(error))
+            })
+            .inspect_err(|_| store.remove_instance())?;
+        set_caller(imports, &instance);
+        Ok(instance)
     }
 
     pub async fn new_safe(
-        _store: impl AsContextMut,
+        mut store: impl AsContextMut,
         module: &Module,
-        _imports: &[()],
+        imports: &[Extern],
     ) -> Result<Self> {
-        let imports = Object::new();
-        Self::new_with_imports_async(module, &imports, vec![]).await
+        let store = store.as_context_mut();
+        let (imports_obj, closures) = Self::collect_imports(module, imports)?;
+        store.add_instance()?;
+
+        let instance = match add_instrument_imports(store, module, &imports_obj) {
+            Ok(()) => Self::new_with_imports_async(module, &imports_obj, closures)
+                .await
+                .map_err(|error| store.map_instrumented_trap(error)),
+            Err(error) => Err(error),
+        };
+        let instance = instance.inspect_err(|_| store.remove_instance())?;
+        set_caller(imports, &instance);
+        Ok(instance)
+    }
+
+    /// Matches the imports with the module's imports in the order they are declared.
+    fn collect_imports(module: &Module, imports: &[Extern]) -> Result<(Object, Vec<DropHandle>)> {
+        // Imports injected by the instrumentation come last, so they are not matched here
+        // The count is taken from JS, the import types are empty if the binary could not be parsed
+        let declared = WebAssembly::Module::imports(&module.module);
+        let expected = declared.length() as usize - module.info.injected_imports.len();
+        if expected != imports.len() {
+            bail!(
+                "Module has {expected} imports, but {} imports were provided",
+                imports.len()
+            );
+        }
+
+        let imports_obj = Object::new();
+        let mut closures = vec![];
+
+        for (index, item) in imports.iter().enumerate() {
+            let descriptor = declared.get(index as u32);
+            let get_str = |key: &'static str| {
+                Reflect::get(&descriptor, static_str_to_js(key))
+                    .ok()
+                    .and_then(|value| value.as_string())
+                    .context("Import descriptor should have string fields")
+            };
+
+            let module_name = get_str("module")?;
+            let name = get_str("name")?;
+            let kind = get_str("kind")?;
+
+            if kind != item.kind_js_str() {
+                bail!(
+                    "Import {module_name}::{name} is a {kind}, but a {} was provided",
+                    item.kind_js_str()
+                );
+            }
+
+            let module_obj = import_module(&imports_obj, &module_name);
+            Reflect::set(&module_obj, &name.into(), &item.to_js_value()).expect("module is object");
+
+            if let Some(item_closures) = item.closures() {
+                closures.push(item_closures);
+            }
+        }
+
+        Ok((imports_obj, closures))
     }
 
     pub(crate) fn new_with_imports(
@@ -44,7 +118,7 @@ impl Instance {
         let instance = WebAssembly::Instance::new(&module.module, imports)
             .map_err(map_js_error("Instantiate WebAssembly module"))?;
 
-        Self::from_js_object(instance.into(), closures)
+        Self::from_js_object(module, instance.into(), closures)
     }
 
     pub(crate) async fn new_with_imports_async(
@@ -58,64 +132,133 @@ impl Instance {
             .await
             .map_err(map_js_error("Instantiate WebAssembly module"))?;
 
-        Self::from_js_object(instance, closures)
+        Self::from_js_object(module, instance, closures)
     }
 
-    fn from_js_object(instance: JsValue, closures: Vec<DropHandle>) -> Result<Self> {
+    fn from_js_object(
+        module: &Module,
+        instance: JsValue,
+        closures: Vec<DropHandle>,
+    ) -> Result<Self> {
         let exports = Reflect::get(&instance, static_str_to_js("exports"))
             .map_err(map_js_error("Get instance's exports"))?;
 
         Ok(Self {
-            exports: process_exports(exports)?,
+            exports: Rc::new(process_exports(exports)?),
             closures: Rc::new(closures),
+            info: module.info.clone(),
         })
     }
 
-    pub fn get_memory(&self, _store: impl AsContextMut, name: &str) -> Option<Memory> {
-        let memory = self.exports.get(name)?;
+    /// Looks up an export by name.
+    ///
+    /// Exported globals are only available if their type could be read from the module binary.
+    pub fn get_export(&self, _store: impl AsContextMut, name: &str) -> Option<Extern> {
+        Extern::from_js_value(
+            self.exports.get(name)?,
+            self.closures.clone(),
+            self.info.export_type(name),
+        )
+        .ok()
+        .flatten()
+    }
 
-        if memory.is_object() {
-            Some(Memory::new(memory.clone().into()))
-        } else {
-            None
+    /// All exports of this instance, used when defining it in a [`Linker`].
+    pub(crate) fn extern_exports(&self) -> Result<Vec<(String, Extern)>> {
+        let mut exports = Vec::with_capacity(self.exports.len());
+        for (name, value) in self.exports.iter() {
+            let item =
+                Extern::from_js_value(value, self.closures.clone(), self.info.export_type(name))
+                    .with_context(|| format!("Export '{name}'"))?;
+            if let Some(item) = item {
+                exports.push((name.clone(), item));
+            }
         }
+        Ok(exports)
     }
 
-    pub fn get_func(&self, _store: impl AsContextMut, name: &str) -> Option<Func> {
-        let function = self.get_func_inner(name).ok()?;
+    pub fn get_memory(&self, store: impl AsContextMut, name: &str) -> Option<Memory> {
+        self.get_export(store, name)?.into_memory()
+    }
+
+    pub fn get_global(&self, store: impl AsContextMut, name: &str) -> Option<Global> {
+        self.get_export(store, name)?.into_global()
+    }
+
+    pub fn get_table(&self, store: impl AsContextMut, name: &str) -> Option<Table> {
+        self.get_export(store, name)?.into_table()
+    }
 
-        Some(Func::new(function, self.closures.clone()))
+    pub fn get_func(&self, store: impl AsContextMut, name: &str) -> Option<Func> {
+        self.get_export(store, name)?.into_func()
     }
 
-    pub fn get_typed_func<Params: ToJsValue, Results: FromJsValue>(
+    pub fn get_typed_func<Params, Results>(
         &self,
-        _store: impl AsContextMut,
+        mut store: impl AsContextMut,
         name: &str,
-    ) -> Result<TypedFunc<Params, Results>> {
-        let function = self.get_func_inner(name)?;
+    ) -> Result<TypedFunc<Params, Results>>
+    where
+        Params: ToJsValue + WasmTyList,
+        Results: FromJsValue + WasmTyList,
+    {
+        let func = self
+            .get_func(&mut store, name)
+            .with_context(|| format!("Exported function '{name}' not found"))?;
 
-        if function.length() != Params::number_of_args() {
-            bail!(
-                "Exported function {name} should have {} arguments, but it has {} instead.",
-                Params::number_of_args(),
-                function.length(),
-            );
-        }
+        func.typed(&store)
+            .with_context(|| format!("Failed to convert function '{name}' to given type"))
+    }
+}
 
-        Ok(TypedFunc::new(function, self.closures.clone()))
+/// Exports of an instance, available to the `Caller` of its imported functions.
+///
+/// Imported functions are created before the instance exists, so this is filled in
+/// only after the instantiation is finished.
+#[derive(Debug, Clone, Default)]
+pub struct LazyExports(Rc<RefCell<Option<SharedExports>>>);
+
+#[derive(Debug)]
+struct SharedExports {
+    exports: Rc<HashMap<String, JsValue>>,
+    closures: Weak<Vec<DropHandle>>,
+    info: Rc<ModuleInfo>,
+}
+
+impl LazyExports {
+    pub(crate) fn new() -> Self {
+        Self::default()
     }
 
-    fn get_func_inner(&self, name: &str) -> Result<Function> {
-        let function = self
-            .exports
-            .get(name)
-            .context("Exported function '{name}' not found")?;
+    pub(crate) fn set(&self, instance: &Instance) {
+        // Weak reference, the closures hold this value, so a strong one would create a cycle
+        *self.0.borrow_mut() = Some(SharedExports {
+            exports: instance.exports.clone(),
+            closures: Rc::downgrade(&instance.closures),
+            info: instance.info.clone(),
+        });
+    }
 
-        if !function.is_function() {
-            bail!("Exported object '{function:?}' with name '{name}' is not a function");
-        }
+    pub(crate) fn get_export(&self, name: &str) -> Option<Extern> {
+        let exports = self.0.borrow();
+        let shared = exports.as_ref()?;
 
-        Ok(function.clone().into())
+        Extern::from_js_value(
+            shared.exports.get(name)?,
+            shared.closures.upgrade()?,
+            shared.info.export_type(name),
+        )
+        .ok()
+        .flatten()
+    }
+}
+
+/// Lets the imported host functions see the exports of the new instance.
+pub(crate) fn set_caller<'a>(imports: impl IntoIterator<Item = &'a Extern>, instance: &Instance) {
+    for item in imports {
+        if let Extern::Func(func) = item {
+            func.set_caller(instance);
+        }
     }
 }
 
@@ -0,0 +1,902 @@
+use anyhow::{bail, Context};
+use js_sys::{Object, Reflect};
+
+use super::{binary_reader::BinaryReader, module_info::ModuleInfo};
+use crate::*;
+
+/// Import module of the items injected into instrumented modules.
+pub(crate) const INSTRUMENT_MODULE: &str = "__wasm_bridge";
+
+/// Name of the imported global holding the remaining fuel of the store.
+pub(crate) const FUEL_GLOBAL: &str = "fuel";
+
+/// Name of the imported global holding the current epoch of the engine.
+pub(crate) const EPOCH_GLOBAL: &str = "epoch";
+
+/// Name of the imported global holding the epoch deadline of the store.
+pub(crate) const EPOCH_DEADLINE_GLOBAL: &str = "epoch_deadline";
+
+/// Prefix of the names of imported memories that were defined by the original module.
+const MEMORY_PREFIX: &str = "memory";
+
+/// Prefix of the names of imported tables that were defined by the original module.
+const TABLE_PREFIX: &str = "table";
+
+/// Engine settings that a module is instrumented for.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Instrumentation {
+    pub(crate) fuel: bool,
+    pub(crate) epoch: bool,
+    pub(crate) limits: bool,
+}
+
+impl Instrumentation {
+    pub(crate) fn new(config: &Config) -> Self {
+        Self {
+            fuel: config.consume_fuel,
+            epoch: config.epoch_interruption,
+            limits: config.resource_limits,
+        }
+    }
+
+    /// Names of the injected globals, in the order they are imported.
+    fn globals(&self) -> Vec<&'static str> {
+        let mut globals = vec![];
+        if self.fuel {
+            globals.push(FUEL_GLOBAL);
+        }
+        if self.epoch {
+            globals.extend([EPOCH_GLOBAL, EPOCH_DEADLINE_GLOBAL]);
+        }
+        globals
+    }
+
+    /// Suffix of the module's key in the [`ModuleCache`], the compiled module depends on these.
+    pub(crate) fn cache_key_suffix(&self) -> String {
+        let mut suffix = String::new();
+        if self.fuel {
+            suffix.push_str("-fuel");
+        }
+        if self.epoch {
+            suffix.push_str("-epoch");
+        }
+        if self.limits {
+            suffix.push_str("-limits");
+        }
+        suffix
+    }
+}
+
+/// Adds the items imported by instrumented modules to the imports.
+///
+/// Memories and tables defined by the original module are created here,
+/// so that the store's resource limiter applies to them.
+pub(crate) fn add_instrument_imports<T>(
+    store: &mut Store<T>,
+    module: &Module,
+    imports: &Object,
+) -> Result<()> {
+    if module.info.injected_imports.is_empty() {
+        return Ok(());
+    }
+
+    let instrument_module = import_module(imports, INSTRUMENT_MODULE);
+    for (name, ty) in &module.info.injected_imports {
+        let value = match ty {
+            ExternType::Memory(ty) => Memory::new(&mut *store, ty.clone())?.memory.into(),
+            ExternType::Table(ty) => {
+                let init = match ty.element().heap_type() {
+                    HeapType::Func => Ref::Func(None),
+                    HeapType::Extern => Ref::Extern(None),
+                };
+                Table::new(&mut *store, ty.clone(), init)?.to_js_value()
+            }
+            _ => {
+                let (setting, global) = match name.as_str() {
+                    FUEL_GLOBAL => ("consume_fuel", store.fuel()),
+                    EPOCH_GLOBAL => ("epoch_interruption", store.engine().epoch()),
+                    _ => ("epoch_interruption", store.epoch_deadline()),
+                };
+
+                let Some(global) = global else {
+                    bail!("Module was compiled with `{setting}` enabled, but the store's engine was not");
+                };
+                global.to_js_value()
+            }
+        };
+
+        Reflect::set(&instrument_module, &name.into(), &value).expect("module is object");
+    }
+
+    Ok(())
+}
+
+const UNREACHABLE: u8 = 0x00;
+const NOP: u8 = 0x01;
+const BLOCK: u8 = 0x02;
+const LOOP: u8 = 0x03;
+const IF: u8 = 0x04;
+const ELSE: u8 = 0x05;
+const TRY: u8 = 0x06;
+const DELEGATE: u8 = 0x18;
+const TRY_TABLE: u8 = 0x1f;
+const END: u8 = 0x0b;
+const RETURN: u8 = 0x0f;
+const DROP: u8 = 0x1a;
+const GLOBAL_GET: u8 = 0x23;
+const GLOBAL_SET: u8 = 0x24;
+const I64_CONST: u8 = 0x42;
+const I64_LT_S: u8 = 0x53;
+const I64_GE_U: u8 = 0x5a;
+const I64_SUB: u8 = 0x7d;
+
+/// A module binary rewritten by [`instrument`].
+pub(crate) struct Instrumented {
+    pub(crate) bytes: Vec<u8>,
+    /// Imports added by the instrumentation, provided by the store instead of the user
+    pub(crate) injected_imports: Vec<(String, ExternType)>,
+}
+
+/// Rewrites the binary of a core module to consume fuel and check the epoch deadline,
+/// like wasmtime does with `Config::consume_fuel` and `Config::epoch_interruption`.
+///
+/// The browser has no hooks into the execution of wasm code, so mutable `i64` globals
+/// are imported into the module, and checked at function entries and loop headers.
+///
+/// Fuel is also checked after loops. Each check charges the instructions up to the next
+/// check in advance, using the same costs as wasmtime, so the consumed fuel can be slightly
+/// higher than on sys when a branch skips some instructions. When there is not enough fuel left,
+/// the global is set to `-1` and the code traps with `unreachable`, which is then reported
+/// as [`Trap::OutOfFuel`].
+///
+/// When the engine's epoch reaches the store's deadline, the code traps with `unreachable`,
+/// which is then reported as [`Trap::Interrupt`].
+///
+/// With [`Config::resource_limits`], memories and tables defined by the module are turned
+/// into imports, so that the store can create them and ask its resource limiter first.
+/// Imported items come before the defined ones in the index spaces, so their indices
+/// do not change.
+pub(crate) fn instrument(bytes: &[u8], instrumentation: Instrumentation) -> Result<Instrumented> {
+    let mut reader = BinaryReader::new(bytes);
+
+    let header = reader.read_bytes(8)?;
+    if header != b"\0asm\x01\0\0\0" {
+        bail!("Not a WebAssembly core module binary");
+    }
+
+    let mut instrumenter = Instrumenter::new(bytes, instrumentation)?;
+
+    if instrumenter.injected_globals.is_empty()
+        && instrumenter.injected_tables.is_empty()
+        && instrumenter.injected_memories.is_empty()
+    {
+        return Ok(Instrumented {
+            bytes: bytes.to_vec(),
+            injected_imports: Vec::new(),
+        });
+    }
+
+    // Indices of globals only change when globals are injected
+    let shift_globals = !instrumenter.injected_globals.is_empty();
+
+    let mut out = header.to_vec();
+    while !reader.is_empty() {
+        let id = reader.read_u8()?;
+        let size = reader.read_var_u32()? as usize;
+        let bytes = reader.read_bytes(size)?;
+        let mut section = BinaryReader::new(bytes);
+
+        // The import section must come after the type section and before all the others
+        if !instrumenter.imports_written && !matches!(id, 0 | 1 | 2) {
+            write_section(&mut out, 2, &instrumenter.rewrite_imports(None)?);
+        }
+
+        // Defined tables and memories are imported instead
+        if (id == 4 && !instrumenter.injected_tables.is_empty())
+            || (id == 5 && !instrumenter.injected_memories.is_empty())
+        {
+            continue;
+        }
+
+        let content = match id {
+            2 => Some(instrumenter.rewrite_imports(Some(&mut section))?),
+            _ if !shift_globals => None,
+            0 => instrumenter.rewrite_custom(&mut section),
+            6 => Some(instrumenter.rewrite_globals(&mut section)?),
+            7 => Some(instrumenter.rewrite_exports(&mut section)?),
+            9 => Some(instrumenter.rewrite_elements(&mut section)?),
+            10 => Some(instrumenter.rewrite_code(&mut section)?),
+            11 => Some(instrumenter.rewrite_data(&mut section)?),
+            _ => None,
+        };
+
+        match content {
+            Some(content) => write_section(&mut out, id, &content),
+            None => write_section(&mut out, id, bytes),
+        }
+    }
+
+    if !instrumenter.imports_written {
+        write_section(&mut out, 2, &instrumenter.rewrite_imports(None)?);
+    }
+
+    Ok(Instrumented {
+        bytes: out,
+        injected_imports: instrumenter.injected_imports()?,
+    })
+}
+
+/// Names and types of the imports that [`instrument`] adds to the module, without rewriting it.
+pub(crate) fn injected_imports(
+    bytes: &[u8],
+    instrumentation: Instrumentation,
+) -> Result<Vec<(String, ExternType)>> {
+    Instrumenter::new(bytes, instrumentation)?.injected_imports()
+}
+
+#[derive(Default)]
+struct Instrumenter {
+    instrumentation: Instrumentation,
+    injected_globals: Vec<&'static str>,
+    /// Encoded types of the tables defined by the original module
+    injected_tables: Vec<Vec<u8>>,
+    /// Encoded types of the memories defined by the original module
+    injected_memories: Vec<Vec<u8>>,
+    imports_written: bool,
+    /// Number of imported globals in the original module, the injected globals come right after them
+    imported_globals: u32,
+}
+
+impl Instrumenter {
+    fn new(bytes: &[u8], instrumentation: Instrumentation) -> Result<Self> {
+        let mut instrumenter = Self {
+            instrumentation,
+            injected_globals: instrumentation.globals(),
+            ..Default::default()
+        };
+        if instrumentation.limits {
+            instrumenter.collect_defined(bytes)?;
+        }
+
+        Ok(instrumenter)
+    }
+
+    /// Maps an index of a global in the original module to the index in the instrumented one.
+    fn global_index(&self, index: u32) -> u32 {
+        if index >= self.imported_globals {
+            index + self.injected_globals.len() as u32
+        } else {
+            index
+        }
+    }
+
+    fn injected_global(&self, name: &str) -> u32 {
+        let position = self
+            .injected_globals
+            .iter()
+            .position(|injected| *injected == name)
+            .expect("global is injected");
+
+        self.imported_globals + position as u32
+    }
+
+    /// Names and types of the injected items, in the order they are imported.
+    fn injected_imports(&self) -> Result<Vec<(String, ExternType)>> {
+        let mut imports = Vec::new();
+
+        for name in &self.injected_globals {
+            let ty = GlobalType::new(ValType::I64, Mutability::Var);
+            imports.push((name.to_string(), ExternType::Global(ty)));
+        }
+
+        for (index, ty) in self.injected_tables.iter().enumerate() {
+            let ty = ModuleInfo::read_table_type(&mut BinaryReader::new(ty))?;
+            imports.push((format!("{TABLE_PREFIX}{index}"), ExternType::Table(ty)));
+        }
+
+        for (index, ty) in self.injected_memories.iter().enumerate() {
+            let ty = ModuleInfo::read_memory_type(&mut BinaryReader::new(ty))?;
+            imports.push((format!("{MEMORY_PREFIX}{index}"), ExternType::Memory(ty)));
+        }
+
+        Ok(imports)
+    }
+
+    /// Collects the tables and memories defined by the module, if the JS API can create them.
+    fn collect_defined(&mut self, bytes: &[u8]) -> Result<()> {
+        let mut reader = BinaryReader::new(bytes);
+        reader.read_bytes(8)?;
+
+        while !reader.is_empty() {
+            let id = reader.read_u8()?;
+            let size = reader.read_var_u32()? as usize;
+            let mut section = BinaryReader::new(reader.read_bytes(size)?);
+
+            match id {
+                4 => self.injected_tables = Self::collect_tables(&mut section)?,
+                5 => self.injected_memories = Self::collect_memories(&mut section)?,
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    fn collect_tables(section: &mut BinaryReader) -> Result<Vec<Vec<u8>>> {
+        let mut tables = Vec::new();
+
+        for _ in 0..section.read_var_u32()? {
+            let start = section.position();
+
+            // Tables with an initializer or a typed reference cannot be created by the JS API
+            match section.read_u8()? {
+                0x70 | 0x6f => {}
+                _ => return Ok(vec![]),
+            }
+
+            section.read_limits()?;
+            tables.push(section.read_since(start).to_vec());
+        }
+
+        Ok(tables)
+    }
+
+    fn collect_memories(section: &mut BinaryReader) -> Result<Vec<Vec<u8>>> {
+        let mut memories = Vec::new();
+
+        for _ in 0..section.read_var_u32()? {
+            let start = section.position();
+            let (_, _, flags) = section.read_limits()?;
+
+            // 64-bit memories cannot be created by the JS API
+            if flags & 0x04 != 0 {
+                return Ok(vec![]);
+            }
+
+            memories.push(section.read_since(start).to_vec());
+        }
+
+        Ok(memories)
+    }
+
+    /// Copies the imports and adds the injected items as the last ones.
+    fn rewrite_imports(&mut self, section: Option<&mut BinaryReader>) -> Result<Vec<u8>> {
+        let mut out = Vec::new();
+        let mut entries = Vec::new();
+        let mut count = 0;
+
+        if let Some(section) = section {
+            count = section.read_var_u32()?;
+            for _ in 0..count {
+                let start = section.position();
+                section.read_name()?;
+                section.read_name()?;
+
+                match section.read_u8()? {
+                    0x00 => {
+                        section.read_var_u32()?;
+                    }
+                    0x01 => {
+                        skip_val_type(section)?;
+                        section.read_limits()?;
+                    }
+                    0x02 => {
+                        section.read_limits()?;
+                    }
+                    0x03 => {
+                        skip_val_type(section)?;
+                        section.read_u8()?;
+                        self.imported_globals += 1;
+                    }
+                    0x04 => {
+                        section.read_u8()?;
+                        section.read_var_u32()?;
+                    }
+                    kind => {
+                        // The size of an unknown entry is not known, so the rest is copied as is.
+                        // Globals after it cannot be counted, which only matters if globals are injected.
+                        if !self.injected_globals.is_empty() {
+                            bail!("Unsupported import kind 0x{kind:02x}");
+                        }
+
+                        entries.extend_from_slice(section.read_since(start));
+                        entries.extend_from_slice(section.read_rest());
+                        break;
+                    }
+                }
+
+                entries.extend_from_slice(section.read_since(start));
+            }
+        }
+
+        let injected =
+            self.injected_globals.len() + self.injected_tables.len() + self.injected_memories.len();
+        write_var_u32(&mut out, count + injected as u32);
+        out.extend_from_slice(&entries);
+
+        for name in &self.injected_globals {
+            write_name(&mut out, INSTRUMENT_MODULE);
+            write_name(&mut out, name);
+            out.extend_from_slice(&[0x03, 0x7e, 0x01]); // Mutable i64 global
+        }
+
+        for (index, ty) in self.injected_tables.iter().enumerate() {
+            write_name(&mut out, INSTRUMENT_MODULE);
+            write_name(&mut out, &format!("{TABLE_PREFIX}{index}"));
+            out.push(0x01);
+            out.extend_from_slice(ty);
+        }
+
+        for (index, ty) in self.injected_memories.iter().enumerate() {
+            write_name(&mut out, INSTRUMENT_MODULE);
+            write_name(&mut out, &format!("{MEMORY_PREFIX}{index}"));
+            out.push(0x02);
+            out.extend_from_slice(ty);
+        }
+
+        self.imports_written = true;
+        Ok(out)
+    }
+
+    fn rewrite_globals(&self, section: &mut BinaryReader) -> Result<Vec<u8>> {
+        let mut out = Vec::new();
+        let count = section.read_var_u32()?;
+        write_var_u32(&mut out, count);
+
+        for _ in 0..count {
+            let start = section.position();
+            skip_val_type(section)?;
+            section.read_u8()?;
+            out.extend_from_slice(section.read_since(start));
+
+            self.copy_const_expr(section, &mut out)?;
+        }
+
+        Ok(out)
+    }
+
+    fn rewrite_exports(&self, section: &mut BinaryReader) -> Result<Vec<u8>> {
+        let mut out = Vec::new();
+        let count = section.read_var_u32()?;
+        write_var_u32(&mut out, count);
+
+        for _ in 0..count {
+            write_name(&mut out, section.read_name()?);
+            let kind = section.read_u8()?;
+            let index = section.read_var_u32()?;
+
+            out.push(kind);
+            match kind {
+                0x03 => write_var_u32(&mut out, self.global_index(index)),
+                _ => write_var_u32(&mut out, index),
+            }
+        }
+
+        Ok(out)
+    }
+
+    fn rewrite_elements(&self, section: &mut BinaryReader) -> Result<Vec<u8>> {
+        let mut out = Vec::new();
+        let count = section.read_var_u32()?;
+        write_var_u32(&mut out, count);
+
+        for _ in 0..count {
+            let flags = section.read_var_u32()?;
+            write_var_u32(&mut out, flags);
+
+            // Active segments have an offset, with an explicit table index if bit 1 is set
+            if flags & 0x01 == 0 {
+                if flags & 0x02 != 0 {
+                    write_var_u32(&mut out, section.read_var_u32()?);
+                }
+                self.copy_const_expr(section, &mut out)?;
+            }
+
+            // Element kind or reference type, except for the legacy active segments
+            if flags & 0x03 != 0 {
+                let start = section.position();
+                if flags & 0x04 != 0 {
+                    skip_val_type(section)?;
+                } else {
+                    section.read_u8()?;
+                }
+                out.extend_from_slice(section.read_since(start));
+            }
+
+            let items = section.read_var_u32()?;
+            write_var_u32(&mut out, items);
+            for _ in 0..items {
+                if flags & 0x04 != 0 {
+                    self.copy_const_expr(section, &mut out)?;
+                } else {
+                    write_var_u32(&mut out, section.read_var_u32()?);
+                }
+            }
+        }
+
+        Ok(out)
+    }
+
+    fn rewrite_data(&self, section: &mut BinaryReader) -> Result<Vec<u8>> {
+        let mut out = Vec::new();
+        let count = section.read_var_u32()?;
+        write_var_u32(&mut out, count);
+
+        for _ in 0..count {
+            let flags = section.read_var_u32()?;
+            write_var_u32(&mut out, flags);
+
+            match flags {
+                0 => self.copy_const_expr(section, &mut out)?,
+                1 => {}
+                2 => {
+                    write_var_u32(&mut out, section.read_var_u32()?);
+                    self.copy_const_expr(section, &mut out)?;
+                }
+                flags => bail!("Unsupported data segment flags 0x{flags:02x}"),
+            }
+
+            let start = section.position();
+            let len = section.read_var_u32()? as usize;
+            section.read_bytes(len)?;
+            out.extend_from_slice(section.read_since(start));
+        }
+
+        Ok(out)
+    }
+
+    fn rewrite_custom(&self, section: &mut BinaryReader) -> Option<Vec<u8>> {
+        match section.read_name() {
+            // Names are only used for debugging, so a malformed section is kept as it is
+            Ok("name") => self.rewrite_names(section).ok(),
+            _ => None,
+        }
+    }
+
+    /// Shifts the indices in the global names subsection of the name section.
+    fn rewrite_names(&self, section: &mut BinaryReader) -> Result<Vec<u8>> {
+        let mut out = Vec::new();
+        write_name(&mut out, "name");
+
+        while !section.is_empty() {
+            let id = section.read_u8()?;
+            let size = section.read_var_u32()? as usize;
+            let bytes = section.read_bytes(size)?;
+
+            if id != 7 {
+                write_section(&mut out, id, bytes);
+                continue;
+            }
+
+            let mut subsection = BinaryReader::new(bytes);
+
+            let mut content = Vec::new();
+            let count = subsection.read_var_u32()?;
+            write_var_u32(&mut content, count);
+            for _ in 0..count {
+                let index = subsection.read_var_u32()?;
+                write_var_u32(&mut content, self.global_index(index));
+                write_name(&mut content, subsection.read_name()?);
+            }
+            write_section(&mut out, id, &content);
+        }
+
+        Ok(out)
+    }
+
+    fn rewrite_code(&self, section: &mut BinaryReader) -> Result<Vec<u8>> {
+        let mut out = Vec::new();
+        let count = section.read_var_u32()?;
+        write_var_u32(&mut out, count);
+
+        for index in 0..count {
+            let size = section.read_var_u32()? as usize;
+            let body = section.read_bytes(size)?;
+
+            let body = self
+                .instrument_body(body)
+                .with_context(|| format!("Failed to instrument function body {index}"))?;
+
+            write_var_u32(&mut out, body.len() as u32);
+            out.extend_from_slice(&body);
+        }
+
+        Ok(out)
+    }
+
+    fn instrument_body(&self, body: &[u8]) -> Result<Vec<u8>> {
+        let mut reader = BinaryReader::new(body);
+        let mut out = Vec::new();
+
+        for _ in 0..reader.read_var_u32()? {
+            reader.read_var_u32()?;
+            skip_val_type(&mut reader)?;
+        }
+        out.extend_from_slice(reader.read_since(0));
+
+        // Instructions since the last check point and their cost. Like in wasmtime, the cost
+        // starts at 1, so that even empty functions consume fuel. This is on top of the cost
+        // of the caller's `call` instruction, wasmtime charges for both.
+        let mut segment = Vec::new();
+        let mut cost = 1;
+
+        // The epoch is only checked at function entries and loop headers, like in wasmtime
+        let mut check_epoch = true;
+
+        // Open blocks, `true` for loops, starting with the function body
+        let mut blocks = vec![false];
+
+        while !reader.is_empty() {
+            let opcode = self.copy_instruction(&mut reader, &mut segment)?;
+            cost += instruction_cost(opcode);
+
+            let check = match opcode {
+                LOOP => {
+                    blocks.push(true);
+                    true
+                }
+                BLOCK | IF | TRY | TRY_TABLE => {
+                    blocks.push(false);
+                    false
+                }
+                END | DELEGATE => blocks.pop().context("Unbalanced block end")?,
+                _ => false,
+            };
+
+            if check {
+                self.write_check_point(&mut out, cost, check_epoch);
+                out.append(&mut segment);
+                cost = 0;
+                check_epoch = opcode == LOOP;
+            }
+        }
+
+        self.write_check_point(&mut out, cost, check_epoch);
+        out.append(&mut segment);
+
+        Ok(out)
+    }
+
+    fn write_check_point(&self, out: &mut Vec<u8>, cost: i64, check_epoch: bool) {
+        if self.instrumentation.epoch && check_epoch {
+            self.write_epoch_check(out);
+        }
+
+        if self.instrumentation.fuel && cost > 0 {
+            self.write_fuel_check(out, cost);
+        }
+    }
+
+    /// Traps if the epoch has reached the deadline.
+    fn write_epoch_check(&self, out: &mut Vec<u8>) {
+        out.push(GLOBAL_GET);
+        write_var_u32(out, self.injected_global(EPOCH_GLOBAL));
+        out.push(GLOBAL_GET);
+        write_var_u32(out, self.injected_global(EPOCH_DEADLINE_GLOBAL));
+        out.push(I64_GE_U);
+        out.extend_from_slice(&[IF, 0x40]);
+        out.push(UNREACHABLE);
+        out.push(END);
+    }
+
+    /// Subtracts `cost` from the fuel, or traps if there is not enough fuel left.
+    fn write_fuel_check(&self, out: &mut Vec<u8>, cost: i64) {
+        let fuel = self.injected_global(FUEL_GLOBAL);
+
+        out.push(GLOBAL_GET);
+        write_var_u32(out, fuel);
+        out.push(I64_CONST);
+        write_var_i64(out, cost);
+        out.push(I64_LT_S);
+        out.extend_from_slice(&[IF, 0x40]);
+        out.push(I64_CONST);
+        write_var_i64(out, -1);
+        out.push(GLOBAL_SET);
+        write_var_u32(out, fuel);
+        out.push(UNREACHABLE);
+        out.push(END);
+
+        out.push(GLOBAL_GET);
+        write_var_u32(out, fuel);
+        out.push(I64_CONST);
+        write_var_i64(out, cost);
+        out.push(I64_SUB);
+        out.push(GLOBAL_SET);
+        write_var_u32(out, fuel);
+    }
+
+    /// Copies a constant expression, including the final `end`.
+    fn copy_const_expr(&self, reader: &mut BinaryReader, out: &mut Vec<u8>) -> Result<()> {
+        while self.copy_instruction(reader, out)? != END {}
+        Ok(())
+    }
+
+    /// Copies a single instruction with global indices remapped, returns its opcode.
+    fn copy_instruction(&self, reader: &mut BinaryReader, out: &mut Vec<u8>) -> Result<u8> {
+        let start = reader.position();
+        let opcode = reader.read_u8()?;
+
+        match opcode {
+            GLOBAL_GET | GLOBAL_SET => {
+                let index = reader.read_var_u32()?;
+                out.push(opcode);
+                write_var_u32(out, self.global_index(index));
+                return Ok(opcode);
+            }
+            0x00 | 0x01 | 0x05 | 0x0a | 0x0b | 0x0f | 0x19 | 0x1a | 0x1b | 0xd1 | 0xd3 | 0xd4 => {}
+            0x45..=0xc4 => {}
+            BLOCK | LOOP | IF | TRY => skip_block_type(reader)?,
+            TRY_TABLE => {
+                skip_block_type(reader)?;
+                for _ in 0..reader.read_var_u32()? {
+                    if reader.read_u8()? < 0x02 {
+                        reader.read_var_u32()?;
+                    }
+                    reader.read_var_u32()?;
+                }
+            }
+            0x07..=0x09 | 0x0c | 0x0d | 0x10 | 0x12 | 0x14 | 0x15 | 0x18 | 0x20..=0x22 => {
+                reader.read_var_u32()?;
+            }
+            0x25 | 0x26 | 0x3f | 0x40 | 0xd2 | 0xd5 | 0xd6 => {
+                reader.read_var_u32()?;
+            }
+            0x0e => {
+                for _ in 0..reader.read_var_u32()? {
+                    reader.read_var_u32()?;
+                }
+                reader.read_var_u32()?;
+            }
+            0x11 | 0x13 => {
+                reader.read_var_u32()?;
+                reader.read_var_u32()?;
+            }
+            0x1c => {
+                for _ in 0..reader.read_var_u32()? {
+                    skip_val_type(reader)?;
+                }
+            }
+            0x28..=0x3e => skip_mem_arg(reader)?,
+            0x41 | I64_CONST => {
+                reader.read_var_i64()?;
+            }
+            0x43 => {
+                reader.read_bytes(4)?;
+            }
+            0x44 => {
+                reader.read_bytes(8)?;
+            }
+            0xd0 => skip_heap_type(reader)?,
+            0xfc => Self::skip_misc_immediates(reader)?,
+            0xfd => Self::skip_simd_immediates(reader)?,
+            0xfe => {
+                // Atomic fence has a single zero byte, all other atomic instructions a memory argument
+                if reader.read_var_u32()? == 0x03 {
+                    reader.read_u8()?;
+                } else {
+                    skip_mem_arg(reader)?;
+                }
+            }
+            opcode => bail!("Unsupported opcode 0x{opcode:02x}"),
+        }
+
+        out.extend_from_slice(reader.read_since(start));
+        Ok(opcode)
+    }
+
+    /// Saturating truncations, bulk memory and table instructions.
+    fn skip_misc_immediates(reader: &mut BinaryReader) -> Result<()> {
+        match reader.read_var_u32()? {
+            0..=7 => {}
+            9 | 11 | 13 | 15..=17 => {
+                reader.read_var_u32()?;
+            }
+            8 | 10 | 12 | 14 => {
+                reader.read_var_u32()?;
+                reader.read_var_u32()?;
+            }
+            opcode => bail!("Unsupported opcode 0xfc {opcode}"),
+        }
+        Ok(())
+    }
+
+    fn skip_simd_immediates(reader: &mut BinaryReader) -> Result<()> {
+        match reader.read_var_u32()? {
+            0..=11 | 92 | 93 => skip_mem_arg(reader)?,
+            12 | 13 => {
+                reader.read_bytes(16)?;
+            }
+            21..=34 => {
+                reader.read_u8()?;
+            }
+            84..=91 => {
+                skip_mem_arg(reader)?;
+                reader.read_u8()?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// Fuel cost of an instruction, matches the costs in wasmtime.
+fn instruction_cost(opcode: u8) -> i64 {
+    match opcode {
+        NOP | DROP => 0,
+        BLOCK | LOOP | UNREACHABLE | RETURN | ELSE | END => 0,
+        _ => 1,
+    }
+}
+
+fn skip_val_type(reader: &mut BinaryReader) -> Result<()> {
+    // Nullable and non-nullable references to a heap type
+    if matches!(reader.read_u8()?, 0x63 | 0x64) {
+        skip_heap_type(reader)?;
+    }
+    Ok(())
+}
+
+fn skip_heap_type(reader: &mut BinaryReader) -> Result<()> {
+    reader.read_var_i64()?;
+    Ok(())
+}
+
+fn skip_block_type(reader: &mut BinaryReader) -> Result<()> {
+    // Either an empty type, a value type, or a type index encoded as a signed LEB128
+    match reader.read_u8()? {
+        0x63 | 0x64 => skip_heap_type(reader)?,
+        byte if byte & 0x80 != 0 => {
+            reader.read_var_i64()?;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn skip_mem_arg(reader: &mut BinaryReader) -> Result<()> {
+    let align = reader.read_var_u32()?;
+    // Bit 6 of the alignment means there is an explicit memory index
+    if align & 0x40 != 0 {
+        reader.read_var_u32()?;
+    }
+    reader.read_var_u64()?;
+    Ok(())
+}
+
+fn write_section(out: &mut Vec<u8>, id: u8, content: &[u8]) {
+    out.push(id);
+    write_var_u32(out, content.len() as u32);
+    out.extend_from_slice(content);
+}
+
+fn write_name(out: &mut Vec<u8>, name: &str) {
+    write_var_u32(out, name.len() as u32);
+    out.extend_from_slice(name.as_bytes());
+}
+
+fn write_var_u32(out: &mut Vec<u8>, mut value: u32) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+fn write_var_i64(out: &mut Vec<u8>, mut value: i64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+
+        let done = (value == 0 && byte & 0x40 == 0) || (value == -1 && byte & 0x40 != 0);
+        if done {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
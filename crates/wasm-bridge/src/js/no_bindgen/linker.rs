@@ -1,17 +1,27 @@
-use std::rc::Rc;
+use std::{
+    error::Error as StdError,
+    fmt::{self, Display, Formatter},
+    rc::Rc,
+};
 
+use anyhow::{bail, Context};
 use js_sys::{Array, Function, Object, Reflect};
 use wasm_bindgen::{prelude::*, JsValue};
 
-use crate::*;
+use super::{func::check_vals, instance::set_caller, instrument::add_instrument_imports};
+use crate::{helpers::host_error_to_js, *};
 
 pub struct Linker<T> {
     fns: Vec<PreparedFn<T>>,
+    externs: Vec<DefinedExtern>,
 }
 
 impl<T> Linker<T> {
     pub fn new(_engine: &Engine) -> Self {
-        Self { fns: vec![] }
+        Self {
+            fns: vec![],
+            externs: vec![],
+        }
     }
 
     #[deprecated(
@@ -20,80 +30,109 @@ impl<T> Linker<T> {
     )]
     pub fn instantiate(
         &self,
-        store: impl AsContextMut<Data = T>,
+        mut store: impl AsContextMut<Data = T>,
         module: &Module,
     ) -> Result<Instance, Error> {
-        let (imports, drop_handles) = self.collect_imports(store);
-        Instance::new_with_imports(module, &imports, drop_handles)
+        self.check_imports(module)?;
+        let store = store.as_context_mut();
+        let (imports, drop_handles, exports) = self.collect_imports(store);
+        store.add_instance()?;
+
+        let instance = add_instrument_imports(store, module, &imports)
+            .and_then(|()| {
+                Instance::new_with_imports(module, &imports, drop_handles)
+                    .map_err(|error| store.map_instrumented_trap(error))
+            })
+            .inspect_err(|_| store.remove_instance())?;
+        exports.set(&instance);
+        set_caller(self.externs.iter().map(|defined| &defined.item), &instance);
+        Ok(instance)
     }
 
     pub async fn instantiate_safe(
         &self,
-        store: impl AsContextMut<Data = T>,
+        mut store: impl AsContextMut<Data = T>,
         module: &Module,
     ) -> Result<Instance> {
-        let (imports, drop_handles) = self.collect_imports(store);
-        Instance::new_with_imports_async(module, &imports, drop_handles).await
+        self.check_imports(module)?;
+        let store = store.as_context_mut();
+        let (imports, drop_handles, exports) = self.collect_imports(store);
+        store.add_instance()?;
+
+        let instance = match add_instrument_imports(store, module, &imports) {
+            Ok(()) => Instance::new_with_imports_async(module, &imports, drop_handles)
+                .await
+                .map_err(|error| store.map_instrumented_trap(error)),
+            Err(error) => Err(error),
+        };
+        let instance = instance.inspect_err(|_| store.remove_instance())?;
+        exports.set(&instance);
+        set_caller(self.externs.iter().map(|defined| &defined.item), &instance);
+        Ok(instance)
     }
 
-    fn collect_imports(&self, store: impl AsContextMut<Data = T>) -> (Object, Vec<DropHandle>) {
-        let store = store.as_context();
+    // Reports missing imports by name, instead of the JS `LinkError` thrown by the instantiation
+    fn check_imports(&self, module: &Module) -> Result<()> {
+        match module.imports().find(|import| !self.is_defined(import)) {
+            Some(import) => Err(UnknownImportError::new(&import).into()),
+            None => Ok(()),
+        }
+    }
+
+    fn is_defined(&self, import: &ImportType) -> bool {
+        self.find_extern(import.module(), import.name()).is_some()
+            || self.find_fn(import.module(), import.name()).is_some()
+    }
 
+    fn collect_imports(&self, store: &Store<T>) -> (Object, Vec<DropHandle>, LazyExports) {
         let imports = Object::new();
         let mut drop_handles = vec![];
+        let exports = LazyExports::new();
 
         for func in self.fns.iter() {
-            let drop_handle = func.add_to_imports(&imports, store.data_handle());
+            let drop_handle = func.add_to_imports(&imports, store.data_handle(), &exports);
             drop_handles.push(drop_handle);
         }
 
-        (imports, drop_handles)
+        for defined in self.externs.iter() {
+            if let Some(drop_handle) = defined.add_to_imports(&imports) {
+                drop_handles.push(drop_handle);
+            }
+        }
+
+        (imports, drop_handles, exports)
     }
 
     pub fn func_new<F>(
         &mut self,
         module: &str,
         name: &str,
-        _ty: FuncType,
+        ty: FuncType,
         func: F,
     ) -> Result<&mut Self>
     where
         F: Fn(Caller<T>, &[Val], &mut [Val]) -> Result<()> + 'static,
         T: 'static,
     {
-        let func_rc = Rc::new(func);
-        let creator = move |handle: DataHandle<T>| {
-            let caller = Caller::new(handle);
-            let func_clone = func_rc.clone();
-
-            let closure =
-                Closure::<dyn Fn(Array) -> Result<JsValue, JsValue>>::new(move |js_args: Array| {
-                    let mut args = Vec::with_capacity(js_args.length() as _);
-                    for index in 0..args.capacity() {
-                        let js_val = Reflect::get_u32(&js_args, index as _)?;
-                        args.push(Val::from_js_value(&js_val).map_err::<JsValue, _>(|e| {
-                            format!("Cannot convert JsValue to Val: {e:}").into()
-                        })?);
-                    }
-
-                    // TODO: support different amounts of return values? HOW????
-                    let mut rets = vec![Val::I32(0)];
-
-                    func_clone(caller.clone(), &args, &mut rets).map_err::<JsValue, _>(|e| {
-                        format!("Error in imported function: {e:?}").into()
-                    })?;
-
-                    Ok(rets[0].to_js_value())
-                });
-
-            let (js_func, handler) = DropHandle::from_closure(closure);
-            let js_func = transform_dynamic_closure_arguments(js_func);
-
-            (js_func, handler)
-        };
+        let creator = make_dynamic_closure(ty.clone(), func);
 
-        self.fns
-            .push(PreparedFn::new(module, name, Box::new(creator)));
+        self.fns.push(PreparedFn::new(module, name, ty, creator));
+
+        Ok(self)
+    }
+
+    pub fn define(
+        &mut self,
+        _store: impl AsContext<Data = T>,
+        module: &str,
+        name: &str,
+        item: impl Into<Extern>,
+    ) -> Result<&mut Self> {
+        self.externs.push(DefinedExtern {
+            module: module.into(),
+            name: name.into(),
+            item: item.into(),
+        });
 
         Ok(self)
     }
@@ -106,13 +145,195 @@ impl<T> Linker<T> {
     ) -> Result<&mut Self>
     where
         F: IntoMakeClosure<T, Params, Results> + 'static,
+        Params: WasmTyList,
+        Results: WasmTyList,
     {
+        let ty = FuncType::new(&Engine::default(), Params::valtypes(), Results::valtypes());
         let creator = func.into_make_closure();
 
-        self.fns.push(PreparedFn::new(module, name, creator));
+        self.fns.push(PreparedFn::new(module, name, ty, creator));
+
+        Ok(self)
+    }
+
+    /// Defines all exports of `instance` under the `module_name` module.
+    pub fn instance(
+        &mut self,
+        _store: impl AsContextMut<Data = T>,
+        module_name: &str,
+        instance: Instance,
+    ) -> Result<&mut Self> {
+        for (name, item) in instance.extern_exports()? {
+            self.externs.push(DefinedExtern {
+                module: module_name.into(),
+                name,
+                item,
+            });
+        }
+
+        Ok(self)
+    }
+
+    #[deprecated(
+        since = "0.5.0",
+        note = "Instantiating a module synchronously can panic on the web, please use `module_safe` instead."
+    )]
+    pub fn module(
+        &mut self,
+        mut store: impl AsContextMut<Data = T>,
+        module_name: &str,
+        module: &Module,
+    ) -> Result<&mut Self>
+    where
+        T: 'static,
+    {
+        Self::check_reactor(module)?;
+
+        #[allow(deprecated)]
+        let instance = self.instantiate(&mut store, module)?;
+        Self::initialize(&mut store, &instance)?;
+
+        self.instance(store, module_name, instance)
+    }
+
+    /// Instantiates `module` and defines its exports under the `module_name` module.
+    ///
+    /// The `_initialize` function of a reactor module is called after the instantiation.
+    /// Command modules, which get a new instance for every call on sys, are not supported yet.
+    pub async fn module_safe(
+        &mut self,
+        mut store: impl AsContextMut<Data = T>,
+        module_name: &str,
+        module: &Module,
+    ) -> Result<&mut Self>
+    where
+        T: 'static,
+    {
+        Self::check_reactor(module)?;
+
+        let instance = self.instantiate_safe(&mut store, module).await?;
+        Self::initialize(&mut store, &instance)?;
+
+        self.instance(store, module_name, instance)
+    }
+
+    fn check_reactor(module: &Module) -> Result<()> {
+        if module.get_export("_start").is_some() {
+            bail!("Command modules (exporting `_start`) are not supported by `Linker::module` on the web");
+        }
+        Ok(())
+    }
+
+    fn initialize(mut store: impl AsContextMut<Data = T>, instance: &Instance) -> Result<()> {
+        if let Some(initialize) = instance.get_func(&mut store, "_initialize") {
+            initialize
+                .typed::<(), ()>(&store)?
+                .call(&mut store, ())
+                .context("Failed to call the `_initialize` function of a reactor module")?;
+        }
+        Ok(())
+    }
+
+    /// Aliases the item named `module::name` as `as_module::as_name`.
+    pub fn alias(
+        &mut self,
+        module: &str,
+        name: &str,
+        as_module: &str,
+        as_name: &str,
+    ) -> Result<&mut Self> {
+        if let Some(item) = self.find_extern(module, name) {
+            let item = item.clone();
+            self.externs.push(DefinedExtern {
+                module: as_module.into(),
+                name: as_name.into(),
+                item,
+            });
+        } else if let Some(func) = self.find_fn(module, name) {
+            let func = func.renamed(as_module, as_name);
+            self.fns.push(func);
+        } else {
+            bail!("no item named `{module}::{name}` defined");
+        }
 
         Ok(self)
     }
+
+    /// Looks up a previously defined item, returns `None` if it was not defined.
+    pub fn get(
+        &self,
+        store: impl AsContextMut<Data = T>,
+        module: &str,
+        name: &str,
+    ) -> Option<Extern> {
+        if let Some(item) = self.find_extern(module, name) {
+            return Some(item.clone());
+        }
+
+        let func = self.find_fn(module, name)?;
+        Some(Func::from_make_closure(store, &func.creator, func.ty.clone()).into())
+    }
+
+    /// Implements all function imports of `module` that are not defined yet
+    /// with a function which traps.
+    pub fn define_unknown_imports_as_traps(&mut self, module: &Module) -> Result<()>
+    where
+        T: 'static,
+    {
+        for import in module.imports() {
+            if self.is_defined(&import) {
+                continue;
+            }
+
+            if let ExternType::Func(ty) = import.ty() {
+                let error = UnknownImportError::new(&import);
+                self.func_new(import.module(), import.name(), ty, move |_, _, _| {
+                    Err(error.clone().into())
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Implements all function imports of `module` that are not defined yet
+    /// with a function that ignores its arguments and returns default values.
+    ///
+    /// Default values are either zero or null, depending on the value type.
+    pub fn define_unknown_imports_as_default_values(&mut self, module: &Module) -> Result<()>
+    where
+        T: 'static,
+    {
+        for import in module.imports() {
+            if self.is_defined(&import) {
+                continue;
+            }
+
+            if let ExternType::Func(ty) = import.ty() {
+                let defaults = ty.results().map(default_value).collect::<Vec<_>>();
+                self.func_new(import.module(), import.name(), ty, move |_, _, results| {
+                    results.clone_from_slice(&defaults);
+                    Ok(())
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    // Defined externs are added to the imports after functions, so they take precedence
+    fn find_extern(&self, module: &str, name: &str) -> Option<&Extern> {
+        self.externs
+            .iter()
+            .rev()
+            .find(|defined| defined.module == module && defined.name == name)
+            .map(|defined| &defined.item)
+    }
+
+    fn find_fn(&self, module: &str, name: &str) -> Option<&PreparedFn<T>> {
+        self.fns
+            .iter()
+            .rev()
+            .find(|func| func.module == module && func.name == name)
+    }
 }
 
 #[allow(dead_code)]
@@ -137,41 +358,220 @@ impl DropHandle {
 struct PreparedFn<T> {
     module: String,
     name: String,
-    creator: MakeClosure<T>,
+    ty: FuncType,
+    creator: Rc<MakeClosure<T>>,
 }
 
 impl<T> PreparedFn<T> {
-    fn new(module: &str, name: &str, creator: MakeClosure<T>) -> Self {
+    fn new(module: &str, name: &str, ty: FuncType, creator: MakeClosure<T>) -> Self {
         Self {
             module: module.into(),
             name: name.into(),
-            creator,
+            ty,
+            creator: Rc::new(creator),
+        }
+    }
+
+    fn renamed(&self, module: &str, name: &str) -> Self {
+        Self {
+            module: module.into(),
+            name: name.into(),
+            ty: self.ty.clone(),
+            creator: self.creator.clone(),
         }
     }
 
     #[must_use]
-    fn add_to_imports(&self, imports: &JsValue, handle: &DataHandle<T>) -> DropHandle {
-        let module = Self::module(imports, &self.module);
+    fn add_to_imports(
+        &self,
+        imports: &JsValue,
+        handle: &DataHandle<T>,
+        exports: &LazyExports,
+    ) -> DropHandle {
+        let module = import_module(imports, &self.module);
 
-        let (js_val, handler) = (self.creator)(handle.clone());
+        let (js_val, handler) = (self.creator)(handle.clone(), exports.clone());
 
         Reflect::set(&module, &self.name.as_str().into(), &js_val).expect("module is object");
 
         handler
     }
+}
+
+struct DefinedExtern {
+    module: String,
+    name: String,
+    item: Extern,
+}
+
+impl DefinedExtern {
+    #[must_use]
+    fn add_to_imports(&self, imports: &JsValue) -> Option<DropHandle> {
+        let module = import_module(imports, &self.module);
 
-    fn module(imports: &JsValue, module: &str) -> JsValue {
-        let module_str: JsValue = module.into();
-        let existing = Reflect::get(imports, &module_str).expect("imports is object");
+        Reflect::set(
+            &module,
+            &self.name.as_str().into(),
+            &self.item.to_js_value(),
+        )
+        .expect("module is object");
 
-        if existing.is_object() {
-            existing
-        } else {
-            let new_module: JsValue = Object::new().into();
-            Reflect::set(imports, &module_str, &new_module).expect("imports is object");
-            new_module
+        self.item.closures()
+    }
+}
+
+fn default_value(ty: ValType) -> Val {
+    match ty {
+        ValType::I32 => Val::I32(0),
+        ValType::I64 => Val::I64(0),
+        ValType::F32 => Val::F32(0.0_f32.to_bits()),
+        ValType::F64 => Val::F64(0.0_f64.to_bits()),
+        ValType::V128 => Val::V128(0_u128.into()),
+        ValType::Ref(ref_type) => Val::null_ref(ref_type.heap_type().clone()),
+    }
+}
+
+/// Error for an import of a module that is not defined in a [`Linker`].
+#[derive(Debug, Clone)]
+pub struct UnknownImportError {
+    module: String,
+    name: String,
+    ty: ExternType,
+}
+
+impl UnknownImportError {
+    fn new(import: &ImportType) -> Self {
+        Self {
+            module: import.module().into(),
+            name: import.name().into(),
+            ty: import.ty(),
         }
     }
+
+    /// Returns the module name that the unknown import was expected to come from.
+    pub fn module(&self) -> &str {
+        &self.module
+    }
+
+    /// Returns the field name of the module that the unknown import was expected to come from.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the type of the unknown import.
+    pub fn ty(&self) -> ExternType {
+        self.ty.clone()
+    }
+}
+
+impl Display for UnknownImportError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "missing import `{}::{}` of type ",
+            self.module, self.name
+        )?;
+
+        match &self.ty {
+            ExternType::Func(ty) => {
+                write!(f, "(")?;
+                write_val_types(f, ty.params())?;
+                write!(f, ")->")?;
+                if ty.results().len() == 1 {
+                    write_val_types(f, ty.results())
+                } else {
+                    write!(f, "(")?;
+                    write_val_types(f, ty.results())?;
+                    write!(f, ")")
+                }
+            }
+            ExternType::Global(ty) => match ty.mutability() {
+                Mutability::Const => write!(f, "global {}", ty.content()),
+                Mutability::Var => write!(f, "global mut {}", ty.content()),
+            },
+            ExternType::Table(ty) => write!(f, "table {}", ValType::Ref(ty.element().clone())),
+            ExternType::Memory(_) => write!(f, "memory"),
+        }
+    }
+}
+
+impl StdError for UnknownImportError {}
+
+fn write_val_types(f: &mut Formatter<'_>, types: impl Iterator<Item = ValType>) -> fmt::Result {
+    for (index, ty) in types.enumerate() {
+        if index > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{ty}")?;
+    }
+    Ok(())
+}
+
+pub(crate) fn import_module(imports: &JsValue, module: &str) -> JsValue {
+    let module_str: JsValue = module.into();
+    let existing = Reflect::get(imports, &module_str).expect("imports is object");
+
+    if existing.is_object() {
+        existing
+    } else {
+        let new_module: JsValue = Object::new().into();
+        Reflect::set(imports, &module_str, &new_module).expect("imports is object");
+        new_module
+    }
+}
+
+/// Creates a host function with a dynamic signature, shared by [`Linker::func_new`] and [`Func::new`].
+pub(crate) fn make_dynamic_closure<T: 'static>(
+    ty: FuncType,
+    func: impl Fn(Caller<T>, &[Val], &mut [Val]) -> Result<()> + 'static,
+) -> MakeClosure<T> {
+    let func_rc = Rc::new(func);
+    let ty_rc = Rc::new(ty);
+    let creator = move |handle: DataHandle<T>, exports: LazyExports| {
+        let caller = Caller::new(handle, exports);
+        let func_clone = func_rc.clone();
+        let ty_clone = ty_rc.clone();
+
+        let closure =
+            Closure::<dyn Fn(Array) -> Result<JsValue, JsValue>>::new(move |js_args: Array| {
+                let mut args = Vec::with_capacity(ty_clone.params().len());
+                for (index, param) in ty_clone.params().enumerate() {
+                    let js_val = Reflect::get_u32(&js_args, index as _)?;
+                    args.push(
+                        Val::from_js_typed(&js_val, &param).map_err::<JsValue, _>(|e| {
+                            format!("Cannot convert argument at index {index} to {param:?}: {e:}")
+                                .into()
+                        })?,
+                    );
+                }
+
+                let mut rets = ty_clone.results().map(|_| Val::I32(0)).collect::<Vec<_>>();
+
+                func_clone(caller.clone(), &args, &mut rets).map_err(host_error_to_js)?;
+
+                results_to_js_value(&ty_clone, &rets).map_err::<JsValue, _>(|e| {
+                    format!("Invalid results of imported function: {e:}").into()
+                })
+            });
+
+        let (js_func, handler) = DropHandle::from_closure(closure);
+        let js_func = transform_dynamic_closure_arguments(js_func);
+
+        (js_func, handler)
+    };
+
+    Box::new(creator)
+}
+
+fn results_to_js_value(ty: &FuncType, rets: &[Val]) -> Result<JsValue> {
+    check_vals("result", rets, ty.results())?;
+
+    // Multiple results are returned as an array, which is what the JS API expects
+    Ok(match rets {
+        [] => JsValue::UNDEFINED,
+        [ret] => ret.to_js_value(),
+        rets => rets.iter().map(Val::to_js_value).collect::<Array>().into(),
+    })
 }
 
 fn transform_dynamic_closure_arguments(closure: JsValue) -> JsValue {
@@ -1,64 +1,287 @@
 // From this PR https://github.com/kajacx/wasm-bridge/pull/3 by zimond
 
-use js_sys::{Reflect, Uint8Array, WebAssembly};
-use wasm_bindgen::JsValue;
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+};
 
-use crate::{helpers::map_js_error, AsContextMut, Result};
+use anyhow::bail;
+use js_sys::{Function, Object, Reflect, Uint8Array, WebAssembly};
+
+use crate::{
+    helpers::{map_js_error, static_str_to_js},
+    AsContext, AsContextMut, MemoryType, Result, Store,
+};
+
+const PAGE_SIZE: u64 = 0x10000;
 
 #[derive(Clone, Debug)]
 pub struct Memory {
     pub(crate) memory: WebAssembly::Memory,
+    ty: MemoryType,
 }
 
 impl Memory {
-    pub(crate) fn new(memory: WebAssembly::Memory) -> Self {
-        Self { memory }
+    pub fn new(mut store: impl AsContextMut, ty: MemoryType) -> Result<Self> {
+        let store = store.as_context_mut();
+        store.add_memory()?;
+        Self::create(store, ty).inspect_err(|_| store.remove_memory())
     }
 
-    // We need this for compatible signature with wasmtime
-    pub fn write(&self, _: impl AsContextMut, offset: usize, buffer: &[u8]) -> Result<()> {
-        self.write_impl(offset, buffer)
+    fn create<T>(store: &Store<T>, ty: MemoryType) -> Result<Self> {
+        Self::check_limits(store, &ty)?;
+
+        let descriptor = Object::new();
+        Reflect::set(
+            &descriptor,
+            static_str_to_js("initial"),
+            &(ty.minimum() as f64).into(),
+        )
+        .expect("descriptor is an object");
+        if let Some(maximum) = ty.maximum() {
+            Reflect::set(
+                &descriptor,
+                static_str_to_js("maximum"),
+                &(maximum as f64).into(),
+            )
+            .expect("descriptor is an object");
+        }
+        if ty.is_shared() {
+            Reflect::set(&descriptor, static_str_to_js("shared"), &true.into())
+                .expect("descriptor is an object");
+        }
+
+        let memory = WebAssembly::Memory::new(&descriptor)
+            .map_err(map_js_error("Create WebAssembly memory"))?;
+
+        Ok(Self { memory, ty })
     }
 
-    pub(crate) fn write_impl(&self, offset: usize, buffer: &[u8]) -> Result<()> {
-        let memory = self.get_memory_buffer()?;
+    /// Asks the store's limiter for the minimum size of a new memory, like wasmtime does.
+    fn check_limits<T>(store: &Store<T>, ty: &MemoryType) -> Result<()> {
+        let minimum = pages_to_bytes(ty.minimum());
+        let maximum = ty.maximum().map(pages_to_bytes);
 
-        let mem = Uint8Array::new_with_byte_offset_and_length(
-            &memory,
-            offset as u32,
-            buffer.len() as u32,
-        );
-        mem.copy_from(buffer);
+        if !store.memory_growing(0, minimum, maximum)? {
+            bail!(
+                "memory minimum size of {} pages exceeds memory limits",
+                ty.minimum()
+            );
+        }
 
         Ok(())
     }
 
+    /// Wraps an exported memory, its type is guessed from the current size if not known.
+    pub(crate) fn from_exported(memory: WebAssembly::Memory, ty: Option<MemoryType>) -> Self {
+        let ty = ty.unwrap_or_else(|| {
+            let minimum = Self::byte_length(&memory) / PAGE_SIZE;
+            MemoryType::new(minimum as u32, None)
+        });
+
+        Self { memory, ty }
+    }
+
+    pub fn ty(&self, _store: impl AsContext) -> MemoryType {
+        self.ty.clone()
+    }
+
+    /// Returns the size of the memory in pages.
+    pub fn size(&self, _store: impl AsContext) -> u64 {
+        Self::byte_length(&self.memory) / PAGE_SIZE
+    }
+
+    /// Returns the size of the memory in bytes.
+    pub fn data_size(&self, _store: impl AsContext) -> usize {
+        Self::byte_length(&self.memory) as usize
+    }
+
+    /// Grows the memory by `delta` pages, returns the previous size in pages.
+    pub fn grow(&self, mut store: impl AsContextMut, delta: u64) -> Result<u64> {
+        let store = store.as_context_mut();
+        let old_size = self.size(&*store);
+        let Some(new_size) = old_size.checked_add(delta) else {
+            bail!("Cannot grow memory by {delta} pages, the size overflows");
+        };
+
+        if let Some(maximum) = self.ty.maximum() {
+            if new_size > maximum {
+                bail!("Cannot grow memory by {delta} pages, the maximum is {maximum} pages");
+            }
+        }
+
+        let maximum = self.ty.maximum().map(pages_to_bytes);
+        if !store.memory_growing(pages_to_bytes(old_size), pages_to_bytes(new_size), maximum)? {
+            bail!("Cannot grow memory by {delta} pages, the store's resource limiter denied it");
+        }
+
+        // Not using `WebAssembly::Memory::grow`, because that panics on failure
+        let grow: Function = Reflect::get(&self.memory, static_str_to_js("grow"))
+            .map_err(map_js_error("Memory has no grow method"))?
+            .into();
+        if let Err(error) = grow.call1(&self.memory, &(delta as f64).into()) {
+            let error = map_js_error("Grow WebAssembly memory")(error);
+            store.memory_grow_failed(
+                error.context(format!("Cannot grow memory by {delta} pages")),
+            )?;
+            bail!("Cannot grow memory by {delta} pages");
+        }
+
+        Ok(old_size)
+    }
+
+    /// Returns a copy of the memory's contents.
+    ///
+    /// The data is copied out of the JS memory buffer, so prefer using [`Memory::read`]
+    /// when only a part of a large memory is needed.
+    pub fn data(&self, _store: impl AsContext) -> MemoryData {
+        MemoryData(Self::view(&self.memory).to_vec())
+    }
+
+    /// Returns a mutable copy of the memory's contents that is written back when dropped.
+    ///
+    /// The copy borrows the store, so that no guest code can change the memory
+    /// before the copy is written back.
+    ///
+    /// The whole memory is copied twice, so prefer using [`Memory::write`]
+    /// when only a part of a large memory needs to change.
+    pub fn data_mut<'a>(&self, _store: &'a mut impl AsContextMut) -> MemoryDataMut<'a> {
+        MemoryDataMut {
+            memory: self.memory.clone(),
+            data: Self::view(&self.memory).to_vec(),
+            _store: PhantomData,
+        }
+    }
+
     // We need this for compatible signature with wasmtime
-    pub fn read(&self, _: impl AsContextMut, offset: usize, buffer: &mut [u8]) -> Result<()> {
-        self.read_impl(offset, buffer)
+    pub fn write(
+        &self,
+        _: impl AsContextMut,
+        offset: usize,
+        buffer: &[u8],
+    ) -> Result<(), MemoryAccessError> {
+        self.write_impl(offset, buffer)
     }
 
-    pub(crate) fn read_impl(&self, offset: usize, buffer: &mut [u8]) -> Result<()> {
-        let memory = self.get_memory_buffer()?;
+    pub(crate) fn write_impl(&self, offset: usize, buffer: &[u8]) -> Result<(), MemoryAccessError> {
+        self.checked_view(offset, buffer.len())?.copy_from(buffer);
+        Ok(())
+    }
 
-        let mem = Uint8Array::new_with_byte_offset_and_length(
-            &memory,
-            offset as u32,
-            buffer.len() as u32,
-        );
-        mem.copy_to(buffer);
+    // We need this for compatible signature with wasmtime
+    pub fn read(
+        &self,
+        _: impl AsContext,
+        offset: usize,
+        buffer: &mut [u8],
+    ) -> Result<(), MemoryAccessError> {
+        self.read_impl(offset, buffer)
+    }
 
+    pub(crate) fn read_impl(
+        &self,
+        offset: usize,
+        buffer: &mut [u8],
+    ) -> Result<(), MemoryAccessError> {
+        self.checked_view(offset, buffer.len())?.copy_to(buffer);
         Ok(())
     }
 
-    fn get_memory_buffer(&self) -> Result<JsValue> {
-        thread_local! {
-            static BUFFER_NAME: JsValue = "buffer".into();
+    pub(crate) fn read_vec_impl(
+        &self,
+        offset: usize,
+        len: usize,
+    ) -> Result<Vec<u8>, MemoryAccessError> {
+        Ok(self.checked_view(offset, len)?.to_vec())
+    }
+
+    /// View of `len` bytes at `offset`, or an error if it does not fit into the memory.
+    fn checked_view(&self, offset: usize, len: usize) -> Result<Uint8Array, MemoryAccessError> {
+        let view = Self::view(&self.memory);
+
+        match offset.checked_add(len) {
+            Some(end) if end <= view.length() as usize => {
+                Ok(view.subarray(offset as u32, end as u32))
+            }
+            _ => Err(MemoryAccessError { _private: () }),
         }
+    }
+
+    // The buffer is detached when the memory grows, so a new view must be made every time
+    fn view(memory: &WebAssembly::Memory) -> Uint8Array {
+        Uint8Array::new(&memory.buffer())
+    }
+
+    fn byte_length(memory: &WebAssembly::Memory) -> u64 {
+        Self::view(memory).length() as u64
+    }
+}
+
+/// Size of `pages` in bytes, `usize::MAX` if it does not fit.
+fn pages_to_bytes(pages: u64) -> usize {
+    pages
+        .checked_mul(PAGE_SIZE)
+        .and_then(|bytes| usize::try_from(bytes).ok())
+        .unwrap_or(usize::MAX)
+}
+
+/// Error for out of bounds [`Memory`] access.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryAccessError {
+    // Keep this private so that it can't be created outside of this module
+    _private: (),
+}
+
+impl Display for MemoryAccessError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "out of bounds memory access")
+    }
+}
+
+impl Error for MemoryAccessError {}
+
+/// Copy of a memory's contents, returned by [`Memory::data`].
+#[derive(Clone, Debug)]
+pub struct MemoryData(Vec<u8>);
+
+impl Deref for MemoryData {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// Mutable copy of a memory's contents, returned by [`Memory::data_mut`].
+///
+/// The contents are written back to the memory when this is dropped.
+#[derive(Debug)]
+pub struct MemoryDataMut<'a> {
+    memory: WebAssembly::Memory,
+    data: Vec<u8>,
+    _store: PhantomData<&'a mut ()>,
+}
+
+impl Deref for MemoryDataMut<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        &self.data
+    }
+}
+
+impl DerefMut for MemoryDataMut<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.data
+    }
+}
 
-        BUFFER_NAME.with(|buffer_name| {
-            Reflect::get(&self.memory, buffer_name)
-                .map_err(map_js_error("Memory has no buffer field"))
-        })
+impl Drop for MemoryDataMut<'_> {
+    fn drop(&mut self) {
+        Memory::view(&self.memory)
+            .subarray(0, self.data.len() as u32)
+            .copy_from(&self.data);
     }
 }
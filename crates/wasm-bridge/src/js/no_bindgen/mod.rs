@@ -1,6 +1,14 @@
 mod module;
 pub use module::*;
 
+mod binary_reader;
+mod instrument;
+mod module_info;
+mod module_stream;
+
+mod backtrace;
+pub use backtrace::*;
+
 mod linker;
 pub use linker::*;
 
@@ -19,5 +27,17 @@ pub use memory::*;
 mod val;
 pub use val::*;
 
+mod extern_ref;
+pub use extern_ref::*;
+
 mod types;
 pub use types::*;
+
+mod global;
+pub use global::*;
+
+mod table;
+pub use table::*;
+
+mod externals;
+pub use externals::*;
@@ -1,13 +1,23 @@
-use std::borrow::Cow;
+use std::{borrow::Cow, rc::Rc};
 
-use crate::{helpers::map_js_error, *};
+use super::{
+    instrument::{injected_imports, instrument, Instrumentation, Instrumented, INSTRUMENT_MODULE},
+    module_info::ModuleInfo,
+    module_stream::compile_stream,
+};
+
+use crate::{
+    helpers::{read_stream, response_body},
+    *,
+};
 use anyhow::bail;
-use js_sys::{Uint8Array, WebAssembly};
-use wasm_bindgen_futures::JsFuture;
+use js_sys::WebAssembly;
+use web_sys::{ReadableStream, Response};
 
 #[derive(Clone, Debug)]
 pub struct Module {
     pub(crate) module: WebAssembly::Module,
+    pub(crate) info: Rc<ModuleInfo>,
 }
 
 impl Module {
@@ -15,26 +25,81 @@ impl Module {
         since = "0.4.0",
         note = "Compiling a module synchronously can panic on the web, please use `new_safe` instead."
     )]
-    pub fn new(_engine: &Engine, bytes: impl AsRef<[u8]>) -> Result<Self> {
+    pub fn new(engine: &Engine, bytes: impl AsRef<[u8]>) -> Result<Self> {
         let bytes = Self::resolve_bytes(bytes.as_ref())?;
-        Self::from_bytes(&bytes)
+        let instrumentation = Instrumentation::new(engine.config());
+        let entry = CacheEntry::new(engine, &bytes, &instrumentation.cache_key_suffix());
+        if let Some(module) = entry.get() {
+            return Self::from_cached(module, &bytes, instrumentation);
+        }
+
+        let instrumented = instrument(&bytes, instrumentation)?;
+        let module = compile_bytes(
+            &instrumented.bytes,
+            "Failed to synchronously compile bytes to a WASM module",
+        )?;
+        entry.insert(&module);
+
+        Ok(Self::from_compiled(module, instrumented))
     }
 
-    pub async fn new_safe(_engine: &Engine, bytes: impl AsRef<[u8]>) -> Result<Self> {
+    pub async fn new_safe(engine: &Engine, bytes: impl AsRef<[u8]>) -> Result<Self> {
         let bytes = Self::resolve_bytes(bytes.as_ref())?;
-        Self::from_bytes_async(&bytes).await
+        let instrumentation = Instrumentation::new(engine.config());
+        let entry = CacheEntry::new(engine, &bytes, &instrumentation.cache_key_suffix());
+        if let Some(module) = entry.get() {
+            return Self::from_cached(module, &bytes, instrumentation);
+        }
+
+        let instrumented = instrument(&bytes, instrumentation)?;
+        let module = compile_bytes_async(
+            &instrumented.bytes,
+            "Failed to asynchronously compile bytes to a WASM module",
+        )
+        .await?;
+        entry.insert(&module);
+
+        Ok(Self::from_compiled(module, instrumented))
+    }
+
+    /// Compiles a module from a fetch response while it is being downloaded,
+    /// using `WebAssembly.compileStreaming`.
+    ///
+    /// The module bytes are not copied to Rust memory, so large modules do not need
+    /// twice the memory to load. The response must contain a binary module, not text.
+    /// Modules compiled this way are not stored in the [`ModuleCache`].
+    ///
+    /// When fuel or epoch interruption is enabled, the code has to be instrumented,
+    /// so the whole response is read first, just like with [`Module::new_safe`].
+    pub async fn from_response(engine: &Engine, response: &Response) -> Result<Self> {
+        Self::from_stream(engine, &response_body(response)?).await
+    }
+
+    /// Same as [`Module::from_response`], but reads the module from a stream of `Uint8Array` chunks.
+    pub async fn from_stream(engine: &Engine, stream: &ReadableStream) -> Result<Self> {
+        let instrumentation = Instrumentation::new(engine.config());
+
+        // The function bodies are rewritten when instrumenting the code, so they are needed in Rust
+        if instrumentation.fuel || instrumentation.epoch {
+            let bytes = read_stream(stream).await?;
+            return Self::new_safe(engine, bytes).await;
+        }
+
+        let (module, info) = compile_stream(stream, instrumentation).await?;
+        Ok(Self::from_info(module, info))
     }
 
-    fn resolve_bytes(bytes: &[u8]) -> Result<Cow<[u8]>> {
+    /// Returns the module binary, parsing it first if it is text.
+    fn resolve_bytes(bytes: &[u8]) -> Result<Cow<'_, [u8]>> {
         if bytes.is_empty() {
             bail!("Cannot create a module from empty bytes")
         }
 
-        if let Ok(text) = std::str::from_utf8(bytes) {
-            Ok(Cow::Owned(Self::parse_wat(text)?))
+        Ok(if let Ok(text) = std::str::from_utf8(bytes) {
+            Cow::Owned(Self::parse_wat(text)?)
         } else {
-            Ok(Cow::Borrowed(bytes))
-        }
+            Cow::Borrowed(bytes)
+        })
     }
 
     #[cfg(feature = "wat")]
@@ -47,26 +112,60 @@ impl Module {
         bail!("Module bytes are valid text, try enabling the 'wat' feature to parse it")
     }
 
-    fn from_bytes(bytes: &[u8]) -> Result<Self> {
-        let byte_array = Uint8Array::from(bytes);
+    /// Creates a module taken from the cache, `bytes` are the original binary.
+    ///
+    /// The instrumented binary is not known, so the offsets of the function bodies are not either.
+    fn from_cached(
+        module: WebAssembly::Module,
+        bytes: &[u8],
+        instrumentation: Instrumentation,
+    ) -> Result<Self> {
+        let mut info = ModuleInfo::new(bytes);
+        info.injected_imports = injected_imports(bytes, instrumentation)?;
+        if !info.injected_imports.is_empty() {
+            info.func_bodies.clear();
+        }
 
-        let module = WebAssembly::Module::new(&byte_array.into()).map_err(map_js_error(
-            "Failed to synchronously compile bytes to a WASM module",
-        ))?;
+        Ok(Self::from_info(module, info))
+    }
 
-        Ok(Self { module })
+    fn from_compiled(module: WebAssembly::Module, instrumented: Instrumented) -> Self {
+        let mut info = ModuleInfo::new(&instrumented.bytes);
+        info.injected_imports = instrumented.injected_imports;
+        Self::from_info(module, info)
     }
 
-    async fn from_bytes_async(bytes: &[u8]) -> Result<Self> {
-        let byte_array = Uint8Array::from(bytes);
+    /// Creates the module, `info.injected_imports` must be already set.
+    fn from_info(module: WebAssembly::Module, mut info: ModuleInfo) -> Self {
+        // The injected imports are provided by the store, not by the user
+        info.imports
+            .retain(|(module, _, _)| module != INSTRUMENT_MODULE);
 
-        let promise = WebAssembly::compile(&byte_array);
-        let module = JsFuture::from(promise).await.map_err(map_js_error(
-            "Failed to asynchronously compile bytes to a WASM module",
-        ))?;
+        Self {
+            module,
+            info: ModuleInfo::register(info),
+        }
+    }
 
-        Ok(Self {
-            module: module.into(),
-        })
+    pub fn name(&self) -> Option<&str> {
+        self.info.name.as_deref()
+    }
+
+    pub fn imports(&self) -> impl ExactSizeIterator<Item = ImportType<'_>> + '_ {
+        self.info
+            .imports
+            .iter()
+            .map(|(module, name, ty)| ImportType::new(module, name, ty))
+    }
+
+    pub fn exports(&self) -> impl ExactSizeIterator<Item = ExportType<'_>> + '_ {
+        self.info
+            .exports
+            .iter()
+            .map(|(name, ty)| ExportType::new(name, ty))
+    }
+
+    pub fn get_export(&self, name: &str) -> Option<ExternType> {
+        self.info.export_type(name).cloned()
     }
 }
@@ -0,0 +1,375 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    ops::Range,
+    rc::{Rc, Weak},
+};
+
+use anyhow::{bail, Context};
+
+use super::binary_reader::BinaryReader;
+use crate::*;
+
+/// Information about a module read from its binary.
+///
+/// The JS API only reports the kinds of imports and exports, so the binary
+/// has to be parsed to get the full types, and the function names for backtraces.
+#[derive(Debug, Default)]
+pub(crate) struct ModuleInfo {
+    pub(crate) imports: Vec<(String, String, ExternType)>,
+    pub(crate) exports: Vec<(String, ExternType)>,
+    /// Imports added by the instrumentation, provided by the store instead of the user
+    pub(crate) injected_imports: Vec<(String, ExternType)>,
+
+    pub(crate) name: Option<String>,
+    func_names: HashMap<u32, String>,
+    imported_funcs: u32,
+    /// Offsets of the defined functions' bodies in the binary
+    pub(crate) func_bodies: Vec<Range<usize>>,
+}
+
+thread_local! {
+    // All loaded modules, used to find the functions in stack traces
+    static MODULES: RefCell<Vec<Weak<ModuleInfo>>> = const { RefCell::new(Vec::new()) };
+
+    // Loaded modules by the url of their frames in stack traces
+    static MODULE_URLS: RefCell<HashMap<String, Weak<ModuleInfo>>> = RefCell::new(HashMap::new());
+}
+
+/// Prefix of the module urls in V8, followed by `<module name>-<hash>` or just `<hash>`.
+const MODULE_URL_PREFIX: &str = "wasm://wasm/";
+
+impl ModuleInfo {
+    pub(crate) fn register(info: Self) -> Rc<Self> {
+        let info = Rc::new(info);
+
+        MODULES.with(|modules| {
+            let mut modules = modules.borrow_mut();
+            modules.retain(|module| module.strong_count() > 0);
+            modules.push(Rc::downgrade(&info));
+        });
+
+        info
+    }
+
+    /// Finds the loaded module of the stack frames with this url,
+    /// `frames` are the function indices and offsets of all those frames.
+    ///
+    /// The url contains a hash of the module's binary, which cannot be computed here.
+    /// So the first time a url is seen, the module is found by its functions, and only
+    /// if there is a single match. Modules with the same layout of functions would
+    /// give the wrong names otherwise.
+    pub(crate) fn find_module(url: &str, frames: &[(u32, usize)]) -> Option<Rc<Self>> {
+        let known = MODULE_URLS.with(|urls| urls.borrow().get(url).and_then(Weak::upgrade));
+        if known.is_some() {
+            return known;
+        }
+
+        // V8 puts the module's name in the url, if it has one
+        let url_name = url
+            .strip_prefix(MODULE_URL_PREFIX)
+            .map(|rest| rest.rsplit_once('-').map(|(name, _)| name));
+
+        let candidates = MODULES.with(|modules| {
+            modules
+                .borrow()
+                .iter()
+                .filter_map(Weak::upgrade)
+                .filter(|module| url_name.is_none_or(|name| module.name.as_deref() == name))
+                .filter(|module| module.has_frames(frames))
+                .collect::<Vec<_>>()
+        });
+
+        // The same module can be loaded more than once, those are still a single match
+        let first = candidates.first()?;
+        if !candidates
+            .iter()
+            .all(|other| other.symbolizes_like(first, frames))
+        {
+            return None;
+        }
+
+        // Other urls are not unique to a module, for example in SpiderMonkey
+        if url_name.is_some() && candidates.len() == 1 {
+            MODULE_URLS.with(|urls| {
+                let mut urls = urls.borrow_mut();
+                urls.retain(|_, module| module.strong_count() > 0);
+                urls.insert(url.to_owned(), Rc::downgrade(first));
+            });
+        }
+
+        Some(first.clone())
+    }
+
+    fn has_frames(&self, frames: &[(u32, usize)]) -> bool {
+        frames.iter().all(|(func_index, module_offset)| {
+            self.func_body(*func_index)
+                .is_some_and(|body| body.contains(module_offset))
+        })
+    }
+
+    fn symbolizes_like(&self, other: &Self, frames: &[(u32, usize)]) -> bool {
+        self.name == other.name
+            && frames.iter().all(|(func_index, _)| {
+                self.func_name(*func_index) == other.func_name(*func_index)
+                    && self.func_body(*func_index) == other.func_body(*func_index)
+            })
+    }
+
+    pub(crate) fn export_type(&self, name: &str) -> Option<&ExternType> {
+        self.exports
+            .iter()
+            .find(|(export_name, _)| export_name == name)
+            .map(|(_, ty)| ty)
+    }
+
+    pub(crate) fn func_name(&self, func_index: u32) -> Option<&str> {
+        self.func_names.get(&func_index).map(String::as_str)
+    }
+
+    pub(crate) fn func_body(&self, func_index: u32) -> Option<&Range<usize>> {
+        let defined_index = func_index.checked_sub(self.imported_funcs)?;
+        self.func_bodies.get(defined_index as usize)
+    }
+
+    /// Reads the information from the binary, like [`Self::parse`].
+    ///
+    /// The browser can compile modules that the parser doesn't support, for example
+    /// ones with GC types. Those modules still load, but without the types of their imports
+    /// and exports, and without function names for backtraces.
+    pub(crate) fn new(bytes: &[u8]) -> Self {
+        Self::parse(bytes).unwrap_or_default()
+    }
+
+    pub(crate) fn parse(bytes: &[u8]) -> Result<Self> {
+        let mut reader = BinaryReader::new(bytes);
+
+        if reader.read_bytes(8)? != b"\0asm\x01\0\0\0" {
+            bail!("Not a WebAssembly core module binary");
+        }
+
+        let mut types = Vec::<FuncType>::new();
+        let mut imports = Vec::new();
+
+        // Index spaces, including the imported items
+        let mut funcs = Vec::<FuncType>::new();
+        let mut tables = Vec::<TableType>::new();
+        let mut memories = Vec::<MemoryType>::new();
+        let mut globals = Vec::<GlobalType>::new();
+
+        let mut exports = Vec::new();
+        let mut imported_funcs = 0;
+        let mut func_bodies = Vec::new();
+        let mut names = None;
+
+        while !reader.is_empty() {
+            let id = reader.read_u8()?;
+            let size = reader.read_var_u32()? as usize;
+            let section_start = reader.position();
+            let mut section = BinaryReader::new(reader.read_bytes(size)?);
+
+            match id {
+                // Custom section
+                0 => {
+                    if section.read_name()? == "name" {
+                        // Names are only used for debugging, so a malformed section is ignored
+                        names = Self::read_names(&mut section).ok();
+                    }
+                }
+                // Type section
+                1 => {
+                    for _ in 0..section.read_var_u32()? {
+                        types.push(Self::read_func_type(&mut section)?);
+                    }
+                }
+                // Import section
+                2 => {
+                    for _ in 0..section.read_var_u32()? {
+                        let module = section.read_name()?.to_owned();
+                        let name = section.read_name()?.to_owned();
+
+                        let ty = match section.read_u8()? {
+                            0x00 => {
+                                let ty = Self::func_type(&types, &mut section)?;
+                                funcs.push(ty.clone());
+                                ExternType::Func(ty)
+                            }
+                            0x01 => {
+                                let ty = Self::read_table_type(&mut section)?;
+                                tables.push(ty.clone());
+                                ExternType::Table(ty)
+                            }
+                            0x02 => {
+                                let ty = Self::read_memory_type(&mut section)?;
+                                memories.push(ty.clone());
+                                ExternType::Memory(ty)
+                            }
+                            0x03 => {
+                                let ty = Self::read_global_type(&mut section)?;
+                                globals.push(ty.clone());
+                                ExternType::Global(ty)
+                            }
+                            // Exception tags have no extern type, they are left out
+                            0x04 => {
+                                section.read_u8()?;
+                                section.read_var_u32()?;
+                                continue;
+                            }
+                            kind => bail!("Unsupported import kind 0x{kind:02x}"),
+                        };
+
+                        imports.push((module, name, ty));
+                    }
+                    imported_funcs = funcs.len() as u32;
+                }
+                // Function section
+                3 => {
+                    for _ in 0..section.read_var_u32()? {
+                        funcs.push(Self::func_type(&types, &mut section)?);
+                    }
+                }
+                // Table section
+                4 => {
+                    for _ in 0..section.read_var_u32()? {
+                        tables.push(Self::read_table_type(&mut section)?);
+                    }
+                }
+                // Memory section
+                5 => {
+                    for _ in 0..section.read_var_u32()? {
+                        memories.push(Self::read_memory_type(&mut section)?);
+                    }
+                }
+                // Global section
+                6 => {
+                    for _ in 0..section.read_var_u32()? {
+                        globals.push(Self::read_global_type(&mut section)?);
+                        section.skip_const_expr()?;
+                    }
+                }
+                // Export section
+                7 => {
+                    for _ in 0..section.read_var_u32()? {
+                        let name = section.read_name()?.to_owned();
+                        let kind = section.read_u8()?;
+                        let index = section.read_var_u32()? as usize;
+
+                        let ty = match kind {
+                            0x00 => funcs.get(index).cloned().map(ExternType::Func),
+                            0x01 => tables.get(index).cloned().map(ExternType::Table),
+                            0x02 => memories.get(index).cloned().map(ExternType::Memory),
+                            0x03 => globals.get(index).cloned().map(ExternType::Global),
+                            0x04 => continue,
+                            kind => bail!("Unsupported export kind 0x{kind:02x}"),
+                        }
+                        .with_context(|| format!("Export '{name}' has an invalid index"))?;
+
+                        exports.push((name, ty));
+                    }
+                }
+                // Code section
+                10 => {
+                    for _ in 0..section.read_var_u32()? {
+                        let body_size = section.read_var_u32()? as usize;
+                        let body_start = section_start + section.position();
+                        section.read_bytes(body_size)?;
+                        func_bodies.push(body_start..body_start + body_size);
+                    }
+                }
+                // Other sections do not affect the imports and exports
+                _ => {}
+            }
+        }
+
+        let (name, func_names) = names.unwrap_or_default();
+
+        Ok(Self {
+            imports,
+            exports,
+            injected_imports: Vec::new(),
+            name,
+            func_names,
+            imported_funcs,
+            func_bodies,
+        })
+    }
+
+    /// Reads the module name and the function names from the name section.
+    fn read_names(reader: &mut BinaryReader) -> Result<(Option<String>, HashMap<u32, String>)> {
+        let mut module_name = None;
+        let mut func_names = HashMap::new();
+
+        while !reader.is_empty() {
+            let id = reader.read_u8()?;
+            let size = reader.read_var_u32()? as usize;
+            let mut subsection = BinaryReader::new(reader.read_bytes(size)?);
+
+            match id {
+                0 => module_name = Some(subsection.read_name()?.to_owned()),
+                1 => {
+                    for _ in 0..subsection.read_var_u32()? {
+                        let index = subsection.read_var_u32()?;
+                        let name = subsection.read_name()?;
+                        func_names.insert(index, name.to_owned());
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok((module_name, func_names))
+    }
+
+    fn read_func_type(reader: &mut BinaryReader) -> Result<FuncType> {
+        let form = reader.read_u8()?;
+        if form != 0x60 {
+            bail!("Unsupported type form 0x{form:02x}");
+        }
+
+        let params = Self::read_val_types(reader)?;
+        let results = Self::read_val_types(reader)?;
+
+        Ok(FuncType::new(&Engine::default(), params, results))
+    }
+
+    fn read_val_types(reader: &mut BinaryReader) -> Result<Vec<ValType>> {
+        (0..reader.read_var_u32()?)
+            .map(|_| reader.read_val_type())
+            .collect()
+    }
+
+    fn func_type(types: &[FuncType], reader: &mut BinaryReader) -> Result<FuncType> {
+        let index = reader.read_var_u32()? as usize;
+        types
+            .get(index)
+            .cloned()
+            .with_context(|| format!("Function type index {index} is out of bounds"))
+    }
+
+    pub(crate) fn read_table_type(reader: &mut BinaryReader) -> Result<TableType> {
+        let element = reader.read_ref_type()?;
+        let (minimum, maximum, _) = reader.read_limits()?;
+
+        Ok(TableType::new(
+            element,
+            u32::try_from(minimum)?,
+            maximum.map(u32::try_from).transpose()?,
+        ))
+    }
+
+    pub(crate) fn read_memory_type(reader: &mut BinaryReader) -> Result<MemoryType> {
+        let (minimum, maximum, flags) = reader.read_limits()?;
+        Ok(MemoryType::from_limits(minimum, maximum, flags))
+    }
+
+    fn read_global_type(reader: &mut BinaryReader) -> Result<GlobalType> {
+        let content = reader.read_val_type()?;
+        let mutability = match reader.read_u8()? {
+            0x00 => Mutability::Const,
+            0x01 => Mutability::Var,
+            byte => bail!("Invalid global mutability 0x{byte:02x}"),
+        };
+
+        Ok(GlobalType::new(content, mutability))
+    }
+}
@@ -0,0 +1,281 @@
+use std::{cell::RefCell, rc::Rc};
+
+use anyhow::{bail, Context};
+use js_sys::{Object, Promise, Reflect, Uint8Array, WebAssembly};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    Headers, ReadableStream, ReadableStreamDefaultController, ReadableStreamDefaultReader,
+    Response, ResponseInit,
+};
+
+use super::{
+    binary_reader::BinaryReader,
+    instrument::{instrument, Instrumentation},
+    module_info::ModuleInfo,
+};
+use crate::{
+    helpers::{map_js_error, read_chunk, static_str_to_js, stream_reader},
+    Result,
+};
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = WebAssembly, js_name = compileStreaming)]
+    fn web_assembly_compile_streaming(source: &Response) -> Promise;
+}
+
+const CODE_SECTION: u8 = 10;
+const DATA_SECTION: u8 = 11;
+
+/// Compiles a module binary while it is being read from the stream.
+///
+/// The sections before the code are small, so they are read and instrumented first.
+/// The function bodies and data segments are then passed to the compiler as they arrive,
+/// without copying them to Rust memory. The instrumentation must not rewrite the code.
+pub(crate) async fn compile_stream(
+    stream: &ReadableStream,
+    instrumentation: Instrumentation,
+) -> Result<(WebAssembly::Module, ModuleInfo)> {
+    let (output, controller) = compiler_input()?;
+
+    let headers = Headers::new().map_err(map_js_error("Create response headers"))?;
+    headers
+        .set("Content-Type", "application/wasm")
+        .map_err(map_js_error("Set the response content type"))?;
+    let init = ResponseInit::new();
+    init.set_headers(&headers);
+    let response = Response::new_with_opt_readable_stream_and_init(Some(&output), &init)
+        .map_err(map_js_error("Create a response for the compiler"))?;
+
+    // The compilation runs in the background while the stream is read
+    let compiled = JsFuture::from(web_assembly_compile_streaming(&response));
+
+    let mut streamer = ModuleStreamer {
+        reader: stream_reader(stream),
+        output: controller,
+        chunk: Uint8Array::new_with_length(0),
+        position: 0,
+        written: 0,
+        compiler_stopped: false,
+    };
+
+    let info = match streamer.run(instrumentation).await {
+        Ok(info) => info,
+        Err(error) => {
+            streamer.output.error_with_e(&format!("{error:?}").into());
+
+            // The compiler stops reading when the module is invalid, that error is more useful
+            if streamer.compiler_stopped {
+                compiled
+                    .await
+                    .map_err(map_js_error("Failed to compile a streamed WASM module"))?;
+            }
+            return Err(error);
+        }
+    };
+
+    let module = compiled
+        .await
+        .map_err(map_js_error("Failed to compile a streamed WASM module"))?;
+
+    Ok((module.into(), info))
+}
+
+/// Creates a stream that the module bytes can be pushed into.
+fn compiler_input() -> Result<(ReadableStream, ReadableStreamDefaultController)> {
+    let controller = Rc::new(RefCell::new(None));
+
+    // The start callback is called from the stream's constructor
+    let start = {
+        let controller = controller.clone();
+        Closure::<dyn FnMut(ReadableStreamDefaultController)>::new(move |value| {
+            *controller.borrow_mut() = Some(value);
+        })
+    };
+
+    let source = Object::new();
+    Reflect::set(&source, static_str_to_js("start"), start.as_ref())
+        .map_err(map_js_error("Set the stream's start callback"))?;
+
+    let stream = ReadableStream::new_with_underlying_source(&source)
+        .map_err(map_js_error("Create a stream for the compiler"))?;
+    let controller = controller
+        .take()
+        .context("The stream for the compiler was not started")?;
+
+    Ok((stream, controller))
+}
+
+struct ModuleStreamer {
+    reader: ReadableStreamDefaultReader,
+    output: ReadableStreamDefaultController,
+    /// The input chunk being read and the position in it
+    chunk: Uint8Array,
+    position: u32,
+    /// Number of bytes passed to the compiler
+    written: usize,
+    compiler_stopped: bool,
+}
+
+impl ModuleStreamer {
+    async fn run(&mut self, instrumentation: Instrumentation) -> Result<ModuleInfo> {
+        let mut prefix = self.read_bytes(8).await?;
+
+        let mut next_section = self.read_section_header().await?;
+        while let Some((id, size, header)) = &next_section {
+            if matches!(*id, CODE_SECTION | DATA_SECTION) {
+                break;
+            }
+
+            prefix.extend_from_slice(header);
+            prefix.extend(self.read_bytes(*size).await?);
+            next_section = self.read_section_header().await?;
+        }
+
+        // Only the other sections are kept, the bodies are recorded for backtraces
+        let instrumented = instrument(&prefix, instrumentation)?;
+        let mut skeleton = instrumented.bytes;
+        let mut func_bodies = Vec::new();
+        self.write(&skeleton)?;
+
+        while let Some((id, size, header)) = next_section {
+            self.write(&header)?;
+
+            match id {
+                CODE_SECTION => {
+                    let (count, count_bytes) = self.read_var_u32().await?;
+                    self.write(&count_bytes)?;
+
+                    for _ in 0..count {
+                        let (body_size, body_size_bytes) = self.read_var_u32().await?;
+                        self.write(&body_size_bytes)?;
+
+                        func_bodies.push(self.written..self.written + body_size as usize);
+                        self.forward(body_size as usize).await?;
+                    }
+                }
+                DATA_SECTION => self.forward(size).await?,
+                _ => {
+                    let content = self.read_bytes(size).await?;
+                    self.write(&content)?;
+
+                    skeleton.extend(header);
+                    skeleton.extend(content);
+                }
+            }
+
+            next_section = self.read_section_header().await?;
+        }
+
+        self.output
+            .close()
+            .map_err(map_js_error("Close the stream for the compiler"))?;
+
+        // The bodies are only useful with the function indices from a successful parse
+        let mut info = match ModuleInfo::parse(&skeleton) {
+            Ok(info) => ModuleInfo {
+                func_bodies,
+                ..info
+            },
+            Err(_) => ModuleInfo::default(),
+        };
+        info.injected_imports = instrumented.injected_imports;
+        Ok(info)
+    }
+
+    /// Makes sure that there are unread bytes in the current chunk,
+    /// returns `false` at the end of the input.
+    async fn fill_chunk(&mut self) -> Result<bool> {
+        while self.position >= self.chunk.length() {
+            match read_chunk(&self.reader).await? {
+                Some(chunk) => {
+                    self.chunk = chunk;
+                    self.position = 0;
+                }
+                None => return Ok(false),
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Takes up to `len` bytes from the current chunk.
+    async fn take_chunk(&mut self, len: usize) -> Result<Uint8Array> {
+        if !self.fill_chunk().await? {
+            bail!("Unexpected end of WebAssembly binary");
+        }
+
+        let available = self.chunk.length() - self.position;
+        let end = self.position + available.min(len.try_into().unwrap_or(u32::MAX));
+
+        let bytes = self.chunk.subarray(self.position, end);
+        self.position = end;
+        Ok(bytes)
+    }
+
+    /// Copies the next bytes of the input to Rust memory.
+    async fn read_bytes(&mut self, len: usize) -> Result<Vec<u8>> {
+        let mut bytes = Vec::with_capacity(len);
+        while bytes.len() < len {
+            bytes.extend(self.take_chunk(len - bytes.len()).await?.to_vec());
+        }
+
+        Ok(bytes)
+    }
+
+    /// Reads a LEB128 encoded u32, returns it together with its encoding.
+    async fn read_var_u32(&mut self) -> Result<(u32, Vec<u8>)> {
+        let mut bytes = Vec::new();
+        loop {
+            let byte = self.read_bytes(1).await?[0];
+            bytes.push(byte);
+
+            if byte & 0x80 == 0 || bytes.len() >= 5 {
+                break;
+            }
+        }
+
+        let value = BinaryReader::new(&bytes).read_var_u32()?;
+        Ok((value, bytes))
+    }
+
+    /// Reads the section id and size, returns them together with their encoding.
+    async fn read_section_header(&mut self) -> Result<Option<(u8, usize, Vec<u8>)>> {
+        if !self.fill_chunk().await? {
+            return Ok(None);
+        }
+
+        let mut header = self.read_bytes(1).await?;
+        let (size, size_bytes) = self.read_var_u32().await?;
+        header.extend(size_bytes);
+
+        Ok(Some((header[0], size as usize, header)))
+    }
+
+    /// Passes the bytes to the compiler.
+    fn write(&mut self, bytes: &[u8]) -> Result<()> {
+        self.write_js(&Uint8Array::from(bytes))
+    }
+
+    fn write_js(&mut self, bytes: &Uint8Array) -> Result<()> {
+        if let Err(error) = self.output.enqueue_with_chunk(bytes) {
+            self.compiler_stopped = true;
+            return Err(map_js_error("Pass the module bytes to the compiler")(error));
+        }
+
+        self.written += bytes.length() as usize;
+        Ok(())
+    }
+
+    /// Passes the next bytes of the input to the compiler without copying them to Rust memory.
+    async fn forward(&mut self, mut len: usize) -> Result<()> {
+        while len > 0 {
+            let bytes = self.take_chunk(len).await?;
+            len -= bytes.length() as usize;
+            self.write_js(&bytes)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,181 @@
+use std::{cell::RefCell, rc::Rc};
+
+use anyhow::bail;
+use js_sys::{Object, Reflect, WebAssembly};
+use wasm_bindgen::{JsCast, JsValue};
+
+use crate::{
+    helpers::{map_js_error, static_str_to_js},
+    *,
+};
+
+#[derive(Clone, Debug)]
+pub struct Table {
+    table: WebAssembly::Table,
+    ty: TableType,
+    /// Closures of the functions stored in the table, shared by all clones of this table
+    closures: Rc<RefCell<Vec<DropHandles>>>,
+}
+
+impl Table {
+    pub fn new(mut store: impl AsContextMut, ty: TableType, init: Ref) -> Result<Self> {
+        let store = store.as_context_mut();
+        store.add_table()?;
+        Self::create(&mut *store, ty, init).inspect_err(|_| store.remove_table())
+    }
+
+    fn create<T>(store: &mut Store<T>, ty: TableType, init: Ref) -> Result<Self> {
+        Self::check_limits(store, &ty)?;
+
+        let descriptor = Object::new();
+        Reflect::set(
+            &descriptor,
+            static_str_to_js("element"),
+            &ty.element().to_js_str().into(),
+        )
+        .expect("descriptor is an object");
+        Reflect::set(
+            &descriptor,
+            static_str_to_js("initial"),
+            &ty.minimum().into(),
+        )
+        .expect("descriptor is an object");
+        if let Some(maximum) = ty.maximum() {
+            Reflect::set(&descriptor, static_str_to_js("maximum"), &maximum.into())
+                .expect("descriptor is an object");
+        }
+
+        let table = WebAssembly::Table::new(&descriptor)
+            .map_err(map_js_error("Create WebAssembly table"))?;
+
+        let table = Self {
+            table,
+            ty,
+            closures: Default::default(),
+        };
+        table.fill(store, 0, &init, table.ty.minimum())?;
+
+        Ok(table)
+    }
+
+    /// Asks the store's limiter for the minimum size of a new table, like wasmtime does.
+    fn check_limits<T>(store: &Store<T>, ty: &TableType) -> Result<()> {
+        if !store.table_growing(0, ty.minimum(), ty.maximum())? {
+            bail!(
+                "table minimum size of {} elements exceeds table limits",
+                ty.minimum()
+            );
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn from_exported(
+        table: WebAssembly::Table,
+        closures: DropHandles,
+        ty: Option<TableType>,
+    ) -> Self {
+        // JS doesn't expose the type of a table, so it is guessed when the module's type is not known
+        let ty = ty.unwrap_or_else(|| TableType::new(RefType::FUNCREF, table.length(), None));
+        Self {
+            table,
+            ty,
+            closures: Rc::new(RefCell::new(vec![closures])),
+        }
+    }
+
+    pub(crate) fn to_js_value(&self) -> JsValue {
+        self.table.clone().into()
+    }
+
+    /// Keeps the closures of the stored functions alive, including ones stored later.
+    pub(crate) fn drop_handle(&self) -> DropHandle {
+        DropHandle::new(self.closures.clone())
+    }
+
+    pub fn ty(&self, _store: impl AsContext) -> TableType {
+        self.ty.clone()
+    }
+
+    pub fn size(&self, _store: impl AsContext) -> u32 {
+        self.table.length()
+    }
+
+    pub fn get(&self, _store: impl AsContextMut, index: u32) -> Option<Ref> {
+        if index >= self.table.length() {
+            return None;
+        }
+
+        let value: JsValue = self.table.get(index).ok()?.into();
+        Ref::from_js_typed(&value, self.ty.element(), Rc::new(vec![self.drop_handle()])).ok()
+    }
+
+    pub fn set(&self, _store: impl AsContextMut, index: u32, val: Ref) -> Result<()> {
+        if index >= self.table.length() {
+            bail!(
+                "Table index {index} is out of bounds, table size is {}",
+                self.table.length()
+            );
+        }
+
+        if &val.heap_type() != self.ty.element().heap_type() {
+            bail!(
+                "Cannot store {val:?} in a table of {:?}",
+                self.ty.element().heap_type()
+            );
+        }
+
+        // The element is passed as is, `unchecked_ref` only satisfies the binding's signature
+        self.table
+            .set(index, val.to_js_value().unchecked_ref())
+            .map_err(map_js_error("Set table element"))?;
+
+        // The table can call the function after the caller's `Func` is dropped
+        if let Ref::Func(Some(func)) = &val {
+            let mut closures = self.closures.borrow_mut();
+            if !closures.iter().any(|kept| Rc::ptr_eq(kept, &func.closures)) {
+                closures.push(func.closures.clone());
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn grow(&self, mut store: impl AsContextMut, delta: u32, init: Ref) -> Result<u32> {
+        let mut store = store.as_context_mut();
+        let current = self.table.length();
+        let desired = current.saturating_add(delta);
+        if !store.table_growing(current, desired, self.ty.maximum())? {
+            bail!("Cannot grow table by {delta} elements, the store's resource limiter denied it");
+        }
+
+        let old_size = match self.table.grow(delta) {
+            Ok(old_size) => old_size,
+            Err(error) => {
+                let error = map_js_error("Grow table")(error);
+                store.table_grow_failed(
+                    error.context(format!("Cannot grow table by {delta} elements")),
+                )?;
+                bail!("Cannot grow table by {delta} elements");
+            }
+        };
+
+        self.fill(&mut store, old_size, &init, delta)?;
+
+        Ok(old_size)
+    }
+
+    fn fill(&self, mut store: impl AsContextMut, dst: u32, val: &Ref, len: u32) -> Result<()> {
+        // New elements of a func ref table are null already, but extern ref elements are
+        // `undefined` in JS, so those have to be set to null explicitly
+        if val.is_null() && val.is_func() {
+            return Ok(());
+        }
+
+        for index in dst..(dst + len) {
+            self.set(&mut store, index, val.clone())?;
+        }
+
+        Ok(())
+    }
+}
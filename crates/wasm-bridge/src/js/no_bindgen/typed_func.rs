@@ -21,9 +21,10 @@ impl<Params: ToJsValue, Results: FromJsValue> TypedFunc<Params, Results> {
         }
     }
 
-    pub fn call(&self, _store: impl AsContextMut, params: Params) -> Result<Results> {
+    pub fn call(&self, store: impl AsContextMut, params: Params) -> Result<Results> {
         let args = params.to_function_args();
         let result = self.function.apply(&JsValue::UNDEFINED, &args);
         Results::from_fn_result(&result)
+            .map_err(|error| store.as_context().map_instrumented_trap(error))
     }
 }
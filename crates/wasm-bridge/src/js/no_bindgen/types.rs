@@ -1,3 +1,5 @@
+use std::fmt::{self, Display, Formatter};
+
 #[derive(Debug, Clone, Hash, Eq, PartialEq)]
 pub struct FuncType {
     params: Vec<ValType>,
@@ -15,6 +17,14 @@ impl FuncType {
             results: results.into_iter().collect(),
         }
     }
+
+    pub fn params(&self) -> impl ExactSizeIterator<Item = ValType> + '_ {
+        self.params.iter().cloned()
+    }
+
+    pub fn results(&self) -> impl ExactSizeIterator<Item = ValType> + '_ {
+        self.results.iter().cloned()
+    }
 }
 
 #[derive(Debug, Clone, Hash, Eq, PartialEq)]
@@ -23,4 +33,313 @@ pub enum ValType {
     I64,
     F32,
     F64,
+    V128,
+    Ref(RefType),
+}
+
+impl ValType {
+    pub const FUNCREF: Self = Self::Ref(RefType::FUNCREF);
+    pub const EXTERNREF: Self = Self::Ref(RefType::EXTERNREF);
+
+    pub fn is_num(&self) -> bool {
+        matches!(self, Self::I32 | Self::I64 | Self::F32 | Self::F64)
+    }
+
+    pub fn is_i32(&self) -> bool {
+        matches!(self, Self::I32)
+    }
+
+    pub fn is_i64(&self) -> bool {
+        matches!(self, Self::I64)
+    }
+
+    pub fn is_f32(&self) -> bool {
+        matches!(self, Self::F32)
+    }
+
+    pub fn is_f64(&self) -> bool {
+        matches!(self, Self::F64)
+    }
+
+    pub fn is_v128(&self) -> bool {
+        matches!(self, Self::V128)
+    }
+
+    pub fn is_ref(&self) -> bool {
+        matches!(self, Self::Ref(_))
+    }
+
+    pub fn is_funcref(&self) -> bool {
+        matches!(self, Self::Ref(ref_type) if ref_type.heap_type() == &HeapType::Func)
+    }
+
+    pub fn is_externref(&self) -> bool {
+        matches!(self, Self::Ref(ref_type) if ref_type.heap_type() == &HeapType::Extern)
+    }
+
+    /// Name of the value type in the JS API
+    pub(crate) fn to_js_str(&self) -> &'static str {
+        match self {
+            Self::I32 => "i32",
+            Self::I64 => "i64",
+            Self::F32 => "f32",
+            Self::F64 => "f64",
+            Self::V128 => "v128",
+            Self::Ref(ref_type) => ref_type.to_js_str(),
+        }
+    }
+}
+
+impl Display for ValType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Ref(ref_type) => match ref_type.heap_type() {
+                HeapType::Func => write!(f, "funcref"),
+                HeapType::Extern => write!(f, "externref"),
+            },
+            other => write!(f, "{}", other.to_js_str()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+pub enum Mutability {
+    Const,
+    Var,
+}
+
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub struct GlobalType {
+    content: ValType,
+    mutability: Mutability,
+}
+
+impl GlobalType {
+    pub fn new(content: ValType, mutability: Mutability) -> Self {
+        Self {
+            content,
+            mutability,
+        }
+    }
+
+    pub fn content(&self) -> &ValType {
+        &self.content
+    }
+
+    pub fn mutability(&self) -> Mutability {
+        self.mutability
+    }
+}
+
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub enum HeapType {
+    Func,
+    Extern,
+}
+
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub struct RefType {
+    heap_type: HeapType,
+}
+
+impl RefType {
+    pub const FUNCREF: Self = Self {
+        heap_type: HeapType::Func,
+    };
+
+    pub const EXTERNREF: Self = Self {
+        heap_type: HeapType::Extern,
+    };
+
+    pub fn heap_type(&self) -> &HeapType {
+        &self.heap_type
+    }
+
+    /// Name of the element type in the JS API
+    pub(crate) fn to_js_str(&self) -> &'static str {
+        match self.heap_type {
+            HeapType::Func => "anyfunc",
+            HeapType::Extern => "externref",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub struct TableType {
+    element: RefType,
+    minimum: u32,
+    maximum: Option<u32>,
+}
+
+impl TableType {
+    pub fn new(element: RefType, min: u32, max: Option<u32>) -> Self {
+        Self {
+            element,
+            minimum: min,
+            maximum: max,
+        }
+    }
+
+    pub fn element(&self) -> &RefType {
+        &self.element
+    }
+
+    pub fn minimum(&self) -> u32 {
+        self.minimum
+    }
+
+    pub fn maximum(&self) -> Option<u32> {
+        self.maximum
+    }
+}
+
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub struct MemoryType {
+    minimum: u64,
+    maximum: Option<u64>,
+    is_64: bool,
+    shared: bool,
+}
+
+impl MemoryType {
+    pub fn new(minimum: u32, maximum: Option<u32>) -> Self {
+        Self {
+            minimum: minimum.into(),
+            maximum: maximum.map(Into::into),
+            is_64: false,
+            shared: false,
+        }
+    }
+
+    pub(crate) fn from_limits(minimum: u64, maximum: Option<u64>, flags: u8) -> Self {
+        Self {
+            minimum,
+            maximum,
+            is_64: flags & 0x04 != 0,
+            shared: flags & 0x02 != 0,
+        }
+    }
+
+    pub fn minimum(&self) -> u64 {
+        self.minimum
+    }
+
+    pub fn maximum(&self) -> Option<u64> {
+        self.maximum
+    }
+
+    pub fn is_64(&self) -> bool {
+        self.is_64
+    }
+
+    pub fn is_shared(&self) -> bool {
+        self.shared
+    }
+}
+
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub enum ExternType {
+    Func(FuncType),
+    Global(GlobalType),
+    Table(TableType),
+    Memory(MemoryType),
+}
+
+impl ExternType {
+    pub fn func(&self) -> Option<&FuncType> {
+        match self {
+            Self::Func(ty) => Some(ty),
+            _ => None,
+        }
+    }
+
+    pub fn global(&self) -> Option<&GlobalType> {
+        match self {
+            Self::Global(ty) => Some(ty),
+            _ => None,
+        }
+    }
+
+    pub fn table(&self) -> Option<&TableType> {
+        match self {
+            Self::Table(ty) => Some(ty),
+            _ => None,
+        }
+    }
+
+    pub fn memory(&self) -> Option<&MemoryType> {
+        match self {
+            Self::Memory(ty) => Some(ty),
+            _ => None,
+        }
+    }
+}
+
+impl From<FuncType> for ExternType {
+    fn from(ty: FuncType) -> Self {
+        Self::Func(ty)
+    }
+}
+
+impl From<GlobalType> for ExternType {
+    fn from(ty: GlobalType) -> Self {
+        Self::Global(ty)
+    }
+}
+
+impl From<TableType> for ExternType {
+    fn from(ty: TableType) -> Self {
+        Self::Table(ty)
+    }
+}
+
+impl From<MemoryType> for ExternType {
+    fn from(ty: MemoryType) -> Self {
+        Self::Memory(ty)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ImportType<'module> {
+    module: &'module str,
+    name: &'module str,
+    ty: &'module ExternType,
+}
+
+impl<'module> ImportType<'module> {
+    pub(crate) fn new(module: &'module str, name: &'module str, ty: &'module ExternType) -> Self {
+        Self { module, name, ty }
+    }
+
+    pub fn module(&self) -> &'module str {
+        self.module
+    }
+
+    pub fn name(&self) -> &'module str {
+        self.name
+    }
+
+    pub fn ty(&self) -> ExternType {
+        self.ty.clone()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ExportType<'module> {
+    name: &'module str,
+    ty: &'module ExternType,
+}
+
+impl<'module> ExportType<'module> {
+    pub(crate) fn new(name: &'module str, ty: &'module ExternType) -> Self {
+        Self { name, ty }
+    }
+
+    pub fn name(&self) -> &'module str {
+        self.name
+    }
+
+    pub fn ty(&self) -> ExternType {
+        self.ty.clone()
+    }
 }
@@ -1,3 +1,10 @@
+use anyhow::bail;
+use wasm_bindgen::JsValue;
+
+use crate::{
+    DropHandles, ExternRef, FromJsValue, Func, HeapType, RefType, Result, Rooted, ValType,
+};
+
 #[derive(Debug, Clone)]
 pub enum Val {
     I32(i32),
@@ -6,15 +13,76 @@ pub enum Val {
     // raw values, use f32::to_bits to fill it
     F32(u32),
     F64(u64),
+
+    /// A 128-bit number, cannot be passed to or from JS.
+    V128(V128),
+
+    FuncRef(Option<Func>),
+    ExternRef(Option<Rooted<ExternRef>>),
 }
 
 impl Val {
+    pub(crate) fn ty(&self) -> ValType {
+        match self {
+            Self::I32(_) => ValType::I32,
+            Self::I64(_) => ValType::I64,
+            Self::F32(_) => ValType::F32,
+            Self::F64(_) => ValType::F64,
+            Self::V128(_) => ValType::V128,
+            Self::FuncRef(_) => ValType::FUNCREF,
+            Self::ExternRef(_) => ValType::EXTERNREF,
+        }
+    }
+
+    /// Returns the null reference for the given heap type.
+    pub fn null_ref(heap_type: HeapType) -> Self {
+        match heap_type {
+            HeapType::Func => Self::FuncRef(None),
+            HeapType::Extern => Self::ExternRef(None),
+        }
+    }
+
+    pub const fn null_func_ref() -> Self {
+        Self::FuncRef(None)
+    }
+
+    pub const fn null_extern_ref() -> Self {
+        Self::ExternRef(None)
+    }
+
+    /// Converts a JS value to `Val` of a known type.
+    pub(crate) fn from_js_typed(value: &JsValue, ty: &ValType) -> Result<Self> {
+        Ok(match ty {
+            ValType::I32 => match value.as_f64() {
+                // Allow the whole u32 range too, the bits are the same
+                Some(number)
+                    if number.fract() == 0.0
+                        && number >= i32::MIN as f64
+                        && number <= u32::MAX as f64 =>
+                {
+                    Self::I32(number as i64 as i32)
+                }
+                _ => bail!("Expected an i32 number, got {value:?}"),
+            },
+            ValType::I64 if value.is_bigint() => Self::I64(u64::from_js_value(value)? as i64),
+            ValType::I64 => bail!("Expected an i64 bigint, got {value:?}"),
+            // An f32 converted to a JS number and back keeps its bits, NaN payload included
+            ValType::F32 => match value.as_f64() {
+                Some(number) => Self::F32((number as f32).to_bits()),
+                None => bail!("Expected an f32 number, got {value:?}"),
+            },
+            ValType::F64 => match value.as_f64() {
+                Some(number) => Self::F64(number.to_bits()),
+                None => bail!("Expected an f64 number, got {value:?}"),
+            },
+            ValType::V128 => bail!("V128 values cannot be passed to or from JS"),
+            ValType::Ref(ty) => Ref::from_js_typed(value, ty, Default::default())?.into(),
+        })
+    }
+
     pub fn i32(&self) -> Option<i32> {
         match self {
             Self::I32(val) => Some(*val),
-            // Can be f64, because we can't tell from JS's number type
-            // TODO: check for overflows
-            Self::F64(val) => Some(f64::from_bits(*val) as _),
             _ => None,
         }
     }
@@ -27,9 +95,6 @@ impl Val {
     pub fn f32(&self) -> Option<f32> {
         match self {
             Self::F32(val) => Some(f32::from_bits(*val)),
-            // Can be f64, because we can't tell from JS's number type
-            // TODO: check for overflows
-            Self::F64(val) => Some(f64::from_bits(*val) as _),
             _ => None,
         }
     }
@@ -40,6 +105,44 @@ impl Val {
             _ => None,
         }
     }
+
+    pub fn v128(&self) -> Option<V128> {
+        match self {
+            Self::V128(val) => Some(*val),
+            _ => None,
+        }
+    }
+
+    pub fn funcref(&self) -> Option<Option<&Func>> {
+        match self {
+            Self::FuncRef(func) => Some(func.as_ref()),
+            _ => None,
+        }
+    }
+
+    pub fn unwrap_funcref(&self) -> Option<&Func> {
+        self.funcref().expect("expected funcref")
+    }
+
+    pub fn externref(&self) -> Option<Option<&Rooted<ExternRef>>> {
+        match self {
+            Self::ExternRef(externref) => Some(externref.as_ref()),
+            _ => None,
+        }
+    }
+
+    pub fn unwrap_externref(&self) -> Option<&Rooted<ExternRef>> {
+        self.externref().expect("expected externref")
+    }
+
+    /// Converts this value to a [`Ref`], if it is a reference.
+    pub fn ref_(self) -> Option<Ref> {
+        match self {
+            Self::FuncRef(func) => Some(Ref::Func(func)),
+            Self::ExternRef(externref) => Some(Ref::Extern(externref)),
+            _ => None,
+        }
+    }
 }
 
 impl From<i32> for Val {
@@ -65,3 +168,171 @@ impl From<f64> for Val {
         Self::F64(value.to_bits())
     }
 }
+
+impl From<V128> for Val {
+    fn from(value: V128) -> Self {
+        Self::V128(value)
+    }
+}
+
+impl From<Func> for Val {
+    fn from(func: Func) -> Self {
+        Self::FuncRef(Some(func))
+    }
+}
+
+impl From<Option<Func>> for Val {
+    fn from(func: Option<Func>) -> Self {
+        Self::FuncRef(func)
+    }
+}
+
+impl From<Rooted<ExternRef>> for Val {
+    fn from(externref: Rooted<ExternRef>) -> Self {
+        Self::ExternRef(Some(externref))
+    }
+}
+
+impl From<Option<Rooted<ExternRef>>> for Val {
+    fn from(externref: Option<Rooted<ExternRef>>) -> Self {
+        Self::ExternRef(externref)
+    }
+}
+
+impl From<Ref> for Val {
+    fn from(value: Ref) -> Self {
+        match value {
+            Ref::Func(func) => Self::FuncRef(func),
+            Ref::Extern(externref) => Self::ExternRef(externref),
+        }
+    }
+}
+
+/// A 128-bit SIMD value, mirrors wasmtime's `V128`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct V128(u128);
+
+impl V128 {
+    pub fn as_u128(&self) -> u128 {
+        self.0
+    }
+}
+
+impl From<u128> for V128 {
+    fn from(value: u128) -> Self {
+        Self(value)
+    }
+}
+
+impl From<V128> for u128 {
+    fn from(value: V128) -> Self {
+        value.0
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Ref {
+    Func(Option<Func>),
+    Extern(Option<Rooted<ExternRef>>),
+}
+
+impl Ref {
+    /// Converts a JS value to `Ref` of a known type.
+    pub(crate) fn from_js_typed(
+        value: &JsValue,
+        ty: &RefType,
+        closures: DropHandles,
+    ) -> Result<Self> {
+        Ok(match ty.heap_type() {
+            HeapType::Func if value.is_null() => Self::Func(None),
+            HeapType::Func if value.is_function() => Self::Func(Some(Func::from_exported(
+                value.clone().into(),
+                closures,
+                None,
+            ))),
+            HeapType::Func => bail!("Expected a function reference, got {value:?}"),
+            HeapType::Extern if value.is_null() => Self::Extern(None),
+            HeapType::Extern => Self::Extern(Some(ExternRef::from_js_value(value)?)),
+        })
+    }
+
+    pub(crate) fn to_js_value(&self) -> JsValue {
+        match self {
+            Self::Func(Some(func)) => func.function.clone().into(),
+            Self::Extern(Some(externref)) => externref.to_js_value(),
+            Self::Func(None) | Self::Extern(None) => JsValue::NULL,
+        }
+    }
+
+    pub(crate) fn heap_type(&self) -> HeapType {
+        match self {
+            Self::Func(_) => HeapType::Func,
+            Self::Extern(_) => HeapType::Extern,
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        match self {
+            Self::Func(func) => func.is_none(),
+            Self::Extern(externref) => externref.is_none(),
+        }
+    }
+
+    pub fn is_non_null(&self) -> bool {
+        !self.is_null()
+    }
+
+    pub fn is_func(&self) -> bool {
+        matches!(self, Self::Func(_))
+    }
+
+    pub fn as_func(&self) -> Option<Option<&Func>> {
+        match self {
+            Self::Func(func) => Some(func.as_ref()),
+            _ => None,
+        }
+    }
+
+    pub fn unwrap_func(&self) -> Option<&Func> {
+        self.as_func().expect("ref is a func ref")
+    }
+
+    pub fn is_extern(&self) -> bool {
+        matches!(self, Self::Extern(_))
+    }
+
+    pub fn as_extern(&self) -> Option<Option<&Rooted<ExternRef>>> {
+        match self {
+            Self::Extern(externref) => Some(externref.as_ref()),
+            _ => None,
+        }
+    }
+
+    pub fn unwrap_extern(&self) -> Option<&Rooted<ExternRef>> {
+        self.as_extern().expect("ref is an extern ref")
+    }
+}
+
+impl From<Func> for Ref {
+    fn from(func: Func) -> Self {
+        Self::Func(Some(func))
+    }
+}
+
+impl From<Option<Func>> for Ref {
+    fn from(func: Option<Func>) -> Self {
+        Self::Func(func)
+    }
+}
+
+impl From<Rooted<ExternRef>> for Ref {
+    fn from(externref: Rooted<ExternRef>) -> Self {
+        Self::Extern(Some(externref))
+    }
+}
+
+impl From<Option<Rooted<ExternRef>>> for Ref {
+    fn from(externref: Option<Rooted<ExternRef>>) -> Self {
+        Self::Extern(externref)
+    }
+}
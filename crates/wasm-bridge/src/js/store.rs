@@ -1,50 +1,300 @@
-use atomic_refcell::AtomicRefCell;
+use atomic_refcell::{AtomicRefCell, AtomicRefMut};
 use std::{
+    cell::OnceCell,
+    fmt::{self, Debug, Formatter},
     ops::{Deref, DerefMut},
     sync::Arc,
 };
 
+use anyhow::{bail, Context};
+
 use crate::*;
 
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct Store<T> {
-    engine: Engine,
-    data: DataHandle<T>,
+    inner: DataHandle<T>,
 }
 
 impl<T> Store<T> {
     pub fn new(engine: &Engine, data: T) -> Self {
+        let new_global = || {
+            Global::from_type(GlobalType::new(ValType::I64, Mutability::Var), Val::I64(0))
+                .expect("i64 global can be created")
+        };
+        let fuel = engine.config().consume_fuel.then(new_global);
+        let epoch_deadline = engine.config().epoch_interruption.then(new_global);
+
         Self {
-            engine: engine.clone(),
-            data: Arc::new(AtomicRefCell::new(data)),
+            inner: Arc::new(StoreInner {
+                engine: engine.clone(),
+                data: AtomicRefCell::new(data),
+                fuel,
+                epoch_deadline,
+                limits: AtomicRefCell::new(Limits::default()),
+            }),
         }
     }
 
     pub(crate) fn from_handle(handle: DataHandle<T>) -> Self {
-        Self {
-            engine: Engine::default(), // Engine is unused, so this is file for now
-            data: handle,
-        }
+        Self { inner: handle }
     }
 
     pub fn engine(&self) -> &Engine {
-        &self.engine
+        &self.inner.engine
     }
 
     pub fn data(&self) -> impl Deref<Target = T> + '_ {
-        self.data.borrow()
+        self.inner.data.borrow()
     }
 
     pub fn data_mut(&mut self) -> impl DerefMut<Target = T> + '_ {
-        self.data.borrow_mut()
+        self.inner.data.borrow_mut()
     }
 
     pub(crate) fn data_handle(&self) -> &DataHandle<T> {
-        &self.data
+        &self.inner
+    }
+
+    /// Configures the [`ResourceLimiter`] used to limit resource creation within this store.
+    ///
+    /// The closure returns the limiter from the store's data, and is called whenever
+    /// an instance, memory or table is created, or a memory or table is grown by the host.
+    /// Memories and tables defined by core modules are only limited
+    /// if the engine was created with [`Config::resource_limits`].
+    pub fn limiter(
+        &mut self,
+        limiter: impl FnMut(&mut T) -> &mut (dyn ResourceLimiter) + Send + Sync + 'static,
+    ) {
+        self.inner.limits.borrow_mut().limiter = Some(Box::new(limiter));
+    }
+
+    /// Calls the resource limiter, returns `None` if the store has no limiter.
+    fn with_limiter<R>(&self, f: impl FnOnce(&mut dyn ResourceLimiter) -> R) -> Option<R> {
+        let mut limits = self.inner.limits.borrow_mut();
+        let limiter = limits.limiter.as_mut()?;
+        let mut data = self.inner.data.borrow_mut();
+        Some(f(limiter(&mut *data)))
+    }
+
+    pub(crate) fn memory_growing(
+        &self,
+        current: usize,
+        desired: usize,
+        maximum: Option<usize>,
+    ) -> Result<bool> {
+        self.with_limiter(|limiter| limiter.memory_growing(current, desired, maximum))
+            .unwrap_or(Ok(true))
+    }
+
+    pub(crate) fn memory_grow_failed(&self, error: Error) -> Result<()> {
+        self.with_limiter(|limiter| limiter.memory_grow_failed(error))
+            .unwrap_or(Ok(()))
+    }
+
+    pub(crate) fn table_growing(
+        &self,
+        current: u32,
+        desired: u32,
+        maximum: Option<u32>,
+    ) -> Result<bool> {
+        self.with_limiter(|limiter| limiter.table_growing(current, desired, maximum))
+            .unwrap_or(Ok(true))
+    }
+
+    pub(crate) fn table_grow_failed(&self, error: Error) -> Result<()> {
+        self.with_limiter(|limiter| limiter.table_grow_failed(error))
+            .unwrap_or(Ok(()))
+    }
+
+    pub(crate) fn add_instance(&self) -> Result<()> {
+        let limit = self
+            .with_limiter(|limiter| limiter.instances())
+            .unwrap_or(DEFAULT_INSTANCE_LIMIT);
+        self.add_resource("instance", limit, |limits| &mut limits.instances)
+    }
+
+    /// Undoes [`Self::add_instance`], for instantiations that failed.
+    pub(crate) fn remove_instance(&self) {
+        self.inner.limits.borrow_mut().instances -= 1;
+    }
+
+    pub(crate) fn add_memory(&self) -> Result<()> {
+        let limit = self
+            .with_limiter(|limiter| limiter.memories())
+            .unwrap_or(DEFAULT_MEMORY_LIMIT);
+        self.add_resource("memory", limit, |limits| &mut limits.memories)
     }
+
+    /// Undoes [`Self::add_memory`], for memories that could not be created.
+    pub(crate) fn remove_memory(&self) {
+        self.inner.limits.borrow_mut().memories -= 1;
+    }
+
+    pub(crate) fn add_table(&self) -> Result<()> {
+        let limit = self
+            .with_limiter(|limiter| limiter.tables())
+            .unwrap_or(DEFAULT_TABLE_LIMIT);
+        self.add_resource("table", limit, |limits| &mut limits.tables)
+    }
+
+    /// Undoes [`Self::add_table`], for tables that could not be created.
+    pub(crate) fn remove_table(&self) {
+        self.inner.limits.borrow_mut().tables -= 1;
+    }
+
+    fn add_resource(
+        &self,
+        kind: &str,
+        limit: usize,
+        count: impl FnOnce(&mut Limits<T>) -> &mut usize,
+    ) -> Result<()> {
+        let mut limits = self.inner.limits.borrow_mut();
+        let count = count(&mut limits);
+        if *count >= limit {
+            bail!("resource limit exceeded: {kind} count too high at {count}");
+        }
+
+        *count += 1;
+        Ok(())
+    }
+
+    /// Returns the remaining fuel in this store.
+    ///
+    /// Returns an error if fuel consumption is not enabled via [`Config::consume_fuel`].
+    pub fn get_fuel(&self) -> Result<u64> {
+        let fuel = self.fuel_global()?.value().i64().unwrap_or_default();
+
+        // The fuel is negative after running out of it
+        Ok(fuel.max(0) as u64)
+    }
+
+    /// Sets the fuel in this store for wasm to consume while executing.
+    ///
+    /// A store starts with 0 fuel, so this must be called before running any wasm code.
+    /// Returns an error if fuel consumption is not enabled via [`Config::consume_fuel`].
+    pub fn set_fuel(&mut self, fuel: u64) -> Result<()> {
+        let fuel = i64::try_from(fuel).unwrap_or(i64::MAX);
+        self.fuel_global()?.set_value(Val::I64(fuel))
+    }
+
+    fn fuel_global(&self) -> Result<&Global> {
+        self.fuel().context("fuel is not configured in this store")
+    }
+
+    /// Returns the global with the remaining fuel, imported by instrumented modules.
+    pub(crate) fn fuel(&self) -> Option<&Global> {
+        self.inner.fuel.as_ref()
+    }
+
+    /// Sets the epoch deadline to a certain number of ticks in the future.
+    ///
+    /// A store starts with a deadline of 0, so wasm code traps immediately unless this is called.
+    /// Does nothing if epoch interruption is not enabled via [`Config::epoch_interruption`].
+    pub fn set_epoch_deadline(&mut self, ticks_beyond_current: u64) {
+        if let Some(deadline) = self.epoch_deadline() {
+            let deadline = self
+                .engine()
+                .current_epoch()
+                .saturating_add(ticks_beyond_current);
+
+            deadline
+                .set_value(Val::I64(deadline as i64))
+                .expect("epoch deadline global is mutable");
+        }
+    }
+
+    /// Configures epoch deadline expiration to trap with [`Trap::Interrupt`].
+    ///
+    /// This is the default, and the only supported behavior on the web.
+    pub fn epoch_deadline_trap(&mut self) {}
+
+    /// Returns the global with the epoch deadline, imported by instrumented modules.
+    pub(crate) fn epoch_deadline(&self) -> Option<&Global> {
+        self.inner.epoch_deadline.as_ref()
+    }
+
+    /// Reports an `unreachable` trap caused by the instrumentation as the trap wasmtime would report,
+    /// [`Trap::OutOfFuel`] or [`Trap::Interrupt`].
+    pub(crate) fn map_instrumented_trap(&self, error: Error) -> Error {
+        if error.downcast_ref::<Trap>() != Some(&Trap::UnreachableCodeReached) {
+            return error;
+        }
+
+        let out_of_fuel = self
+            .fuel()
+            .is_some_and(|fuel| matches!(fuel.value(), Val::I64(fuel) if fuel < 0));
+
+        let interrupted = self.epoch_deadline().is_some_and(|deadline| {
+            let deadline = deadline.value().i64().unwrap_or_default() as u64;
+            self.engine().current_epoch() >= deadline
+        });
+
+        let trap = if out_of_fuel {
+            Trap::OutOfFuel
+        } else if interrupted {
+            Trap::Interrupt
+        } else {
+            return error;
+        };
+
+        match error.downcast_ref::<WasmBacktrace>() {
+            Some(backtrace) => Error::new(trap).context(backtrace.clone()),
+            None => Error::new(trap),
+        }
+    }
+}
+
+impl<T: Default> Default for Store<T> {
+    fn default() -> Self {
+        Self::new(&Engine::default(), T::default())
+    }
+}
+
+pub(crate) type DataHandle<T> = Arc<StoreInner<T>>;
+
+/// State of a store, shared with the callers of imported functions.
+#[derive(Debug)]
+pub(crate) struct StoreInner<T> {
+    engine: Engine,
+    pub(crate) data: AtomicRefCell<T>,
+    /// Remaining fuel, imported as a global by instrumented modules
+    fuel: Option<Global>,
+    /// Epoch at which wasm code traps, imported as a global by instrumented modules
+    epoch_deadline: Option<Global>,
+    limits: AtomicRefCell<Limits<T>>,
+}
+
+type LimiterFn<T> = Box<dyn FnMut(&mut T) -> &mut (dyn ResourceLimiter) + Send + Sync>;
+
+/// Resource limiter of a store, and the resources counted against it.
+struct Limits<T> {
+    limiter: Option<LimiterFn<T>>,
+    instances: usize,
+    memories: usize,
+    tables: usize,
 }
 
-pub(crate) type DataHandle<T> = Arc<AtomicRefCell<T>>;
+impl<T> Default for Limits<T> {
+    fn default() -> Self {
+        Self {
+            limiter: None,
+            instances: 0,
+            memories: 0,
+            tables: 0,
+        }
+    }
+}
+
+impl<T> Debug for Limits<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Limits")
+            .field("has_limiter", &self.limiter.is_some())
+            .field("instances", &self.instances)
+            .field("memories", &self.memories)
+            .field("tables", &self.tables)
+            .finish()
+    }
+}
 
 pub struct StoreContext<'a, T>(&'a T);
 
@@ -58,27 +308,60 @@ impl<'a, T> StoreContext<'a, T> {
     }
 }
 
-pub struct StoreContextMut<'a, T>(&'a mut T);
+/// The store passed to component host functions.
+///
+/// The store's data is borrowed when it is first accessed, and released again
+/// when the store is used to call an exported function, so that the called
+/// function can call back into the host.
+pub struct StoreContextMut<'a, T> {
+    inner: &'a StoreInner<T>,
+    store: Store<T>,
+    data: OnceCell<AtomicRefMut<'a, T>>,
+}
 
 impl<'a, T> StoreContextMut<'a, T> {
-    pub fn new(reference: &'a mut T) -> Self {
-        Self(reference)
+    pub(crate) fn new(handle: &'a DataHandle<T>) -> Self {
+        Self {
+            inner: handle,
+            store: Store::from_handle(handle.clone()),
+            data: OnceCell::new(),
+        }
+    }
+
+    fn borrow_data(&self) -> &AtomicRefMut<'a, T> {
+        self.data.get_or_init(|| self.inner.data.borrow_mut())
     }
 
     pub fn data(&self) -> &T {
-        self.0
+        self.borrow_data()
     }
 
     pub fn data_mut(&mut self) -> &mut T {
-        self.0
+        self.borrow_data();
+        self.data.get_mut().expect("data is borrowed")
+    }
+}
+
+impl<T> AsContext for StoreContextMut<'_, T> {
+    type Data = T;
+
+    fn as_context(&self) -> &Store<Self::Data> {
+        &self.store
+    }
+}
+
+impl<T> AsContextMut for StoreContextMut<'_, T> {
+    fn as_context_mut(&mut self) -> &mut Store<Self::Data> {
+        // The data can be borrowed again by a host function called from wasm
+        self.data.take();
+        &mut self.store
     }
 }
 
 impl<T> Clone for Store<T> {
     fn clone(&self) -> Self {
         Self {
-            data: self.data.clone(),
-            engine: self.engine.clone(),
+            inner: self.inner.clone(),
         }
     }
 }
@@ -0,0 +1,154 @@
+use std::fmt::{self, Display, Formatter};
+
+use js_sys::{Reflect, WebAssembly};
+use wasm_bindgen::{JsCast, JsValue};
+
+use crate::helpers::static_str_to_js;
+
+/// Reason why WebAssembly code trapped, mirrors wasmtime's `Trap`.
+#[non_exhaustive]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum Trap {
+    /// The current stack space was exhausted.
+    StackOverflow,
+
+    /// An out-of-bounds memory access.
+    MemoryOutOfBounds,
+
+    /// A wasm atomic operation was presented with a not-naturally-aligned linear-memory address.
+    HeapMisaligned,
+
+    /// An out-of-bounds access to a table.
+    TableOutOfBounds,
+
+    /// Indirect call to a null table entry.
+    IndirectCallToNull,
+
+    /// Signature mismatch on indirect call.
+    BadSignature,
+
+    /// An integer arithmetic operation caused an overflow.
+    IntegerOverflow,
+
+    /// An integer division by zero.
+    IntegerDivisionByZero,
+
+    /// Failed float-to-int conversion.
+    BadConversionToInteger,
+
+    /// Code that was supposed to have been unreachable was reached.
+    UnreachableCodeReached,
+
+    /// Execution has potentially run too long and may be interrupted.
+    Interrupt,
+
+    /// A function that was `canon lift`'d, then `canon lower`'d, then called.
+    AlwaysTrapAdapter,
+
+    /// Wasm code ran out of fuel.
+    OutOfFuel,
+
+    /// Atomic wait operation on non shared memory.
+    AtomicWaitNonSharedMemory,
+
+    /// Call to a null reference.
+    NullReference,
+
+    /// Attempt to get the bits of a null `i31ref`.
+    NullI31Ref,
+
+    /// Component tried to call another component, but it would violate the reentrance rules.
+    CannotEnterComponent,
+}
+
+impl Trap {
+    /// Classifies an error thrown by JS, returns `None` if it is not a trap.
+    pub(crate) fn from_js_error(error: &JsValue) -> Option<Self> {
+        let is_runtime_error = error.is_instance_of::<WebAssembly::RuntimeError>();
+        let is_recursion_error = error.is_instance_of::<js_sys::RangeError>()
+            || Self::error_name(error).as_deref() == Some("InternalError");
+
+        if !is_runtime_error && !is_recursion_error {
+            return None;
+        }
+
+        let message = Reflect::get(error, static_str_to_js("message"))
+            .ok()?
+            .as_string()?
+            .to_lowercase();
+
+        if is_recursion_error {
+            // Chrome and Safari throw a RangeError, Firefox throws an InternalError
+            return (message.contains("call stack") || message.contains("too much recursion"))
+                .then_some(Self::StackOverflow);
+        }
+
+        // Error messages from V8 and SpiderMonkey, the most specific ones must go first
+        const MESSAGES: &[(&str, Trap)] = &[
+            ("unreachable", Trap::UnreachableCodeReached),
+            ("table index is out of bounds", Trap::TableOutOfBounds),
+            ("out of bounds table access", Trap::TableOutOfBounds),
+            ("out of bounds", Trap::MemoryOutOfBounds),
+            ("divide by zero", Trap::IntegerDivisionByZero),
+            ("remainder by zero", Trap::IntegerDivisionByZero),
+            ("divide result unrepresentable", Trap::IntegerOverflow),
+            ("integer overflow", Trap::IntegerOverflow),
+            ("float unrepresentable", Trap::BadConversionToInteger),
+            (
+                "invalid conversion to integer",
+                Trap::BadConversionToInteger,
+            ),
+            ("indirect call to null", Trap::IndirectCallToNull),
+            // V8 does not tell a null entry and a wrong signature apart
+            (
+                "null function or function signature mismatch",
+                Trap::BadSignature,
+            ),
+            ("signature mismatch", Trap::BadSignature),
+            ("unaligned", Trap::HeapMisaligned),
+            ("null", Trap::NullReference),
+            ("call stack", Trap::StackOverflow),
+            ("too much recursion", Trap::StackOverflow),
+        ];
+
+        MESSAGES
+            .iter()
+            .find(|(pattern, _)| message.contains(pattern))
+            .map(|(_, trap)| *trap)
+    }
+
+    fn error_name(error: &JsValue) -> Option<String> {
+        Reflect::get(error, static_str_to_js("name"))
+            .ok()?
+            .as_string()
+    }
+}
+
+impl Display for Trap {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        use Trap::*;
+
+        let desc = match self {
+            StackOverflow => "call stack exhausted",
+            MemoryOutOfBounds => "out of bounds memory access",
+            HeapMisaligned => "unaligned atomic",
+            TableOutOfBounds => "undefined element: out of bounds table access",
+            IndirectCallToNull => "uninitialized element",
+            BadSignature => "indirect call type mismatch",
+            IntegerOverflow => "integer overflow",
+            IntegerDivisionByZero => "integer divide by zero",
+            BadConversionToInteger => "invalid conversion to integer",
+            UnreachableCodeReached => "wasm `unreachable` instruction executed",
+            Interrupt => "interrupt",
+            AlwaysTrapAdapter => "degenerate component adapter called",
+            OutOfFuel => "all fuel consumed by WebAssembly",
+            AtomicWaitNonSharedMemory => "atomic wait on non-shared memory",
+            NullReference => "null reference",
+            NullI31Ref => "null i31 reference",
+            CannotEnterComponent => "cannot enter component instance",
+        };
+        write!(f, "wasm trap: {desc}")
+    }
+}
+
+impl std::error::Error for Trap {}
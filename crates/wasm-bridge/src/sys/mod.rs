@@ -1,3 +1,5 @@
+use std::{io::Read, path::Path};
+
 use ref_cast::RefCast;
 
 pub use wasmtime::*;
@@ -184,6 +186,111 @@ impl Module {
         #[allow(deprecated)]
         Self::new(engine, bytes)
     }
+
+    /// Creates a new WebAssembly `Module` from the bytes read from `reader`,
+    /// for example an opened file.
+    ///
+    /// The whole module is read into memory before it is compiled. On the web,
+    /// `Module::from_response` and `Module::from_stream` compile the module
+    /// while it is being downloaded instead.
+    pub fn from_reader(engine: &Engine, mut reader: impl Read) -> Result<Self> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+
+        #[allow(deprecated)]
+        Self::new(engine, bytes)
+    }
+
+    /// Deserializes an in-memory compiled module previously created with
+    /// [`Module::serialize`] or [`Engine::precompile_module`].
+    ///
+    /// This function will deserialize the binary blobs emitted by
+    /// [`Module::serialize`] and [`Engine::precompile_module`] back into an
+    /// in-memory [`Module`] that's ready to be instantiated.
+    ///
+    /// Note that the [`Module::deserialize_file`] method is more optimized than
+    /// this function, so if the serialized module is already present in a file
+    /// it's recommended to use that method instead.
+    ///
+    /// # Safety
+    ///
+    /// This function is marked as `unsafe` because if fed invalid input or used
+    /// improperly this could lead to memory safety vulnerabilities. This method
+    /// should not, for example, be exposed to arbitrary user input.
+    ///
+    /// This function is only designed to receive the previous input from
+    /// [`Module::serialize`] and [`Engine::precompile_module`]. If the exact
+    /// output of those functions (unmodified) is passed to this function then
+    /// calls to this function can be considered safe.
+    pub unsafe fn deserialize(engine: &Engine, bytes: impl AsRef<[u8]>) -> Result<Self> {
+        Ok(Self(wasmtime::Module::deserialize(engine, bytes)?))
+    }
+
+    /// Same as [`deserialize`], except that the contents of `path` are read to
+    /// deserialize into a [`Module`].
+    ///
+    /// This method is provided because it can be faster than [`deserialize`]
+    /// since the data doesn't need to be copied around, but rather the module
+    /// can be used directly from an mmap'd view of the file provided.
+    ///
+    /// [`deserialize`]: Module::deserialize
+    ///
+    /// # Safety
+    ///
+    /// All of the reasons that [`deserialize`] is `unsafe` applies to this
+    /// function as well. Additionally the file referenced must remain unchanged
+    /// and a valid precompiled module for the entire lifetime of the [`Module`] returned.
+    pub unsafe fn deserialize_file(engine: &Engine, path: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self(wasmtime::Module::deserialize_file(engine, path)?))
+    }
+
+    /// Serializes this module to a vector of bytes.
+    ///
+    /// This function is similar to the [`Engine::precompile_module`] method
+    /// where it produces an artifact of Wasmtime which is suitable to later
+    /// pass into [`Module::deserialize`].
+    pub fn serialize(&self) -> Result<Vec<u8>> {
+        self.0.serialize()
+    }
+
+    /// Returns identifier/name that this [`Module`] has. This name
+    /// is used in traps/backtrace details.
+    ///
+    /// Note that most LLVM/clang/Rust-produced modules do not have a name
+    /// associated with them, but other wasm tooling can be used to inject or
+    /// add a name.
+    pub fn name(&self) -> Option<&str> {
+        self.0.name()
+    }
+
+    /// Returns the list of imports that this [`Module`] has and must be
+    /// satisfied.
+    ///
+    /// This function returns the list of imports that the wasm module has, but
+    /// only the types of each import. The type of each import is used to
+    /// typecheck the [`Instance::new`](crate::Instance::new) method's `imports`
+    /// argument. The arguments to that function must match up 1-to-1 with the
+    /// entries in the array returned here.
+    pub fn imports(&self) -> impl ExactSizeIterator<Item = ImportType<'_>> + '_ {
+        self.0.imports()
+    }
+
+    /// Returns the list of exports that this [`Module`] has and will be
+    /// available after instantiation.
+    ///
+    /// This function will return the type of each item that will be exported
+    /// from an [`Instance`](crate::Instance) of this module. The entries here
+    /// will indicate the name of the export along with the type of the export.
+    pub fn exports(&self) -> impl ExactSizeIterator<Item = ExportType<'_>> + '_ {
+        self.0.exports()
+    }
+
+    /// Looks up an export in this [`Module`] by name.
+    ///
+    /// This function will return the type of an export with the given name.
+    pub fn get_export(&self, name: &str) -> Option<ExternType> {
+        self.0.get_export(name)
+    }
 }
 
 /// An instantiated WebAssembly module.
@@ -324,6 +431,44 @@ impl Instance {
         self.0.get_memory(store, name)
     }
 
+    /// Looks up an exported [`Extern`] value by name.
+    ///
+    /// This method will search the module for an export named `name` and
+    /// return the value, if found.
+    ///
+    /// Returns `None` if there was no export named `name`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `store` does not own this instance.
+    pub fn get_export(&self, store: impl AsContextMut, name: &str) -> Option<Extern> {
+        self.0.get_export(store, name)
+    }
+
+    /// Looks up an exported [`Global`] value by name.
+    ///
+    /// Returns `None` if there was no export named `name`, or if there was but
+    /// it wasn't a global.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `store` does not own this instance.
+    pub fn get_global(&self, store: impl AsContextMut, name: &str) -> Option<Global> {
+        self.0.get_global(store, name)
+    }
+
+    /// Looks up an exported [`Table`] value by name.
+    ///
+    /// Returns `None` if there was no export named `name`, or if there was but
+    /// it wasn't a table.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `store` does not own this instance.
+    pub fn get_table(&self, store: impl AsContextMut, name: &str) -> Option<Table> {
+        self.0.get_table(store, name)
+    }
+
     /// Looks up an exported [`Func`] value by name.
     ///
     /// Returns `None` if there was no export named `name`, or if there was but
@@ -609,6 +754,179 @@ impl<T> Linker<T> {
     ) -> Result<&mut Self> {
         Ok(Self::ref_cast_mut(self.0.func_wrap(module, name, func)?))
     }
+
+    /// Defines a new item in this [`Linker`].
+    ///
+    /// This method will add a new definition, by name, to this instance of
+    /// [`Linker`]. The `module` and `name` provided are what to name the
+    /// `item`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the `module` and `name` already identify an item
+    /// of the same type as the `item` provided and if shadowing is disallowed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any item used to instantiate `module` is not owned by
+    /// `store`.
+    pub fn define(
+        &mut self,
+        store: impl AsContext<Data = T>,
+        module: &str,
+        name: &str,
+        item: impl Into<Extern>,
+    ) -> Result<&mut Self> {
+        Ok(Self::ref_cast_mut(
+            self.0.define(store, module, name, item)?,
+        ))
+    }
+
+    /// Convenience wrapper to define an entire [`Instance`] in this linker.
+    ///
+    /// This function is a convenience wrapper around [`Linker::define`] which
+    /// will define all exports on `instance` into this linker. The module name
+    /// for each export is `module_name`, and the name for each export is the
+    /// name in the instance itself.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the any item is redefined twice in this linker (for
+    /// example the same `module_name` was already defined) and shadowing is
+    /// disallowed, or if `instance` comes from a different
+    /// [`Store`](crate::Store) than this [`Linker`] originally was created
+    /// with.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `instance` does not belong to `store`.
+    pub fn instance(
+        &mut self,
+        store: impl AsContextMut<Data = T>,
+        module_name: &str,
+        instance: Instance,
+    ) -> Result<&mut Self> {
+        Ok(Self::ref_cast_mut(self.0.instance(
+            store,
+            module_name,
+            instance.0,
+        )?))
+    }
+
+    #[deprecated(
+        since = "0.5.0",
+        note = "Instantiating a module synchronously can panic on the web, please use `module_safe` instead."
+    )]
+    pub fn module(
+        &mut self,
+        store: impl AsContextMut<Data = T>,
+        module_name: &str,
+        module: &Module,
+    ) -> Result<&mut Self>
+    where
+        T: 'static,
+    {
+        Ok(Self::ref_cast_mut(self.0.module(
+            store,
+            module_name,
+            &module.0,
+        )?))
+    }
+
+    /// Define automatic instantiations of a [`Module`] in this linker.
+    ///
+    /// This automatically handles [Commands and Reactors] instantiation and
+    /// initialization.
+    ///
+    /// Exported functions of a Command module may be called directly, however
+    /// instead of having a single instance which is reused for each call,
+    /// each call creates a new instance, which lives for the duration of the
+    /// call. The imports of the Command are resolved once, and reused for
+    /// each instantiation, so all dependencies need to be present at the time
+    /// when `Linker::module` is called.
+    ///
+    /// For Reactors, a single instance is created, and an initialization
+    /// function is called, and then its exports may be called.
+    ///
+    /// Ordinary modules which don't declare themselves to be either Commands
+    /// or Reactors are treated as Reactors without any initialization calls.
+    ///
+    /// Command modules are not supported on the web yet.
+    ///
+    /// [Commands and Reactors]: https://github.com/WebAssembly/WASI/blob/main/legacy/application-abi.md#current-unstable-abi
+    pub async fn module_safe(
+        &mut self,
+        store: impl AsContextMut<Data = T>,
+        module_name: &str,
+        module: &Module,
+    ) -> Result<&mut Self>
+    where
+        T: 'static,
+    {
+        // This just calls `module` on sys, but uses proper async instantiation on the web.
+        #[allow(deprecated)]
+        self.module(store, module_name, module)
+    }
+
+    /// Aliases one item's name as another.
+    ///
+    /// This method will alias an item with the specified `module` and `name`
+    /// under a new name of `as_module` and `as_name`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any shadowing violations happen while defining new
+    /// items, or if the original item wasn't defined.
+    pub fn alias(
+        &mut self,
+        module: &str,
+        name: &str,
+        as_module: &str,
+        as_name: &str,
+    ) -> Result<&mut Self> {
+        Ok(Self::ref_cast_mut(
+            self.0.alias(module, name, as_module, as_name)?,
+        ))
+    }
+
+    /// Looks up a previously defined value in this [`Linker`], identified by
+    /// the names provided.
+    ///
+    /// Returns `None` if this name was not previously defined in this
+    /// [`Linker`].
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the `store` provided does not come from the
+    /// same [`Engine`] that this linker was created with.
+    pub fn get(
+        &self,
+        store: impl AsContextMut<Data = T>,
+        module: &str,
+        name: &str,
+    ) -> Option<Extern> {
+        self.0.get(store, module, name)
+    }
+
+    /// Implement any imports of the given [`Module`] with a function which traps.
+    ///
+    /// By default a [`Linker`] will error when unknown imports are encountered
+    /// in a command module while using [`Linker::module`].
+    ///
+    /// This method can be used to allow unknown imports from command modules.
+    pub fn define_unknown_imports_as_traps(&mut self, module: &Module) -> Result<()> {
+        self.0.define_unknown_imports_as_traps(&module.0)
+    }
+
+    /// Implement any function imports of the [`Module`] with a function that
+    /// ignores its arguments and returns default values.
+    ///
+    /// Default values are either zero or null, depending on the value type.
+    ///
+    /// This method can be used to allow unknown imports from command modules.
+    pub fn define_unknown_imports_as_default_values(&mut self, module: &Module) -> Result<()> {
+        self.0.define_unknown_imports_as_default_values(&module.0)
+    }
 }
 
 pub mod helpers {
@@ -634,6 +952,8 @@ pub mod component {
     pub use wasm_bridge_macros::Lower;
 
     use ref_cast::RefCast;
+    use std::{io::Read, path::Path};
+
     use wasmtime::{AsContextMut, Engine, Result};
 
     /// A compiled WebAssembly Component.
@@ -743,6 +1063,131 @@ pub mod component {
             #[allow(deprecated)]
             Self::new(engine, bytes)
         }
+
+        /// Same as [`Module::from_reader`], but for components.
+        ///
+        /// [`Module::from_reader`]: crate::Module::from_reader
+        pub fn from_reader(engine: &Engine, mut reader: impl Read) -> Result<Self> {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes)?;
+
+            #[allow(deprecated)]
+            Self::new(engine, bytes)
+        }
+
+        /// Same as [`Module::deserialize`], but for components.
+        ///
+        /// Note that the bytes referenced here must contain contents previously
+        /// produced by [`Engine::precompile_component`] or
+        /// [`Component::serialize`].
+        ///
+        /// # Safety
+        ///
+        /// The unsafety of this method is the same as that of the
+        /// [`Module::deserialize`] method.
+        ///
+        /// [`Module::deserialize`]: crate::Module::deserialize
+        pub unsafe fn deserialize(engine: &Engine, bytes: impl AsRef<[u8]>) -> Result<Self> {
+            Ok(Self(wasmtime::component::Component::deserialize(
+                engine, bytes,
+            )?))
+        }
+
+        /// Same as [`Module::deserialize_file`], but for components.
+        ///
+        /// Note that the file referenced here must contain contents previously
+        /// produced by [`Engine::precompile_component`] or
+        /// [`Component::serialize`].
+        ///
+        /// # Safety
+        ///
+        /// The unsafety of this method is the same as that of the
+        /// [`Module::deserialize_file`] method.
+        ///
+        /// [`Module::deserialize_file`]: crate::Module::deserialize_file
+        pub unsafe fn deserialize_file(engine: &Engine, path: impl AsRef<Path>) -> Result<Self> {
+            Ok(Self(wasmtime::component::Component::deserialize_file(
+                engine, path,
+            )?))
+        }
+
+        /// Same as [`Module::serialize`], except for a component.
+        ///
+        /// Note that the artifact produced here must be passed to
+        /// [`Component::deserialize`] and is not compatible for use with
+        /// [`Module`](crate::Module).
+        ///
+        /// [`Module::serialize`]: crate::Module::serialize
+        pub fn serialize(&self) -> Result<Vec<u8>> {
+            self.0.serialize()
+        }
+
+        /// Returns the type of this component as a [`types::Component`].
+        ///
+        /// This method enables runtime introspection of the type of a component
+        /// before instantiation, if necessary.
+        ///
+        /// Resource types in the returned type are unique to the component
+        /// and do not compare equal to the types of an instantiated component.
+        pub fn component_type(&self) -> types::Component {
+            self.0.component_type()
+        }
+    }
+
+    /// A "pre-instantiated" [`Instance`] which has all of its arguments already
+    /// supplied and is ready to instantiate.
+    ///
+    /// This structure represents an efficient form of instantiation where import
+    /// type-checking and import lookup has all been resolved by the time that this
+    /// type is created. This type is primarily created through the
+    /// [`Linker::instantiate_pre`] method.
+    #[repr(transparent)]
+    #[derive(RefCast)]
+    pub struct InstancePre<T>(pub(crate) wasmtime::component::InstancePre<T>);
+
+    impl<T> Clone for InstancePre<T> {
+        fn clone(&self) -> Self {
+            Self(self.0.clone())
+        }
+    }
+
+    impl<T> InstancePre<T> {
+        /// Returns the underlying component that will be instantiated.
+        pub fn component(&self) -> &Component {
+            Component::ref_cast(self.0.component())
+        }
+
+        #[deprecated(
+            since = "0.5.0",
+            note = "Instantiating a component synchronously can panic on the web, please use `instantiate_safe` instead."
+        )]
+        pub fn instantiate(&self, store: impl AsContextMut<Data = T>) -> Result<Instance> {
+            self.0.instantiate(store)
+        }
+
+        /// Performs the instantiation process into the store specified.
+        pub async fn instantiate_safe(
+            &self,
+            store: impl AsContextMut<Data = T>,
+        ) -> Result<Instance> {
+            // This just calls `instantiate` on sys, but uses proper async instantiation on the web.
+            #[allow(deprecated)]
+            self.instantiate(store)
+        }
+
+        /// Performs the instantiation process into the store specified.
+        ///
+        /// Exactly like [`InstancePre::instantiate`] except for use on async stores.
+        #[cfg(feature = "async")]
+        pub async fn instantiate_async(
+            &self,
+            store: impl AsContextMut<Data = T>,
+        ) -> Result<Instance>
+        where
+            T: Send,
+        {
+            self.0.instantiate_async(store).await
+        }
     }
 
     /// A type used to instantiate [`Component`]s.
@@ -854,6 +1299,22 @@ pub mod component {
             self.0.instantiate_async(store, &component.0).await
         }
 
+        /// Performs all checks necessary for instantiating `component` with this
+        /// linker, except that instantiation doesn't actually finish.
+        ///
+        /// This method is used for front-loading type-checking information as well
+        /// as collecting the imports to use to instantiate a component. The returned
+        /// [`InstancePre`] represents a ready-to-be-instantiated component, which can
+        /// also be instantiated multiple times quickly.
+        ///
+        /// # Errors
+        ///
+        /// Returns an error if this [`Linker`] doesn't define an import that
+        /// `component` requires or if it is of the wrong type.
+        pub fn instantiate_pre(&self, component: &Component) -> Result<InstancePre<T>> {
+            Ok(InstancePre(self.0.instantiate_pre(&component.0)?))
+        }
+
         /// Returns the "root instance" of this linker, used to define names into
         /// the root namespace.
         pub fn root(&mut self) -> LinkerInstance<T> {
@@ -0,0 +1,4 @@
+#[no_mangle]
+pub fn panic_now() {
+    panic!("guest panicked");
+}
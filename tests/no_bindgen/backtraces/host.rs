@@ -0,0 +1,184 @@
+use wasm_bridge::*;
+
+pub async fn run_test(bytes: &[u8]) -> Result<()> {
+    let mut store = Store::<()>::default();
+
+    guest_backtrace(&mut store, bytes).await.unwrap();
+    trap_backtrace(&mut store).await.unwrap();
+    host_backtrace(&mut store).await.unwrap();
+    overlapping_modules(&mut store).await.unwrap();
+
+    Ok(())
+}
+
+async fn guest_backtrace(mut store: &mut Store<()>, bytes: &[u8]) -> Result<()> {
+    let module = Module::new_safe(store.engine(), bytes).await.unwrap();
+    let instance = Instance::new_safe(&mut store, &module, &[]).await.unwrap();
+
+    let panic_now = instance
+        .get_typed_func::<(), ()>(&mut store, "panic_now")
+        .unwrap();
+    let error = panic_now
+        .call(&mut store, ())
+        .expect_err("guest should panic");
+
+    let backtrace = error.downcast_ref::<WasmBacktrace>().unwrap();
+    assert!(!backtrace.frames().is_empty());
+
+    Ok(())
+}
+
+const TRACED_WAT: &str = r#"(module $traced
+    (import "host" "check_backtrace" (func $check_backtrace (result i32)))
+    (import "host" "fail" (func $fail))
+    (func $inner
+        unreachable
+    )
+    (func $middle
+        (call $inner)
+    )
+    (func $outer (export "outer")
+        (call $middle)
+    )
+    (func $call_check (export "call_check") (result i32)
+        (call $check_backtrace)
+    )
+    (func $call_fail (export "call_fail")
+        (call $fail)
+    )
+)"#;
+
+async fn instantiate_traced(mut store: &mut Store<()>) -> Result<Instance> {
+    let module = Module::new_safe(store.engine(), TRACED_WAT.as_bytes())
+        .await
+        .unwrap();
+    assert_eq!(module.name(), Some("traced"));
+
+    let mut linker = Linker::new(store.engine());
+    linker
+        .func_new(
+            "host",
+            "check_backtrace",
+            FuncType::new(store.engine(), [], [ValType::I32]),
+            |caller, _, rets| {
+                let backtrace = WasmBacktrace::force_capture(&caller);
+                let names = frame_names(&backtrace);
+                rets[0] = Val::I32((names == ["call_check"]) as i32);
+                Ok(())
+            },
+        )
+        .unwrap();
+    linker
+        .func_new(
+            "host",
+            "fail",
+            FuncType::new(store.engine(), [], []),
+            |_, _, _| Err(Error::msg("host failure")),
+        )
+        .unwrap();
+
+    linker.instantiate_safe(&mut store, &module).await
+}
+
+async fn trap_backtrace(mut store: &mut Store<()>) -> Result<()> {
+    let instance = instantiate_traced(&mut store).await.unwrap();
+
+    let outer = instance
+        .get_typed_func::<(), ()>(&mut store, "outer")
+        .unwrap();
+    let error = outer.call(&mut store, ()).expect_err("should trap");
+
+    assert_eq!(
+        error.downcast_ref::<Trap>(),
+        Some(&Trap::UnreachableCodeReached)
+    );
+
+    let backtrace = error.downcast_ref::<WasmBacktrace>().unwrap();
+    assert_eq!(frame_names(backtrace), ["inner", "middle", "outer"]);
+
+    let indices = backtrace
+        .frames()
+        .iter()
+        .map(|frame| frame.func_index())
+        .collect::<Vec<_>>();
+    assert_eq!(indices, [2, 3, 4]);
+
+    let inner = &backtrace.frames()[0];
+    assert!(inner.module_offset().unwrap() > inner.func_offset().unwrap());
+
+    let text = backtrace.to_string();
+    assert!(text.contains("traced!inner"), "{text}");
+
+    Ok(())
+}
+
+async fn host_backtrace(mut store: &mut Store<()>) -> Result<()> {
+    let instance = instantiate_traced(&mut store).await.unwrap();
+
+    // Capturing the backtrace in an imported function
+    let call_check = instance
+        .get_typed_func::<(), i32>(&mut store, "call_check")
+        .unwrap();
+    assert_eq!(call_check.call(&mut store, ()).unwrap(), 1);
+
+    // Errors from imported functions get the backtrace as well
+    let call_fail = instance
+        .get_typed_func::<(), ()>(&mut store, "call_fail")
+        .unwrap();
+    let error = call_fail
+        .call(&mut store, ())
+        .expect_err("host should fail");
+    assert_eq!(error.root_cause().to_string(), "host failure");
+
+    let backtrace = error.downcast_ref::<WasmBacktrace>().unwrap();
+    assert_eq!(frame_names(backtrace), ["call_fail"]);
+
+    Ok(())
+}
+
+// Only the names differ, so the functions are at the same offsets in both modules
+fn overlapping_wat(name: &str) -> String {
+    format!(
+        r#"(module ${name}
+            (func ${name}_inner
+                unreachable
+            )
+            (func ${name}_outer (export "outer")
+                (call ${name}_inner)
+            )
+        )"#
+    )
+}
+
+async fn overlapping_modules(mut store: &mut Store<()>) -> Result<()> {
+    // Both modules stay loaded
+    let mut instances = Vec::new();
+    for name in ["first", "second"] {
+        let module = Module::new_safe(store.engine(), overlapping_wat(name))
+            .await
+            .unwrap();
+        instances.push(Instance::new_safe(&mut store, &module, &[]).await.unwrap());
+    }
+
+    for (instance, name) in instances.iter().zip(["first", "second"]).rev() {
+        let outer = instance.get_typed_func::<(), ()>(&mut store, "outer")?;
+        let error = outer.call(&mut store, ()).expect_err("should trap");
+        let backtrace = error.downcast_ref::<WasmBacktrace>().unwrap();
+
+        let expected = [format!("{name}_inner"), format!("{name}_outer")];
+        assert_eq!(frame_names(backtrace), expected);
+
+        let text = backtrace.to_string();
+        assert!(text.contains(&format!("{name}!{name}_inner")), "{text}");
+    }
+
+    Ok(())
+}
+
+fn frame_names(backtrace: &WasmBacktrace) -> Vec<&str> {
+    backtrace
+        .frames()
+        .iter()
+        .map(|frame| frame.func_name().unwrap_or("<unknown>"))
+        .collect()
+}
@@ -0,0 +1,18 @@
+#[no_mangle]
+pub fn count_vowels() -> u32 {
+    let text = "Hello from the guest";
+    unsafe { count_vowels_import(text.as_ptr() as u32, text.len() as u32) }
+}
+
+#[no_mangle]
+pub fn sum_filled_buffer() -> u32 {
+    let mut buffer = [0u8; 4];
+    unsafe { fill_buffer_import(buffer.as_mut_ptr() as u32, buffer.len() as u32) };
+    buffer.iter().map(|byte| *byte as u32).sum()
+}
+
+#[link(wasm_import_module = "imported_fns")]
+extern "C" {
+    fn count_vowels_import(ptr: u32, len: u32) -> u32;
+    fn fill_buffer_import(ptr: u32, len: u32);
+}
@@ -0,0 +1,90 @@
+use wasm_bridge::*;
+
+pub async fn run_test(bytes: &[u8]) -> Result<()> {
+    let mut store = Store::<()>::default();
+    let module = Module::new_safe(store.engine(), bytes).await.unwrap();
+
+    let mut linker = Linker::new(store.engine());
+
+    linker.func_wrap(
+        "imported_fns",
+        "count_vowels_import",
+        |mut caller: Caller<()>, ptr: u32, len: u32| {
+            let memory = caller.get_export("memory").unwrap().into_memory().unwrap();
+
+            let mut text = vec![0u8; len as usize];
+            memory.read(&mut caller, ptr as usize, &mut text).unwrap();
+
+            text.iter().filter(|byte| b"aeiou".contains(byte)).count() as u32
+        },
+    ).unwrap();
+
+    linker.func_wrap(
+        "imported_fns",
+        "fill_buffer_import",
+        |mut caller: Caller<()>, ptr: u32, len: u32| {
+            let memory = caller.get_export("memory").unwrap().into_memory().unwrap();
+
+            let buffer = (1..=len as u8).collect::<Vec<_>>();
+            memory.write(&mut caller, ptr as usize, &buffer).unwrap();
+
+            assert!(caller.get_export("non_existing").is_none());
+        },
+    ).unwrap();
+
+    let instance = linker.instantiate_safe(&mut store, &module).await.unwrap();
+
+    let count_vowels = instance.get_typed_func::<(), u32>(&mut store, "count_vowels").unwrap();
+    assert_eq!(count_vowels.call(&mut store, ()).unwrap(), 6);
+
+    let sum_filled_buffer = instance.get_typed_func::<(), u32>(&mut store, "sum_filled_buffer").unwrap();
+    assert_eq!(sum_filled_buffer.call(&mut store, ()).unwrap(), 1 + 2 + 3 + 4);
+
+    wrapped_funcs(&mut store).await.unwrap();
+
+    Ok(())
+}
+
+async fn wrapped_funcs(mut store: &mut Store<()>) -> Result<()> {
+    let wat = r#"(module
+        (import "host" "sum_bytes" (func $sum_bytes (param i32 i32) (result i32)))
+        (memory (export "memory") 1)
+        (data (i32.const 16) "\01\02\03\04")
+        (func (export "sum") (result i32)
+            (call $sum_bytes (i32.const 16) (i32.const 4))
+        )
+    )"#;
+    let module = Module::new_safe(store.engine(), wat.as_bytes()).await.unwrap();
+
+    let sum_bytes = |mut caller: Caller<()>, ptr: u32, len: u32| {
+        let memory = caller.get_export("memory").unwrap().into_memory().unwrap();
+
+        let mut bytes = vec![0u8; len as usize];
+        memory.read(&mut caller, ptr as usize, &mut bytes).unwrap();
+
+        bytes.iter().map(|byte| *byte as u32).sum::<u32>()
+    };
+
+    // A function passed directly to the instance
+    let func = Func::wrap(&mut store, sum_bytes);
+    let instance = Instance::new_safe(&mut store, &module, &[func.clone().into()]).await.unwrap();
+    let sum = instance.get_typed_func::<(), u32>(&mut store, "sum").unwrap();
+    assert_eq!(sum.call(&mut store, ()).unwrap(), 10);
+
+    // A function defined in a linker
+    let mut linker = Linker::new(store.engine());
+    linker.define(&store, "host", "sum_bytes", func).unwrap();
+    let instance = linker.instantiate_safe(&mut store, &module).await.unwrap();
+    let sum = instance.get_typed_func::<(), u32>(&mut store, "sum").unwrap();
+    assert_eq!(sum.call(&mut store, ()).unwrap(), 10);
+
+    // A function taken out of a linker
+    let mut linker = Linker::new(store.engine());
+    linker.func_wrap("host", "sum_bytes", sum_bytes).unwrap();
+    let func = linker.get(&mut store, "host", "sum_bytes").unwrap();
+    let instance = Instance::new_safe(&mut store, &module, &[func]).await.unwrap();
+    let sum = instance.get_typed_func::<(), u32>(&mut store, "sum").unwrap();
+    assert_eq!(sum.call(&mut store, ()).unwrap(), 10);
+
+    Ok(())
+}
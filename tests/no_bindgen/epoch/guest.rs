@@ -0,0 +1,14 @@
+#[link(wasm_import_module = "host")]
+extern "C" {
+    fn tick();
+}
+
+#[no_mangle]
+pub fn run(ticks: u32) -> u32 {
+    let mut count = 0;
+    while count < ticks {
+        unsafe { tick() };
+        count += 1;
+    }
+    count
+}
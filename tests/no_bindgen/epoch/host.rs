@@ -0,0 +1,46 @@
+use wasm_bridge::*;
+
+pub async fn run_test(bytes: &[u8]) -> Result<()> {
+    let mut config = Config::new();
+    config.epoch_interruption(true);
+    let engine = Engine::new(&config)?;
+
+    // The store data is the number of ticks after which the epoch is incremented
+    let mut store = Store::new(&engine, 0u32);
+    let module = Module::new_safe(&engine, bytes).await?;
+
+    let mut linker = Linker::new(&engine);
+    linker.func_wrap("host", "tick", |mut caller: Caller<u32>| {
+        let remaining = *caller.data();
+        if remaining == 1 {
+            caller.engine().increment_epoch();
+        }
+        *caller.data_mut() = remaining.saturating_sub(1);
+    })?;
+
+    let instance = linker.instantiate_safe(&mut store, &module).await?;
+    let run = instance.get_typed_func::<u32, u32>(&mut store, "run")?;
+
+    // Stores start with a deadline of 0
+    let error = run.call(&mut store, 10).unwrap_err();
+    assert_eq!(error.downcast_ref::<Trap>(), Some(&Trap::Interrupt));
+
+    // The deadline is not reached
+    store.set_epoch_deadline(1);
+    assert_eq!(run.call(&mut store, 10)?, 10);
+
+    // The epoch is incremented by the host while the guest is running
+    *store.data_mut() = 5;
+    let error = run.call(&mut store, 100).unwrap_err();
+    assert_eq!(error.downcast_ref::<Trap>(), Some(&Trap::Interrupt));
+
+    // The epoch can also be incremented between calls
+    store.set_epoch_deadline(2);
+    engine.increment_epoch();
+    assert_eq!(run.call(&mut store, 10)?, 10);
+    engine.increment_epoch();
+    let error = run.call(&mut store, 10).unwrap_err();
+    assert_eq!(error.downcast_ref::<Trap>(), Some(&Trap::Interrupt));
+
+    Ok(())
+}
@@ -0,0 +1,17 @@
+use std::hint::black_box;
+
+#[no_mangle]
+pub fn count_to(limit: u32) -> u32 {
+    let mut count = 0;
+    while count < limit {
+        count = black_box(count + 1);
+    }
+    count
+}
+
+#[no_mangle]
+pub fn spin() {
+    loop {
+        black_box(());
+    }
+}
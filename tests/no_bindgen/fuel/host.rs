@@ -0,0 +1,116 @@
+use wasm_bridge::*;
+
+pub async fn run_test(bytes: &[u8]) -> Result<()> {
+    let mut config = Config::new();
+    config.consume_fuel(true);
+    let engine = Engine::new(&config)?;
+
+    let mut store = Store::new(&engine, ());
+    let module = Module::new_safe(&engine, bytes).await?;
+    let instance = Instance::new_safe(&mut store, &module, &[]).await?;
+    assert_eq!(module.imports().len(), 0);
+
+    // Stores start with no fuel
+    assert_eq!(store.get_fuel()?, 0);
+    let count_to = instance.get_typed_func::<u32, u32>(&mut store, "count_to")?;
+    let error = count_to.call(&mut store, 10).unwrap_err();
+    assert_eq!(error.downcast_ref::<Trap>(), Some(&Trap::OutOfFuel));
+
+    store.set_fuel(10_000)?;
+    assert_eq!(count_to.call(&mut store, 10)?, 10);
+    let short_run = 10_000 - store.get_fuel()?;
+    assert!(short_run > 0);
+
+    store.set_fuel(10_000)?;
+    assert_eq!(count_to.call(&mut store, 100)?, 100);
+    let long_run = 10_000 - store.get_fuel()?;
+    assert!(long_run > short_run * 5);
+
+    // Infinite loops are stopped
+    store.set_fuel(10_000)?;
+    let spin = instance.get_typed_func::<(), ()>(&mut store, "spin")?;
+    let error = spin.call(&mut store, ()).unwrap_err();
+    assert_eq!(error.downcast_ref::<Trap>(), Some(&Trap::OutOfFuel));
+    assert_eq!(store.get_fuel()?, 0);
+
+    // The instance can run again after refueling
+    store.set_fuel(10_000)?;
+    assert_eq!(count_to.call(&mut store, 5)?, 5);
+
+    exact_costs(&engine).await?;
+
+    #[cfg(target_arch = "wasm32")]
+    typed_references(&engine).await?;
+
+    // Fuel is not configured by default
+    let mut store = Store::<()>::default();
+    assert!(store.get_fuel().is_err());
+    assert!(store.set_fuel(100).is_err());
+
+    Ok(())
+}
+
+// Same costs as wasmtime, every instruction costs 1, except for `nop`, `drop` and control flow.
+// Entering a function costs 1 too, so that even empty functions consume fuel.
+async fn exact_costs(engine: &Engine) -> Result<()> {
+    let wat = r#"(module
+        (func $one (result i32) (i32.const 1))
+        (func (export "add") (param i32 i32) (result i32)
+            (i32.add (local.get 0) (local.get 1))
+        )
+        (func (export "call_one") (result i32)
+            (block (nop))
+            (call $one)
+        )
+    )"#;
+
+    let mut store = Store::new(engine, ());
+    let module = Module::new_safe(engine, wat).await?;
+    let instance = Instance::new_safe(&mut store, &module, &[]).await?;
+
+    store.set_fuel(100)?;
+    let add = instance.get_typed_func::<(i32, i32), i32>(&mut store, "add")?;
+    assert_eq!(add.call(&mut store, (1, 2))?, 3);
+    assert_eq!(store.get_fuel()?, 100 - 4);
+
+    // The `call` instruction and the entry of the called function are both charged
+    store.set_fuel(100)?;
+    let call_one = instance.get_typed_func::<(), i32>(&mut store, "call_one")?;
+    assert_eq!(call_one.call(&mut store, ())?, 1);
+    assert_eq!(store.get_fuel()?, 100 - 4);
+
+    Ok(())
+}
+
+// Wasmtime doesn't enable typed function references by default
+#[cfg(target_arch = "wasm32")]
+async fn typed_references(engine: &Engine) -> Result<()> {
+    let wat = r#"(module
+        (type $get (func (result i32)))
+        (func $seven (type $get) (i32.const 7))
+        (elem declare func $seven)
+        (func (export "call_if_set") (param $set i32) (result i32)
+            (local $func (ref null $get))
+            (if (local.get $set) (then (local.set $func (ref.func $seven))))
+            (block $null
+                (br_on_null $null (local.get $func))
+                ref.as_non_null
+                call_ref $get
+                return
+            )
+            (i32.const -1)
+        )
+    )"#;
+
+    let mut store = Store::new(engine, ());
+    let module = Module::new_safe(engine, wat).await?;
+    let instance = Instance::new_safe(&mut store, &module, &[]).await?;
+    let call_if_set = instance.get_typed_func::<u32, i32>(&mut store, "call_if_set")?;
+
+    store.set_fuel(10_000)?;
+    assert_eq!(call_if_set.call(&mut store, 1)?, 7);
+    assert_eq!(call_if_set.call(&mut store, 0)?, -1);
+    assert!(store.get_fuel()? < 10_000);
+
+    Ok(())
+}
@@ -0,0 +1,9 @@
+#[no_mangle]
+pub fn add_i32(a: i32, b: i32) -> i32 {
+    a + b
+}
+
+#[no_mangle]
+pub fn scale_f64(value: f64, factor: f32) -> f64 {
+    value * factor as f64
+}
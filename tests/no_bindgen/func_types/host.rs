@@ -0,0 +1,149 @@
+use wasm_bridge::*;
+
+pub async fn run_test(bytes: &[u8]) -> Result<()> {
+    let mut store = Store::<i32>::default();
+
+    exported_types(&mut store, bytes).await.unwrap();
+    typed_mismatch(&mut store, bytes).await.unwrap();
+    host_funcs(&mut store).await.unwrap();
+    exported_extern_types(&mut store).await.unwrap();
+
+    Ok(())
+}
+
+async fn exported_types(mut store: &mut Store<i32>, bytes: &[u8]) -> Result<()> {
+    let module = Module::new_safe(store.engine(), bytes).await.unwrap();
+    let instance = Instance::new_safe(&mut store, &module, &[]).await.unwrap();
+
+    let add_i32 = instance.get_func(&mut store, "add_i32").unwrap();
+    let ty = add_i32.ty(&store);
+    assert!(is_types(ty.params(), &[ValType::is_i32, ValType::is_i32]));
+    assert!(is_types(ty.results(), &[ValType::is_i32]));
+
+    let scale_f64 = instance.get_func(&mut store, "scale_f64").unwrap();
+    let ty = scale_f64.ty(&store);
+    assert!(is_types(ty.params(), &[ValType::is_f64, ValType::is_f32]));
+    assert!(is_types(ty.results(), &[ValType::is_f64]));
+
+    let typed = scale_f64.typed::<(f64, f32), f64>(&store).unwrap();
+    assert_eq!(typed.call(&mut store, (2.5, 4.0)).unwrap(), 10.0);
+
+    Ok(())
+}
+
+async fn typed_mismatch(mut store: &mut Store<i32>, bytes: &[u8]) -> Result<()> {
+    let module = Module::new_safe(store.engine(), bytes).await.unwrap();
+    let instance = Instance::new_safe(&mut store, &module, &[]).await.unwrap();
+
+    let add_i32 = instance.get_func(&mut store, "add_i32").unwrap();
+    assert!(add_i32.typed::<(i32, i32), i32>(&store).is_ok());
+
+    // Same number of arguments, but different types
+    assert!(add_i32.typed::<(i64, i32), i32>(&store).is_err());
+    assert!(add_i32.typed::<(i32, i32), f32>(&store).is_err());
+    assert!(add_i32.typed::<(i32, i32), ()>(&store).is_err());
+
+    assert!(instance
+        .get_typed_func::<(f64, f64), f64>(&mut store, "scale_f64")
+        .is_err());
+    assert!(instance
+        .get_typed_func::<(), ()>(&mut store, "nonexistent")
+        .is_err());
+
+    Ok(())
+}
+
+async fn host_funcs(mut store: &mut Store<i32>) -> Result<()> {
+    let wat = r#"(module
+        (import "host" "add_to_data" (func $add_to_data (param i32) (result i32)))
+        (import "host" "mul" (func $mul (param i64 i64) (result i64)))
+        (func (export "add_twice") (param i32) (result i32)
+            (call $add_to_data (call $add_to_data (local.get 0)))
+        )
+        (func (export "square") (param i64) (result i64)
+            (call $mul (local.get 0) (local.get 0))
+        )
+    )"#;
+
+    *store.data_mut() = 10;
+
+    let add_to_data = Func::wrap(&mut store, |caller: Caller<i32>, value: i32| {
+        *caller.data() + value
+    });
+    let ty = add_to_data.ty(&store);
+    assert!(is_types(ty.params(), &[ValType::is_i32]));
+    assert!(is_types(ty.results(), &[ValType::is_i32]));
+
+    let mul_ty = FuncType::new(store.engine(), [ValType::I64, ValType::I64], [ValType::I64]);
+    let mul = Func::new(&mut store, mul_ty, |_caller, args, rets| {
+        rets[0] = Val::I64(args[0].i64().unwrap() * args[1].i64().unwrap());
+        Ok(())
+    });
+    let ty = mul.ty(&store);
+    assert!(is_types(ty.params(), &[ValType::is_i64, ValType::is_i64]));
+    assert!(is_types(ty.results(), &[ValType::is_i64]));
+
+    // Host functions can be called directly too
+    let add_to_data_typed = add_to_data.typed::<i32, i32>(&store).unwrap();
+    assert_eq!(add_to_data_typed.call(&mut store, 5).unwrap(), 15);
+
+    let module = Module::new_safe(store.engine(), wat.as_bytes())
+        .await
+        .unwrap();
+    let instance = Instance::new_safe(&mut store, &module, &[add_to_data.into(), mul.into()])
+        .await
+        .unwrap();
+
+    let add_twice = instance
+        .get_typed_func::<i32, i32>(&mut store, "add_twice")
+        .unwrap();
+    assert_eq!(add_twice.call(&mut store, 1).unwrap(), 21);
+
+    let square = instance
+        .get_typed_func::<i64, i64>(&mut store, "square")
+        .unwrap();
+    assert_eq!(square.call(&mut store, 7).unwrap(), 49);
+
+    Ok(())
+}
+
+async fn exported_extern_types(mut store: &mut Store<i32>) -> Result<()> {
+    let wat = r#"(module
+        (memory (export "memory") 1 5)
+        (global (export "pi") f64 (f64.const 3.0))
+        (global (export "counter") (mut i64) (i64.const 7))
+        (table (export "table") 2 10 funcref)
+    )"#;
+
+    let module = Module::new_safe(store.engine(), wat.as_bytes())
+        .await
+        .unwrap();
+    let instance = Instance::new_safe(&mut store, &module, &[]).await.unwrap();
+
+    let memory = instance.get_memory(&mut store, "memory").unwrap();
+    assert_eq!(memory.ty(&store).minimum(), 1);
+    assert_eq!(memory.ty(&store).maximum(), Some(5));
+
+    // The value is a whole number, but the type must still be read as f64
+    let pi = instance.get_global(&mut store, "pi").unwrap();
+    assert!(pi.ty(&store).content().is_f64());
+    assert_eq!(pi.ty(&store).mutability(), Mutability::Const);
+    assert_eq!(pi.get(&mut store).f64().unwrap(), 3.0);
+
+    let counter = instance.get_global(&mut store, "counter").unwrap();
+    assert!(counter.ty(&store).content().is_i64());
+    assert_eq!(counter.ty(&store).mutability(), Mutability::Var);
+
+    let table = instance.get_table(&mut store, "table").unwrap();
+    assert_eq!(table.ty(&store).minimum(), 2);
+    assert_eq!(table.ty(&store).maximum(), Some(10));
+
+    Ok(())
+}
+
+fn is_types(
+    types: impl ExactSizeIterator<Item = ValType>,
+    checks: &[fn(&ValType) -> bool],
+) -> bool {
+    types.len() == checks.len() && types.zip(checks).all(|(ty, check)| check(&ty))
+}
@@ -0,0 +1,209 @@
+use wasm_bridge::*;
+
+pub async fn run_test(bytes: &[u8]) -> Result<()> {
+    let mut store = Store::<()>::default();
+
+    exported_globals(&mut store, bytes).await.unwrap();
+    globals(&mut store).await.unwrap();
+    tables(&mut store).await.unwrap();
+    host_funcs_in_table(&mut store).await.unwrap();
+    host_func_in_global(&mut store).unwrap();
+
+    Ok(())
+}
+
+async fn exported_globals(mut store: &mut Store<()>, bytes: &[u8]) -> Result<()> {
+    let module = Module::new_safe(store.engine(), bytes).await.unwrap();
+    let instance = Instance::new_safe(&mut store, &module, &[]).await.unwrap();
+
+    let heap_base = instance.get_global(&mut store, "__heap_base").unwrap();
+    assert!(heap_base.get(&mut store).i32().unwrap() > 0);
+
+    assert!(instance.get_global(&mut store, "memory").is_none());
+    assert!(instance.get_global(&mut store, "get_five").is_none());
+    assert!(instance.get_memory(&mut store, "__heap_base").is_none());
+
+    let get_five = instance.get_export(&mut store, "get_five").unwrap().into_func();
+    assert!(get_five.is_some());
+
+    Ok(())
+}
+
+async fn globals(mut store: &mut Store<()>) -> Result<()> {
+    let wat = r#"(module
+        (import "host" "offset" (global $offset (mut i32)))
+        (global $counter (export "counter") (mut i32) (i32.const 10))
+        (global $big (export "big") i64 (i64.const 5000000000))
+        (global $ratio (export "ratio") (mut f64) (f64.const 3.0))
+        (global $scale (export "scale") f32 (f32.const 2.0))
+        (func $get_counter (export "get_counter") (result i32)
+            (i32.add (global.get $counter) (global.get $offset))
+        )
+    )"#;
+
+    let module = Module::new_safe(store.engine(), wat.as_bytes()).await.unwrap();
+
+    let offset = Global::new(
+        &mut store,
+        GlobalType::new(ValType::I32, Mutability::Var),
+        Val::I32(100),
+    ).unwrap();
+
+    let mut linker = Linker::new(store.engine());
+    linker.define(&mut store, "host", "offset", offset.clone()).unwrap();
+    let instance = linker.instantiate_safe(&mut store, &module).await.unwrap();
+
+    let get_counter = instance.get_typed_func::<(), i32>(&mut store, "get_counter").unwrap();
+    assert_eq!(get_counter.call(&mut store, ()).unwrap(), 110);
+
+    let counter = instance.get_global(&mut store, "counter").unwrap();
+    assert_eq!(counter.get(&mut store).i32().unwrap(), 10);
+    counter.set(&mut store, Val::I32(11)).unwrap();
+    assert_eq!(counter.get(&mut store).i32().unwrap(), 11);
+    assert_eq!(get_counter.call(&mut store, ()).unwrap(), 111);
+
+    offset.set(&mut store, Val::I32(200)).unwrap();
+    assert_eq!(get_counter.call(&mut store, ()).unwrap(), 211);
+
+    counter
+        .set(&mut store, Val::I64(5))
+        .expect_err("setting a global to a value of wrong type");
+
+    let big = instance.get_global(&mut store, "big").unwrap();
+    assert_eq!(big.get(&mut store).i64().unwrap(), 5_000_000_000);
+    big.set(&mut store, Val::I64(5))
+        .expect_err("setting an immutable global");
+
+    // Whole-valued floats keep their real types
+    let ratio = instance.get_global(&mut store, "ratio").unwrap();
+    assert!(matches!(ratio.ty(&store).content(), ValType::F64));
+    assert_eq!(ratio.ty(&store).mutability(), Mutability::Var);
+    ratio.set(&mut store, Val::F64(2.5f64.to_bits())).unwrap();
+    assert_eq!(ratio.get(&mut store).f64().unwrap(), 2.5);
+
+    let scale = instance.get_global(&mut store, "scale").unwrap();
+    assert!(matches!(scale.ty(&store).content(), ValType::F32));
+    assert_eq!(scale.ty(&store).mutability(), Mutability::Const);
+    assert_eq!(scale.get(&mut store).f32().unwrap(), 2.0);
+    scale
+        .set(&mut store, Val::F32(1.0f32.to_bits()))
+        .expect_err("setting an immutable global");
+
+    Ok(())
+}
+
+async fn tables(mut store: &mut Store<()>) -> Result<()> {
+    let wat = r#"(module
+        (type $t0 (func (result i32)))
+        (table $table (export "table") 2 10 funcref)
+        (func $forty_two (type $t0)
+            (i32.const 42)
+        )
+        (elem (i32.const 0) $forty_two)
+        (func $call_indirect (export "call_indirect") (param $p0 i32) (result i32)
+            (call_indirect (type $t0) (local.get $p0))
+        )
+    )"#;
+
+    let module = Module::new_safe(store.engine(), wat.as_bytes()).await.unwrap();
+    let instance = Instance::new_safe(&mut store, &module, &[]).await.unwrap();
+
+    let table = instance.get_table(&mut store, "table").unwrap();
+    assert_eq!(table.size(&mut store), 2);
+
+    let first = table.get(&mut store, 0).unwrap();
+    assert!(!first.is_null());
+    assert!(table.get(&mut store, 1).unwrap().is_null());
+    assert!(table.get(&mut store, 2).is_none());
+
+    let mut results = [Val::I32(0)];
+    first
+        .unwrap_func()
+        .unwrap()
+        .call(&mut store, &[], &mut results)
+        .unwrap();
+    assert_eq!(results[0].i32().unwrap(), 42);
+
+    assert_eq!(table.grow(&mut store, 3, Ref::Func(None)).unwrap(), 2);
+    assert_eq!(table.size(&mut store), 5);
+    table
+        .grow(&mut store, 10, Ref::Func(None))
+        .expect_err("growing the table over its maximum");
+
+    table.set(&mut store, 4, first.clone()).unwrap();
+    let call_indirect = instance.get_typed_func::<i32, i32>(&mut store, "call_indirect").unwrap();
+    assert_eq!(call_indirect.call(&mut store, 4).unwrap(), 42);
+
+    call_indirect
+        .call(&mut store, 3)
+        .expect_err("calling a null table element");
+
+    Ok(())
+}
+
+async fn host_funcs_in_table(mut store: &mut Store<()>) -> Result<()> {
+    let wat = r#"(module
+        (type $t0 (func (result i32)))
+        (import "host" "table" (table $table 2 funcref))
+        (func $call_indirect (export "call_indirect") (param $p0 i32) (result i32)
+            (call_indirect (type $t0) (local.get $p0))
+        )
+    )"#;
+
+    let ty = TableType::new(RefType::FUNCREF, 1, None);
+    let table = Table::new(&mut store, ty, Ref::Func(None)).unwrap();
+
+    // The table keeps the host functions alive after they are dropped
+    let seven = Func::wrap(&mut store, |_: Caller<()>| 7i32);
+    table.set(&mut store, 0, seven.into()).unwrap();
+    let eight = Func::wrap(&mut store, |_: Caller<()>| 8i32);
+    assert_eq!(table.grow(&mut store, 1, eight.into()).unwrap(), 1);
+
+    let module = Module::new_safe(store.engine(), wat.as_bytes()).await.unwrap();
+    let instance = Instance::new_safe(&mut store, &module, &[table.clone().into()])
+        .await
+        .unwrap();
+
+    let call_indirect = instance.get_typed_func::<i32, i32>(&mut store, "call_indirect").unwrap();
+    assert_eq!(call_indirect.call(&mut store, 0).unwrap(), 7);
+    assert_eq!(call_indirect.call(&mut store, 1).unwrap(), 8);
+
+    let eight = table.get(&mut store, 1).unwrap();
+    let mut results = [Val::I32(0)];
+    eight
+        .unwrap_func()
+        .unwrap()
+        .call(&mut store, &[], &mut results)
+        .unwrap();
+    assert_eq!(results[0].i32().unwrap(), 8);
+
+    Ok(())
+}
+
+fn host_func_in_global(mut store: &mut Store<()>) -> Result<()> {
+    let call = |store: &mut Store<()>, func: &Func| {
+        let mut results = [Val::I32(0)];
+        func.call(store, &[], &mut results).unwrap();
+        results[0].i32().unwrap()
+    };
+
+    // The global keeps the host functions alive after they are dropped
+    let seven = Func::wrap(&mut store, |_: Caller<()>| 7i32);
+    let ty = GlobalType::new(ValType::FUNCREF, Mutability::Var);
+    let global = Global::new(&mut store, ty, seven.into()).unwrap();
+
+    let seven = global.get(&mut store).unwrap_funcref().unwrap().clone();
+    assert_eq!(call(store, &seven), 7);
+
+    let eight = Func::wrap(&mut store, |_: Caller<()>| 8i32);
+    global.set(&mut store, eight.into()).unwrap();
+    let eight = global.get(&mut store).unwrap_funcref().unwrap().clone();
+    assert_eq!(call(store, &eight), 8);
+
+    // So does a function taken out of the global
+    global.set(&mut store, Val::null_func_ref()).unwrap();
+    assert_eq!(call(store, &seven), 7);
+    assert_eq!(call(store, &eight), 8);
+
+    Ok(())
+}
@@ -0,0 +1,4 @@
+#[no_mangle]
+pub fn get_five() -> i32 {
+    5
+}
@@ -0,0 +1,88 @@
+use wasm_bridge::*;
+
+pub async fn run_test(bytes: &[u8]) -> Result<()> {
+    let mut store = Store::<()>::default();
+
+    from_guest(&mut store, bytes).await.unwrap();
+    from_other_instance(&mut store).await.unwrap();
+
+    Ok(())
+}
+
+async fn from_guest(mut store: &mut Store<()>, bytes: &[u8]) -> Result<()> {
+    let wat = r#"(module
+        (import "guest" "get_five" (func $get_five (result i32)))
+        (func $get_ten (export "get_ten") (result i32)
+            (i32.add (call $get_five) (call $get_five))
+        )
+    )"#;
+
+    let guest_module = Module::new_safe(store.engine(), bytes).await.unwrap();
+    let guest = Instance::new_safe(&mut store, &guest_module, &[]).await.unwrap();
+    let get_five = guest.get_export(&mut store, "get_five").unwrap();
+
+    let module = Module::new_safe(store.engine(), wat.as_bytes()).await.unwrap();
+    let instance = Instance::new_safe(&mut store, &module, &[get_five]).await.unwrap();
+
+    let get_ten = instance.get_typed_func::<(), i32>(&mut store, "get_ten").unwrap();
+    assert_eq!(get_ten.call(&mut store, ()).unwrap(), 10);
+
+    Ok(())
+}
+
+async fn from_other_instance(mut store: &mut Store<()>) -> Result<()> {
+    let exporter_wat = r#"(module
+        (memory (export "memory") 1)
+        (global (export "base") i32 (i32.const 7))
+        (func $add (export "add") (param $p0 i32) (param $p1 i32) (result i32)
+            (i32.add (local.get $p0) (local.get $p1))
+        )
+    )"#;
+
+    let importer_wat = r#"(module
+        (import "env" "add" (func $add (param i32 i32) (result i32)))
+        (import "env" "memory" (memory 1))
+        (import "env" "base" (global $base i32))
+        (func $add_base_and_store (export "add_base_and_store") (param $p0 i32)
+            (i32.store (i32.const 0) (call $add (local.get $p0) (global.get $base)))
+        )
+    )"#;
+
+    let exporter_module = Module::new_safe(store.engine(), exporter_wat.as_bytes()).await.unwrap();
+    let exporter = Instance::new_safe(&mut store, &exporter_module, &[]).await.unwrap();
+
+    let add = exporter.get_export(&mut store, "add").unwrap();
+    let memory = exporter.get_export(&mut store, "memory").unwrap();
+    let base = exporter.get_export(&mut store, "base").unwrap();
+
+    let importer_module = Module::new_safe(store.engine(), importer_wat.as_bytes()).await.unwrap();
+
+    Instance::new_safe(&mut store, &importer_module, &[add.clone(), memory.clone()])
+        .await
+        .map(|_| ())
+        .expect_err("missing an import");
+
+    Instance::new_safe(&mut store, &importer_module, &[add.clone(), base.clone(), memory.clone()])
+        .await
+        .map(|_| ())
+        .expect_err("imports in the wrong order");
+
+    let importer = Instance::new_safe(&mut store, &importer_module, &[add, memory, base])
+        .await
+        .unwrap();
+
+    let add_base_and_store = importer
+        .get_typed_func::<i32, ()>(&mut store, "add_base_and_store")
+        .unwrap();
+    add_base_and_store.call(&mut store, 5).unwrap();
+
+    let mut stored = [0u8; 4];
+    exporter
+        .get_memory(&mut store, "memory")
+        .unwrap()
+        .read(&mut store, 0, &mut stored)
+        .unwrap();
+    assert_eq!(i32::from_le_bytes(stored), 12);
+
+    Ok(())
+}
@@ -0,0 +1,4 @@
+#[no_mangle]
+pub fn grow(pages: u32) -> i32 {
+    core::arch::wasm32::memory_grow::<0>(pages as usize) as i32
+}
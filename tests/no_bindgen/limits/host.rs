@@ -0,0 +1,101 @@
+use wasm_bridge::*;
+
+const PAGE_SIZE: usize = 0x10000;
+
+pub async fn run_test(bytes: &[u8]) -> Result<()> {
+    let engine = limited_engine()?;
+    let module = Module::new_safe(&engine, bytes).await?;
+
+    let limits = StoreLimitsBuilder::new()
+        .memory_size(64 * PAGE_SIZE)
+        .table_elements(10)
+        .instances(1)
+        .build();
+    let mut store = Store::new(&engine, limits);
+    store.limiter(|limits| limits);
+
+    // Failed instantiations do not count against the limit
+    let importing = Module::new_safe(&engine, r#"(module (import "env" "f" (func)))"#).await?;
+    let mut linker = Linker::new(&engine);
+    let not_func = Global::new(
+        &mut store,
+        GlobalType::new(ValType::I32, Mutability::Const),
+        Val::I32(0),
+    )?;
+    linker.define(&store, "env", "f", not_func)?;
+    assert!(linker
+        .instantiate_safe(&mut store, &importing)
+        .await
+        .is_err());
+
+    let instance = Instance::new_safe(&mut store, &module, &[]).await?;
+    let memory = instance.get_memory(&mut store, "memory").unwrap();
+    let initial = memory.size(&store) as i32;
+    assert!(initial < 64);
+
+    // The guest can grow its memory up to the limit, but not past it.
+    // On the web, the guest grows its memory without asking the limiter.
+    let grow = instance.get_typed_func::<u32, i32>(&mut store, "grow")?;
+    assert_eq!(grow.call(&mut store, 1)?, initial);
+    #[cfg(not(target_arch = "wasm32"))]
+    assert_eq!(grow.call(&mut store, 1000)?, -1);
+    assert_eq!(grow.call(&mut store, (63 - initial) as u32)?, initial + 1);
+    assert_eq!(memory.size(&store), 64);
+    #[cfg(not(target_arch = "wasm32"))]
+    assert_eq!(grow.call(&mut store, 1)?, -1);
+
+    // The host cannot grow it past the limit
+    assert!(memory.grow(&mut store, 1).is_err());
+
+    // Only one instance is allowed
+    assert!(Instance::new_safe(&mut store, &module, &[]).await.is_err());
+
+    // Memories and tables created by the host are limited as well
+    assert!(Memory::new(&mut store, MemoryType::new(65, None)).is_err());
+    let memory = Memory::new(&mut store, MemoryType::new(1, None))?;
+    assert_eq!(memory.grow(&mut store, 63)?, 1);
+    assert!(memory.grow(&mut store, 1).is_err());
+
+    let ty = TableType::new(RefType::FUNCREF, 11, None);
+    assert!(Table::new(&mut store, ty, Ref::Func(None)).is_err());
+    let ty = TableType::new(RefType::FUNCREF, 1, None);
+    let table = Table::new(&mut store, ty, Ref::Func(None))?;
+    assert_eq!(table.grow(&mut store, 9, Ref::Func(None))?, 1);
+    assert!(table.grow(&mut store, 1, Ref::Func(None)).is_err());
+
+    // Memories and tables that could not be created do not count against the limit
+    let limits = StoreLimitsBuilder::new()
+        .memory_size(PAGE_SIZE)
+        .table_elements(1)
+        .memories(1)
+        .tables(1)
+        .build();
+    let mut store = Store::new(&engine, limits);
+    store.limiter(|limits| limits);
+    assert!(Memory::new(&mut store, MemoryType::new(2, None)).is_err());
+    Memory::new(&mut store, MemoryType::new(1, None))?;
+    let ty = TableType::new(RefType::FUNCREF, 2, None);
+    assert!(Table::new(&mut store, ty, Ref::Func(None)).is_err());
+    let ty = TableType::new(RefType::FUNCREF, 1, None);
+    Table::new(&mut store, ty, Ref::Func(None))?;
+
+    // Stores without a limiter are not limited
+    let mut store = Store::new(&engine, ());
+    let instance = Instance::new_safe(&mut store, &module, &[]).await?;
+    let grow = instance.get_typed_func::<u32, i32>(&mut store, "grow")?;
+    assert!(grow.call(&mut store, 1000)? > 0);
+
+    Ok(())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn limited_engine() -> Result<Engine> {
+    Ok(Engine::default())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn limited_engine() -> Result<Engine> {
+    let mut config = Config::new();
+    config.resource_limits(true);
+    Engine::new(&config)
+}
@@ -0,0 +1,9 @@
+#[no_mangle]
+pub fn two_ids() -> i32 {
+    unsafe { next_id() * 100 + next_id() }
+}
+
+#[link(wasm_import_module = "runtime")]
+extern "C" {
+    fn next_id() -> i32;
+}
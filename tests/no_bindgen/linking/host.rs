@@ -0,0 +1,158 @@
+use wasm_bridge::*;
+
+pub async fn run_test(bytes: &[u8]) -> Result<()> {
+    shared_runtime(bytes).await.unwrap();
+    linked_instance().await.unwrap();
+    alias_and_get().await.unwrap();
+
+    Ok(())
+}
+
+const RUNTIME_WAT: &str = r#"(module
+    (global $counter (mut i32) (i32.const 0))
+    (func (export "_initialize")
+        (global.set $counter (i32.const 10))
+    )
+    (func (export "next_id") (result i32)
+        (global.set $counter (i32.add (global.get $counter) (i32.const 1)))
+        (global.get $counter)
+    )
+)"#;
+
+async fn shared_runtime(bytes: &[u8]) -> Result<()> {
+    let mut store = Store::<()>::default();
+    let runtime = Module::new_safe(store.engine(), RUNTIME_WAT.as_bytes())
+        .await
+        .unwrap();
+
+    let mut linker = Linker::new(store.engine());
+    linker
+        .module_safe(&mut store, "runtime", &runtime)
+        .await
+        .unwrap();
+
+    let guest = Module::new_safe(store.engine(), bytes).await.unwrap();
+    let instance = linker.instantiate_safe(&mut store, &guest).await.unwrap();
+    let two_ids = instance
+        .get_typed_func::<(), i32>(&mut store, "two_ids")
+        .unwrap();
+    assert_eq!(two_ids.call(&mut store, ()).unwrap(), 1112);
+
+    // A second guest module shares the same runtime instance
+    let other_wat = r#"(module
+        (import "runtime" "next_id" (func $next_id (result i32)))
+        (func (export "one_id") (result i32)
+            (call $next_id)
+        )
+    )"#;
+    let other = Module::new_safe(store.engine(), other_wat.as_bytes())
+        .await
+        .unwrap();
+    let other = linker.instantiate_safe(&mut store, &other).await.unwrap();
+    let one_id = other
+        .get_typed_func::<(), i32>(&mut store, "one_id")
+        .unwrap();
+    assert_eq!(one_id.call(&mut store, ()).unwrap(), 13);
+
+    Ok(())
+}
+
+async fn linked_instance() -> Result<()> {
+    let mut store = Store::<()>::default();
+
+    let provider_wat = r#"(module
+        (memory (export "memory") 1)
+        (func (export "store") (param i32 i32)
+            (i32.store (local.get 0) (local.get 1))
+        )
+    )"#;
+    let provider = Module::new_safe(store.engine(), provider_wat.as_bytes())
+        .await
+        .unwrap();
+
+    let mut linker = Linker::new(store.engine());
+    let provider = linker
+        .instantiate_safe(&mut store, &provider)
+        .await
+        .unwrap();
+    let memory = provider.get_memory(&mut store, "memory").unwrap();
+    linker.instance(&mut store, "provider", provider).unwrap();
+
+    let user_wat = r#"(module
+        (import "provider" "memory" (memory 1))
+        (import "provider" "store" (func $store (param i32 i32)))
+        (func (export "store_and_load") (param i32) (result i32)
+            (call $store (i32.const 16) (local.get 0))
+            (i32.load (i32.const 16))
+        )
+    )"#;
+    let user = Module::new_safe(store.engine(), user_wat.as_bytes())
+        .await
+        .unwrap();
+    let user = linker.instantiate_safe(&mut store, &user).await.unwrap();
+
+    let store_and_load = user
+        .get_typed_func::<i32, i32>(&mut store, "store_and_load")
+        .unwrap();
+    assert_eq!(store_and_load.call(&mut store, 1234).unwrap(), 1234);
+
+    let mut bytes = [0u8; 4];
+    memory.read(&store, 16, &mut bytes).unwrap();
+    assert_eq!(i32::from_le_bytes(bytes), 1234);
+
+    Ok(())
+}
+
+async fn alias_and_get() -> Result<()> {
+    let mut store = Store::<()>::default();
+
+    let mut linker = Linker::new(store.engine());
+    linker
+        .func_wrap("host", "double", |_caller: Caller<()>, value: i32| {
+            value * 2
+        })
+        .unwrap();
+    linker.alias("host", "double", "env", "twice").unwrap();
+    assert!(linker.alias("host", "missing", "env", "other").is_err());
+
+    let global = Global::new(
+        &mut store,
+        GlobalType::new(ValType::I32, Mutability::Const),
+        Val::I32(7),
+    )
+    .unwrap();
+    linker.define(&mut store, "host", "seven", global).unwrap();
+    linker.alias("host", "seven", "env", "seven").unwrap();
+
+    let twice = linker.get(&mut store, "env", "twice").unwrap();
+    let twice = twice.into_func().unwrap();
+    let ty = twice.ty(&store);
+    assert_eq!(ty.params().len(), 1);
+    assert_eq!(ty.results().len(), 1);
+    let twice = twice.typed::<i32, i32>(&store).unwrap();
+    assert_eq!(twice.call(&mut store, 21).unwrap(), 42);
+
+    let seven = linker.get(&mut store, "env", "seven").unwrap();
+    let seven = seven.into_global().unwrap();
+    assert_eq!(seven.get(&mut store).i32(), Some(7));
+
+    assert!(linker.get(&mut store, "env", "missing").is_none());
+
+    let wat = r#"(module
+        (import "env" "twice" (func $twice (param i32) (result i32)))
+        (import "env" "seven" (global $seven i32))
+        (func (export "run") (result i32)
+            (call $twice (global.get $seven))
+        )
+    )"#;
+    let module = Module::new_safe(store.engine(), wat.as_bytes())
+        .await
+        .unwrap();
+    let instance = linker.instantiate_safe(&mut store, &module).await.unwrap();
+    let run = instance
+        .get_typed_func::<(), i32>(&mut store, "run")
+        .unwrap();
+    assert_eq!(run.call(&mut store, ()).unwrap(), 14);
+
+    Ok(())
+}
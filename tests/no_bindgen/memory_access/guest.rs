@@ -0,0 +1,5 @@
+#[no_mangle]
+pub fn sum_bytes(ptr: u32, len: u32) -> u32 {
+    let bytes = unsafe { std::slice::from_raw_parts(ptr as *const u8, len as usize) };
+    bytes.iter().map(|byte| *byte as u32).sum()
+}
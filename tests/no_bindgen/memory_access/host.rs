@@ -0,0 +1,142 @@
+use wasm_bridge::*;
+
+const PAGE_SIZE: usize = 0x10000;
+
+pub async fn run_test(bytes: &[u8]) -> Result<()> {
+    let mut store = Store::<()>::default();
+
+    grow_guest_memory(&mut store, bytes).await.unwrap();
+    host_memory(&mut store).await.unwrap();
+    out_of_bounds(&mut store).await.unwrap();
+
+    Ok(())
+}
+
+async fn grow_guest_memory(mut store: &mut Store<()>, bytes: &[u8]) -> Result<()> {
+    let module = Module::new_safe(store.engine(), bytes).await.unwrap();
+    let instance = Instance::new_safe(&mut store, &module, &[]).await.unwrap();
+
+    let memory = instance.get_memory(&mut store, "memory").unwrap();
+    let sum_bytes = instance
+        .get_typed_func::<(u32, u32), u32>(&mut store, "sum_bytes")
+        .unwrap();
+
+    let pages = memory.size(&store);
+    assert_eq!(memory.data_size(&store), pages as usize * PAGE_SIZE);
+    assert_eq!(memory.data(&store).len(), memory.data_size(&store));
+
+    let old_pages = memory.grow(&mut store, 2).unwrap();
+    assert_eq!(old_pages, pages);
+    assert_eq!(memory.size(&store), pages + 2);
+    assert_eq!(memory.data_size(&store), (pages as usize + 2) * PAGE_SIZE);
+
+    memory
+        .grow(&mut store, u64::MAX)
+        .expect_err("the new size overflows");
+    assert_eq!(memory.size(&store), pages + 2);
+
+    // Write into the newly grown pages
+    let offset = pages as usize * PAGE_SIZE + 10;
+    memory.data_mut(&mut store)[offset..offset + 4].copy_from_slice(&[1, 2, 3, 4]);
+    assert_eq!(&memory.data(&store)[offset..offset + 4], &[1, 2, 3, 4]);
+
+    let mut buffer = [0u8; 4];
+    memory.read(&mut store, offset, &mut buffer).unwrap();
+    assert_eq!(buffer, [1, 2, 3, 4]);
+
+    let sum = sum_bytes.call(&mut store, (offset as u32, 4)).unwrap();
+    assert_eq!(sum, 10);
+
+    Ok(())
+}
+
+async fn host_memory(mut store: &mut Store<()>) -> Result<()> {
+    let wat = r#"(module
+        (import "env" "memory" (memory 1 3))
+        (func (export "load") (param $addr i32) (result i32)
+            (i32.load8_u (local.get $addr))
+        )
+        (func (export "store") (param $addr i32) (param $value i32)
+            (i32.store8 (local.get $addr) (local.get $value))
+        )
+        (func (export "grow") (param $delta i32) (result i32)
+            (memory.grow (local.get $delta))
+        )
+    )"#;
+
+    let memory = Memory::new(&mut store, MemoryType::new(1, Some(3))).unwrap();
+    let ty = memory.ty(&store);
+    assert_eq!(ty.minimum(), 1);
+    assert_eq!(ty.maximum(), Some(3));
+    assert_eq!(memory.size(&store), 1);
+
+    let module = Module::new_safe(store.engine(), wat.as_bytes())
+        .await
+        .unwrap();
+    let instance = Instance::new_safe(&mut store, &module, &[memory.into()])
+        .await
+        .unwrap();
+
+    let load = instance
+        .get_typed_func::<u32, u32>(&mut store, "load")
+        .unwrap();
+    let store_fn = instance
+        .get_typed_func::<(u32, u32), ()>(&mut store, "store")
+        .unwrap();
+    let grow = instance
+        .get_typed_func::<u32, i32>(&mut store, "grow")
+        .unwrap();
+
+    memory.data_mut(&mut store)[100] = 42;
+    assert_eq!(load.call(&mut store, 100).unwrap(), 42);
+
+    store_fn.call(&mut store, (200, 7)).unwrap();
+    assert_eq!(memory.data(&store)[200], 7);
+
+    // Growing from the guest is visible to the host
+    assert_eq!(grow.call(&mut store, 1).unwrap(), 1);
+    assert_eq!(memory.size(&store), 2);
+
+    assert_eq!(memory.grow(&mut store, 1).unwrap(), 2);
+    assert_eq!(memory.size(&store), 3);
+
+    memory
+        .grow(&mut store, 1)
+        .expect_err("cannot grow past the maximum");
+    assert_eq!(memory.size(&store), 3);
+    assert_eq!(grow.call(&mut store, 1).unwrap(), -1);
+
+    Ok(())
+}
+
+async fn out_of_bounds(mut store: &mut Store<()>) -> Result<()> {
+    let memory = Memory::new(&mut store, MemoryType::new(1, None)).unwrap();
+    let mut buffer = [0u8; 8];
+
+    // Accesses right at the end are fine
+    memory.write(&mut store, PAGE_SIZE - 8, &[5; 8]).unwrap();
+    memory.read(&store, PAGE_SIZE - 8, &mut buffer).unwrap();
+    assert_eq!(buffer, [5; 8]);
+
+    let error = memory
+        .read(&store, PAGE_SIZE - 4, &mut buffer)
+        .expect_err("read should be out of bounds");
+    assert_eq!(error.to_string(), "out of bounds memory access");
+
+    memory
+        .write(&mut store, PAGE_SIZE, &[1])
+        .expect_err("write should be out of bounds");
+    memory
+        .write(&mut store, usize::MAX, &[1, 2])
+        .expect_err("write should not overflow");
+
+    // Errors convert into anyhow errors
+    let result: Result<()> = (|| Ok(memory.read(&store, PAGE_SIZE * 2, &mut buffer)?))();
+    assert!(result.is_err());
+
+    // Nothing was written by the failed write
+    memory.read(&store, PAGE_SIZE - 8, &mut buffer).unwrap();
+    assert_eq!(buffer, [5; 8]);
+
+    Ok(())
+}
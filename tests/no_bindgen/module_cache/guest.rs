@@ -0,0 +1,4 @@
+#[no_mangle]
+pub fn add(a: i32, b: i32) -> i32 {
+    a + b
+}
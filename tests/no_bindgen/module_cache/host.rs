@@ -0,0 +1,66 @@
+use wasm_bridge::*;
+
+pub async fn run_test(bytes: &[u8]) -> Result<()> {
+    let engine = cached_engine()?;
+
+    let module = Module::new_safe(&engine, bytes).await?;
+    assert_eq!(add(&engine, &module).await?, 5);
+
+    // The compiled module can be loaded again without compiling it
+    let module = reload(&engine, &module, bytes).await?;
+    assert_eq!(add(&engine, &module).await?, 5);
+
+    Ok(())
+}
+
+async fn add(engine: &Engine, module: &Module) -> Result<i32> {
+    let mut store = Store::new(engine, ());
+    let instance = Instance::new_safe(&mut store, module, &[]).await?;
+    let add = instance.get_typed_func::<(i32, i32), i32>(&mut store, "add")?;
+    add.call(&mut store, (2, 3))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn cached_engine() -> Result<Engine> {
+    Ok(Engine::default())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+async fn reload(engine: &Engine, module: &Module, _bytes: &[u8]) -> Result<Module> {
+    let serialized = module.serialize()?;
+
+    // Safety: the bytes were serialized by the same engine just now
+    unsafe { Module::deserialize(engine, serialized) }
+}
+
+#[cfg(target_arch = "wasm32")]
+thread_local! {
+    static CACHE: JsModuleCache = JsModuleCache::new();
+}
+
+#[cfg(target_arch = "wasm32")]
+fn cached_engine() -> Result<Engine> {
+    let mut config = Config::new();
+    config.module_cache(CACHE.with(Clone::clone));
+    Engine::new(&config)
+}
+
+#[cfg(target_arch = "wasm32")]
+async fn reload(engine: &Engine, _module: &Module, bytes: &[u8]) -> Result<Module> {
+    assert_eq!(CACHE.with(|cache| cache.map().size()), 1);
+
+    // Compiling the same bytes again takes the module from the cache
+    let module = Module::new_safe(engine, bytes).await?;
+    assert_eq!(CACHE.with(|cache| cache.map().size()), 1);
+
+    // The same bytes are instrumented differently with fuel, so they have another key
+    let mut config = Config::new();
+    config.module_cache(CACHE.with(Clone::clone));
+    config.consume_fuel(true);
+    let fuel_engine = Engine::new(&config)?;
+    Module::new_safe(&fuel_engine, bytes).await?;
+    Module::new_safe(&fuel_engine, bytes).await?;
+    assert_eq!(CACHE.with(|cache| cache.map().size()), 2);
+
+    Ok(module)
+}
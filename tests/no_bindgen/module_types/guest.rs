@@ -0,0 +1,4 @@
+#[no_mangle]
+pub fn add_five(value: i32) -> i32 {
+    value + 5
+}
@@ -0,0 +1,147 @@
+use wasm_bridge::*;
+
+pub async fn run_test(bytes: &[u8]) -> Result<()> {
+    let engine = Engine::default();
+
+    guest_exports(&engine, bytes).await.unwrap();
+    wat_imports_exports(&engine).await.unwrap();
+    #[cfg(target_arch = "wasm32")]
+    exception_tags(&engine).await.unwrap();
+
+    Ok(())
+}
+
+async fn guest_exports(engine: &Engine, bytes: &[u8]) -> Result<()> {
+    let module = Module::new_safe(engine, bytes).await.unwrap();
+
+    let add_five = module.get_export("add_five").unwrap();
+    let func = add_five.func().unwrap();
+    assert!(is_types(func.params(), &[ValType::is_i32]));
+    assert!(is_types(func.results(), &[ValType::is_i32]));
+
+    let memory = module.get_export("memory").unwrap();
+    assert!(memory.memory().is_some());
+
+    assert!(module.get_export("nonexistent").is_none());
+
+    Ok(())
+}
+
+async fn wat_imports_exports(engine: &Engine) -> Result<()> {
+    let wat = r#"(module
+        (import "env" "log" (func $log (param i32 f64)))
+        (import "env" "memory" (memory 1 4))
+        (import "other" "offset" (global $offset (mut i64)))
+        (func $double (param $p0 f32) (result f32 f32)
+            (local.get $p0)
+            (local.get $p0)
+        )
+        (table $table (export "table") 2 funcref)
+        (global $counter (export "counter") (mut i32) (i32.const 3))
+        (export "double" (func $double))
+        (export "mem" (memory 0))
+        (export "log_again" (func $log))
+    )"#;
+
+    let module = Module::new_safe(engine, wat.as_bytes()).await.unwrap();
+
+    let imports = module.imports().collect::<Vec<_>>();
+    assert_eq!(imports.len(), 3);
+
+    assert_eq!(imports[0].module(), "env");
+    assert_eq!(imports[0].name(), "log");
+    let log = imports[0].ty();
+    let log = log.func().unwrap();
+    assert!(is_types(log.params(), &[ValType::is_i32, ValType::is_f64]));
+    assert_eq!(log.results().len(), 0);
+
+    assert_eq!(imports[1].module(), "env");
+    assert_eq!(imports[1].name(), "memory");
+    let memory = imports[1].ty();
+    let memory = memory.memory().unwrap();
+    assert_eq!(memory.minimum(), 1);
+    assert_eq!(memory.maximum(), Some(4));
+
+    assert_eq!(imports[2].module(), "other");
+    assert_eq!(imports[2].name(), "offset");
+    let offset = imports[2].ty();
+    let offset = offset.global().unwrap();
+    assert!(offset.content().is_i64());
+    assert_eq!(offset.mutability(), Mutability::Var);
+
+    let exports = module
+        .exports()
+        .map(|export| export.name().to_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        exports,
+        vec!["table", "counter", "double", "mem", "log_again"]
+    );
+
+    let table = module.get_export("table").unwrap();
+    let table = table.table().unwrap();
+    assert_eq!(table.minimum(), 2);
+    assert_eq!(table.maximum(), None);
+
+    let counter = module.get_export("counter").unwrap();
+    let counter = counter.global().unwrap();
+    assert!(counter.content().is_i32());
+    assert_eq!(counter.mutability(), Mutability::Var);
+
+    let double = module.get_export("double").unwrap();
+    let double = double.func().unwrap();
+    assert!(is_types(double.params(), &[ValType::is_f32]));
+    assert!(is_types(
+        double.results(),
+        &[ValType::is_f32, ValType::is_f32]
+    ));
+
+    // Exported imports have the type of the import
+    let log_again = module.get_export("log_again").unwrap();
+    assert_eq!(log_again.func().unwrap().params().len(), 2);
+
+    let mem = module.get_export("mem").unwrap();
+    assert_eq!(mem.memory().unwrap().minimum(), 1);
+
+    Ok(())
+}
+
+// Wasmtime doesn't support exception handling yet
+#[cfg(target_arch = "wasm32")]
+async fn exception_tags(engine: &Engine) -> Result<()> {
+    let wat = r#"(module
+        (import "env" "error" (tag (param i32)))
+        (import "env" "log" (func $log (param i32)))
+        (tag $failed (export "failed") (param i64))
+        (func $answer (export "answer") (result i32)
+            (i32.const 42)
+        )
+        (memory (export "memory") 1)
+    )"#;
+
+    let module = Module::new_safe(engine, wat.as_bytes()).await.unwrap();
+
+    // Tags have no extern type, so they are not listed, and the defined memory is still not an import
+    let imports = module.imports().collect::<Vec<_>>();
+    assert_eq!(imports.len(), 1);
+    assert_eq!(imports[0].name(), "log");
+    assert!(is_types(
+        imports[0].ty().func().unwrap().params(),
+        &[ValType::is_i32]
+    ));
+
+    let exports = module
+        .exports()
+        .map(|export| export.name().to_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(exports, vec!["answer", "memory"]);
+
+    Ok(())
+}
+
+fn is_types(
+    types: impl ExactSizeIterator<Item = ValType>,
+    checks: &[fn(&ValType) -> bool],
+) -> bool {
+    types.len() == checks.len() && types.zip(checks).all(|(ty, check)| check(&ty))
+}
@@ -0,0 +1,160 @@
+use wasm_bridge::*;
+
+pub async fn run_test(bytes: &[u8]) -> Result<()> {
+    let mut store = Store::<()>::default();
+
+    untyped_numbers(&mut store, bytes).await.unwrap();
+    extern_ref_data(&mut store).unwrap();
+    extern_ref_table(&mut store).await.unwrap();
+    extern_ref_global(&mut store).unwrap();
+    v128_values().unwrap();
+
+    Ok(())
+}
+
+async fn untyped_numbers(mut store: &mut Store<()>, bytes: &[u8]) -> Result<()> {
+    let module = Module::new_safe(store.engine(), bytes).await.unwrap();
+    let instance = Instance::new_safe(&mut store, &module, &[]).await.unwrap();
+
+    let add_five = instance.get_func(&mut store, "add_five").unwrap();
+    let mut rets = [Val::I32(0)];
+    add_five
+        .call(&mut store, &[Val::I32(5)], &mut rets)
+        .unwrap();
+    assert_eq!(rets[0].i32(), Some(10));
+
+    Ok(())
+}
+
+fn extern_ref_data(mut store: &mut Store<()>) -> Result<()> {
+    let externref = ExternRef::new(&mut store, String::from("hello")).unwrap();
+    assert_eq!(
+        externref
+            .data(&store)
+            .unwrap()
+            .downcast_ref::<String>()
+            .unwrap(),
+        "hello"
+    );
+
+    externref
+        .data_mut(&mut store)
+        .unwrap()
+        .downcast_mut::<String>()
+        .unwrap()
+        .push_str(" world");
+    assert_eq!(
+        externref
+            .data(&store)
+            .unwrap()
+            .downcast_ref::<String>()
+            .unwrap(),
+        "hello world"
+    );
+
+    let val = Val::from(externref);
+    assert!(val.externref().unwrap().is_some());
+    assert!(val.funcref().is_none());
+    assert!(Val::null_extern_ref().unwrap_externref().is_none());
+    assert!(Val::null_func_ref().unwrap_funcref().is_none());
+
+    Ok(())
+}
+
+async fn extern_ref_table(mut store: &mut Store<()>) -> Result<()> {
+    let wat = r#"(module
+        (table $handles (export "handles") 4 externref)
+        (func (export "store_handle") (param i32 externref)
+            (table.set $handles (local.get 0) (local.get 1))
+        )
+        (func (export "load_handle") (param i32) (result externref)
+            (table.get $handles (local.get 0))
+        )
+        (func (export "is_null") (param externref) (result i32)
+            (ref.is_null (local.get 0))
+        )
+    )"#;
+
+    let module = Module::new_safe(store.engine(), wat.as_bytes())
+        .await
+        .unwrap();
+    let instance = Instance::new_safe(&mut store, &module, &[]).await.unwrap();
+
+    let first = ExternRef::new(&mut store, 10u32).unwrap();
+    let second = ExternRef::new(&mut store, 20u32).unwrap();
+
+    // The guest keeps the handles in its table
+    let store_handle = instance.get_func(&mut store, "store_handle").unwrap();
+    store_handle
+        .call(&mut store, &[Val::I32(0), first.into()], &mut [])
+        .unwrap();
+
+    let handles = instance.get_table(&mut store, "handles").unwrap();
+    assert!(matches!(
+        handles.ty(&store).element().heap_type(),
+        HeapType::Extern
+    ));
+    handles.set(&mut store, 1, second.into()).unwrap();
+
+    let load_handle = instance.get_func(&mut store, "load_handle").unwrap();
+    for (index, expected) in [(0, 10u32), (1, 20u32)] {
+        let mut rets = [Val::null_extern_ref()];
+        load_handle
+            .call(&mut store, &[Val::I32(index)], &mut rets)
+            .unwrap();
+
+        let handle = rets[0].unwrap_externref().unwrap();
+        let data = handle.data(&store).unwrap();
+        assert_eq!(data.downcast_ref::<u32>(), Some(&expected));
+    }
+
+    let from_table = handles.get(&mut store, 1).unwrap();
+    let from_table = from_table.unwrap_extern().unwrap();
+    assert_eq!(
+        from_table.data(&store).unwrap().downcast_ref::<u32>(),
+        Some(&20)
+    );
+
+    assert!(handles.get(&mut store, 2).unwrap().is_null());
+
+    let is_null = instance.get_func(&mut store, "is_null").unwrap();
+    let mut rets = [Val::I32(0)];
+    is_null
+        .call(&mut store, &[Val::null_extern_ref()], &mut rets)
+        .unwrap();
+    assert_eq!(rets[0].i32(), Some(1));
+
+    // A function reference does not fit into a table of extern refs
+    let func = Func::wrap(&mut store, |_caller: Caller<()>| {});
+    assert!(handles.set(&mut store, 2, func.into()).is_err());
+
+    Ok(())
+}
+
+fn extern_ref_global(mut store: &mut Store<()>) -> Result<()> {
+    let externref = ExternRef::new(&mut store, 5i64).unwrap();
+
+    let global = Global::new(
+        &mut store,
+        GlobalType::new(ValType::EXTERNREF, Mutability::Var),
+        externref.into(),
+    )
+    .unwrap();
+
+    let value = global.get(&mut store);
+    let value = value.unwrap_externref().unwrap();
+    assert_eq!(value.data(&store).unwrap().downcast_ref::<i64>(), Some(&5));
+
+    global.set(&mut store, Val::null_extern_ref()).unwrap();
+    assert!(global.get(&mut store).unwrap_externref().is_none());
+
+    Ok(())
+}
+
+fn v128_values() -> Result<()> {
+    let val = Val::from(V128::from(0x1234u128));
+    assert_eq!(val.v128().unwrap().as_u128(), 0x1234);
+    assert!(val.i32().is_none());
+
+    Ok(())
+}
@@ -0,0 +1,11 @@
+static GREETING: &str = "Hello from a streamed module";
+
+#[no_mangle]
+pub fn add(a: i32, b: i32) -> i32 {
+    a + b
+}
+
+#[no_mangle]
+pub fn greeting_byte(index: u32) -> u32 {
+    GREETING.as_bytes()[index as usize] as u32
+}
@@ -0,0 +1,70 @@
+use wasm_bridge::*;
+
+pub async fn run_test(bytes: &[u8]) -> Result<()> {
+    for consume_fuel in [false, true] {
+        let mut config = Config::new();
+        config.consume_fuel(consume_fuel);
+        let engine = Engine::new(&config)?;
+
+        let module = load_module(&engine, bytes).await?;
+        check_module(&engine, &module, consume_fuel).await?;
+    }
+
+    Ok(())
+}
+
+async fn check_module(engine: &Engine, module: &Module, consume_fuel: bool) -> Result<()> {
+    let mut store = Store::new(engine, ());
+    if consume_fuel {
+        store.set_fuel(10_000)?;
+    }
+
+    let instance = Instance::new_safe(&mut store, module, &[]).await?;
+
+    let add = instance.get_typed_func::<(i32, i32), i32>(&mut store, "add")?;
+    assert_eq!(add.call(&mut store, (2, 3))?, 5);
+
+    // The data segments are passed through as well
+    let greeting_byte = instance.get_typed_func::<u32, u32>(&mut store, "greeting_byte")?;
+    let greeting = (0..28)
+        .map(|index| greeting_byte.call(&mut store, index).map(|byte| byte as u8))
+        .collect::<Result<Vec<_>>>()?;
+    assert_eq!(greeting, b"Hello from a streamed module");
+
+    Ok(())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+async fn load_module(engine: &Engine, bytes: &[u8]) -> Result<Module> {
+    // A file stands in for the server
+    let path = std::env::temp_dir().join("wasm_bridge_streaming_test.wasm");
+    std::fs::write(&path, bytes)?;
+
+    Module::from_reader(engine, std::fs::File::open(&path)?)
+}
+
+#[cfg(target_arch = "wasm32")]
+async fn load_module(engine: &Engine, bytes: &[u8]) -> Result<Module> {
+    use wasm_bridge::{
+        js_sys::{Function, Uint8Array},
+        wasm_bindgen::JsValue,
+        web_sys::{ReadableStream, Response},
+    };
+
+    // A response with small chunks stands in for the server, the sections span several chunks
+    let chunked = Function::new_with_args(
+        "bytes",
+        "return new ReadableStream({ start(controller) {
+            for (let i = 0; i < bytes.length; i += 7) controller.enqueue(bytes.slice(i, i + 7));
+            controller.close();
+        } })",
+    );
+    let stream: ReadableStream = chunked
+        .call1(&JsValue::UNDEFINED, &Uint8Array::from(bytes))
+        .map_err(|error| Error::msg(format!("{error:?}")))?
+        .into();
+    let response = Response::new_with_opt_readable_stream(Some(&stream))
+        .map_err(|error| Error::msg(format!("{error:?}")))?;
+
+    Module::from_response(engine, &response).await
+}
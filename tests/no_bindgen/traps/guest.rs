@@ -0,0 +1,14 @@
+#[no_mangle]
+pub fn panic_now() {
+    panic!("guest panicked");
+}
+
+#[no_mangle]
+pub fn call_failing_import() {
+    unsafe { failing_import() }
+}
+
+#[link(wasm_import_module = "host")]
+extern "C" {
+    fn failing_import();
+}
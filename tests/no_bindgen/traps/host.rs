@@ -0,0 +1,150 @@
+use std::fmt::{self, Display, Formatter};
+
+use wasm_bridge::*;
+
+pub async fn run_test(bytes: &[u8]) -> Result<()> {
+    let mut store = Store::<()>::default();
+
+    guest_traps(&mut store, bytes).await.unwrap();
+    wat_traps(&mut store).await.unwrap();
+
+    Ok(())
+}
+
+#[derive(Debug)]
+struct HostError(u32);
+
+impl Display for HostError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "host error {}", self.0)
+    }
+}
+
+impl std::error::Error for HostError {}
+
+async fn guest_traps(mut store: &mut Store<()>, bytes: &[u8]) -> Result<()> {
+    let module = Module::new_safe(store.engine(), bytes).await.unwrap();
+
+    let mut linker = Linker::new(store.engine());
+    linker
+        .func_new(
+            "host",
+            "failing_import",
+            FuncType::new(store.engine(), [], []),
+            |_, _, _| Err(HostError(7).into()),
+        )
+        .unwrap();
+
+    let instance = linker.instantiate_safe(&mut store, &module).await.unwrap();
+
+    let panic_now = instance
+        .get_typed_func::<(), ()>(&mut store, "panic_now")
+        .unwrap();
+    let error = panic_now
+        .call(&mut store, ())
+        .expect_err("guest should panic");
+    assert_eq!(
+        error.downcast_ref::<Trap>(),
+        Some(&Trap::UnreachableCodeReached)
+    );
+
+    // Errors from host functions are returned as they are
+    let call_failing_import = instance
+        .get_typed_func::<(), ()>(&mut store, "call_failing_import")
+        .unwrap();
+    let error = call_failing_import
+        .call(&mut store, ())
+        .expect_err("host function should fail");
+    assert_eq!(error.downcast_ref::<HostError>().unwrap().0, 7);
+    assert!(error.downcast_ref::<Trap>().is_none());
+
+    Ok(())
+}
+
+async fn wat_traps(mut store: &mut Store<()>) -> Result<()> {
+    let wat = r#"(module
+        (memory 1)
+        (table 1 funcref)
+        (type $void (func))
+        (func (export "unreachable")
+            unreachable
+        )
+        (func (export "divide") (param $a i32) (param $b i32) (result i32)
+            (i32.div_s (local.get $a) (local.get $b))
+        )
+        (func (export "truncate") (param $value f32) (result i32)
+            (i32.trunc_f32_s (local.get $value))
+        )
+        (func (export "load") (param $addr i32) (result i32)
+            (i32.load (local.get $addr))
+        )
+        (func (export "call_indirect") (param $index i32)
+            (call_indirect (type $void) (local.get $index))
+        )
+        (func $recurse (export "recurse") (param $depth i32) (result i32)
+            (i32.add (call $recurse (i32.add (local.get $depth) (i32.const 1))) (i32.const 1))
+        )
+    )"#;
+
+    let module = Module::new_safe(store.engine(), wat.as_bytes())
+        .await
+        .unwrap();
+    let instance = Instance::new_safe(&mut store, &module, &[]).await.unwrap();
+
+    let unreachable = instance.get_func(&mut store, "unreachable").unwrap();
+    let error = unreachable
+        .call(&mut store, &[], &mut [])
+        .expect_err("should trap");
+    assert_eq!(trap(&error), Trap::UnreachableCodeReached);
+
+    let divide = instance
+        .get_typed_func::<(i32, i32), i32>(&mut store, "divide")
+        .unwrap();
+    assert_eq!(divide.call(&mut store, (10, 2)).unwrap(), 5);
+    let error = divide.call(&mut store, (10, 0)).expect_err("should trap");
+    assert_eq!(trap(&error), Trap::IntegerDivisionByZero);
+    let error = divide
+        .call(&mut store, (i32::MIN, -1))
+        .expect_err("should trap");
+    assert_eq!(trap(&error), Trap::IntegerOverflow);
+
+    let truncate = instance
+        .get_typed_func::<f32, i32>(&mut store, "truncate")
+        .unwrap();
+    let error = truncate
+        .call(&mut store, f32::NAN)
+        .expect_err("should trap");
+    assert_eq!(trap(&error), Trap::BadConversionToInteger);
+
+    let load = instance
+        .get_typed_func::<i32, i32>(&mut store, "load")
+        .unwrap();
+    assert_eq!(load.call(&mut store, 0).unwrap(), 0);
+    let error = load.call(&mut store, 0x10000).expect_err("should trap");
+    assert_eq!(trap(&error), Trap::MemoryOutOfBounds);
+
+    let call_indirect = instance
+        .get_typed_func::<i32, ()>(&mut store, "call_indirect")
+        .unwrap();
+    let error = call_indirect.call(&mut store, 5).expect_err("should trap");
+    assert_eq!(trap(&error), Trap::TableOutOfBounds);
+
+    let recurse = instance
+        .get_typed_func::<i32, i32>(&mut store, "recurse")
+        .unwrap();
+    let error = recurse
+        .call(&mut store, 0)
+        .expect_err("should overflow the stack");
+    assert_eq!(trap(&error), Trap::StackOverflow);
+
+    // The instance is still usable after a trap
+    assert_eq!(divide.call(&mut store, (9, 3)).unwrap(), 3);
+
+    Ok(())
+}
+
+fn trap(error: &Error) -> Trap {
+    *error
+        .downcast_ref::<Trap>()
+        .expect("error should be a trap")
+}
@@ -0,0 +1,15 @@
+#[no_mangle]
+pub fn call_missing() -> i32 {
+    unsafe { missing(5) }
+}
+
+#[no_mangle]
+pub fn call_provided() -> i32 {
+    unsafe { provided() }
+}
+
+#[link(wasm_import_module = "env")]
+extern "C" {
+    fn missing(value: i32) -> i32;
+    fn provided() -> i32;
+}
@@ -0,0 +1,87 @@
+use wasm_bridge::*;
+
+pub async fn run_test(bytes: &[u8]) -> Result<()> {
+    missing_import(bytes).await.unwrap();
+    imports_as_traps(bytes).await.unwrap();
+    imports_as_default_values(bytes).await.unwrap();
+
+    Ok(())
+}
+
+fn new_linker(store: &Store<()>) -> Linker<()> {
+    let mut linker = Linker::new(store.engine());
+    linker
+        .func_wrap("env", "provided", |_: Caller<()>| 7i32)
+        .unwrap();
+    linker
+}
+
+async fn missing_import(bytes: &[u8]) -> Result<()> {
+    let mut store = Store::<()>::default();
+    let module = Module::new_safe(store.engine(), bytes).await.unwrap();
+    let linker = new_linker(&store);
+
+    let error = linker
+        .instantiate_safe(&mut store, &module)
+        .await
+        .err()
+        .expect("instantiation should fail");
+
+    let unknown = error
+        .downcast_ref::<UnknownImportError>()
+        .expect("error should be an unknown import error");
+    assert_eq!(unknown.module(), "env");
+    assert_eq!(unknown.name(), "missing");
+    assert!(unknown.ty().func().is_some());
+    assert!(error.to_string().contains("`env::missing`"));
+
+    Ok(())
+}
+
+async fn imports_as_traps(bytes: &[u8]) -> Result<()> {
+    let mut store = Store::<()>::default();
+    let module = Module::new_safe(store.engine(), bytes).await.unwrap();
+    let mut linker = new_linker(&store);
+
+    linker.define_unknown_imports_as_traps(&module).unwrap();
+    let instance = linker.instantiate_safe(&mut store, &module).await.unwrap();
+
+    let call_provided = instance
+        .get_typed_func::<(), i32>(&mut store, "call_provided")
+        .unwrap();
+    assert_eq!(call_provided.call(&mut store, ()).unwrap(), 7);
+
+    let call_missing = instance
+        .get_typed_func::<(), i32>(&mut store, "call_missing")
+        .unwrap();
+    let error = call_missing.call(&mut store, ()).unwrap_err();
+    let unknown = error
+        .downcast_ref::<UnknownImportError>()
+        .expect("error should be an unknown import error");
+    assert_eq!(unknown.name(), "missing");
+
+    Ok(())
+}
+
+async fn imports_as_default_values(bytes: &[u8]) -> Result<()> {
+    let mut store = Store::<()>::default();
+    let module = Module::new_safe(store.engine(), bytes).await.unwrap();
+    let mut linker = new_linker(&store);
+
+    linker
+        .define_unknown_imports_as_default_values(&module)
+        .unwrap();
+    let instance = linker.instantiate_safe(&mut store, &module).await.unwrap();
+
+    let call_provided = instance
+        .get_typed_func::<(), i32>(&mut store, "call_provided")
+        .unwrap();
+    assert_eq!(call_provided.call(&mut store, ()).unwrap(), 7);
+
+    let call_missing = instance
+        .get_typed_func::<(), i32>(&mut store, "call_missing")
+        .unwrap();
+    assert_eq!(call_missing.call(&mut store, ()).unwrap(), 0);
+
+    Ok(())
+}
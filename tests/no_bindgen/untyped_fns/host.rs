@@ -3,6 +3,7 @@ use wasm_bridge::*;
 pub async fn run_test(bytes: &[u8]) -> Result<()> {
     single_value(bytes).unwrap();
     multiple_values().unwrap();
+    multiple_results().unwrap();
 
     Ok(())
 }
@@ -18,12 +19,10 @@ fn single_value(bytes: &[u8]) -> Result<()> {
         "add_one_i32",
         FuncType::new(store.engine(), [ValType::I32], [ValType::I32]),
         |_: Caller<()>, args: &[Val], rets: &mut [Val]| {
-            // TODO: cannot use this match, it will be Val::F64
-            // Ok(match args[0] {
-            //     Val::I32(val) => rets[0] = Val::I32(*val + 1),
-            //     _ => unreachable!(),
-            // })
-            rets[0] = Val::I32(args[0].i32().unwrap() + 1);
+            match args[0] {
+                Val::I32(val) => rets[0] = Val::I32(val + 1),
+                _ => unreachable!(),
+            }
             Ok(())
         },
     ).unwrap();
@@ -41,12 +40,10 @@ fn single_value(bytes: &[u8]) -> Result<()> {
         "add_one_f32",
         FuncType::new(store.engine(), [ValType::F32], [ValType::F32]),
         |_: Caller<()>, args: &[Val], rets: &mut [Val]| {
-            // TODO: cannot use this match, it will be Val::F64
-            // Ok(match args[0] {
-            //     Val::F32(val) => rets[0] = Val::F32(f32::from_bits(*val) + 1),
-            //     _ => unreachable!(),
-            // })
-            rets[0] = (args[0].f32().unwrap() + 1.0).into();
+            match args[0] {
+                Val::F32(val) => rets[0] = (f32::from_bits(val) + 1.0).into(),
+                _ => unreachable!(),
+            }
             Ok(())
         },
     ).unwrap();
@@ -80,6 +77,21 @@ fn single_value(bytes: &[u8]) -> Result<()> {
     add_three_f64.call(&mut store, &[(5.5f64).into()], &mut results).unwrap();
     assert_eq!(results[0].f64().unwrap(), 8.5);
 
+    // Results have the exact type of the function, not just a number
+    add_three_i32.call(&mut store, &[Val::I32(-5)], &mut results).unwrap();
+    assert!(matches!(results[0], Val::I32(-2)));
+    assert!(results[0].f64().is_none());
+
+    add_three_f32.call(&mut store, &[(1.0f32).into()], &mut results).unwrap();
+    assert!(matches!(results[0], Val::F32(bits) if f32::from_bits(bits) == 4.0));
+
+    add_three_i32
+        .call(&mut store, &[Val::I64(5)], &mut results)
+        .expect_err("argument of a wrong type");
+    add_three_i32
+        .call(&mut store, &[], &mut results)
+        .expect_err("missing argument");
+
     Ok(())
 }
 
@@ -164,3 +176,59 @@ fn multiple_values() -> Result<()> {
 
     Ok(())
 }
+
+fn multiple_results() -> Result<()> {
+    let wat = r#"(module
+        (import "imported_fns" "split" (func $split (param i64) (result i32 i32)))
+        (import "imported_fns" "wrong_type" (func $wrong_type (result i32)))
+        (func $split_export (export "split") (param $p0 i64) (result i32 i32)
+            (call $split (local.get $p0))
+        )
+        (func $wrong_type_export (export "wrong_type") (result i32)
+            (call $wrong_type)
+        )
+    )"#;
+
+    let mut store = Store::<()>::default();
+    #[allow(deprecated)]
+    let module = Module::new(store.engine(), wat.as_bytes()).unwrap();
+
+    let mut linker = Linker::new(store.engine());
+    linker.func_new(
+        "imported_fns",
+        "split",
+        FuncType::new(store.engine(), [ValType::I64], [ValType::I32, ValType::I32]),
+        |_: Caller<()>, args: &[Val], rets: &mut [Val]| {
+            assert_eq!(rets.len(), 2);
+            let value = args[0].i64().unwrap();
+            rets[0] = Val::I32((value >> 32) as i32);
+            rets[1] = Val::I32(value as i32);
+            Ok(())
+        },
+    ).unwrap();
+    linker.func_new(
+        "imported_fns",
+        "wrong_type",
+        FuncType::new(store.engine(), [], [ValType::I32]),
+        |_: Caller<()>, _args: &[Val], rets: &mut [Val]| {
+            rets[0] = Val::I64(5);
+            Ok(())
+        },
+    ).unwrap();
+
+    #[allow(deprecated)]
+    let instance = linker.instantiate(&mut store, &module).unwrap();
+
+    let split = instance.get_func(&mut store, "split").unwrap();
+    let mut results = [Val::I32(0), Val::I32(0)];
+    split.call(&mut store, &[Val::I64((7 << 32) + 11)], &mut results).unwrap();
+    assert_eq!(results[0].i32().unwrap(), 7);
+    assert_eq!(results[1].i32().unwrap(), 11);
+
+    let wrong_type = instance.get_func(&mut store, "wrong_type").unwrap();
+    wrong_type
+        .call(&mut store, &[], &mut [Val::I32(0)])
+        .expect_err("imported function returned a value of wrong type");
+
+    Ok(())
+}
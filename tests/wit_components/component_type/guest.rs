@@ -0,0 +1,40 @@
+use std::cell::Cell;
+
+wit_bindgen::generate!({
+    path: "../protocol.wit",
+    world: "component-type",
+});
+
+use component_test::wit_protocol::host_math::{divide, Rounding};
+
+struct GuestImpl;
+
+impl Guest for GuestImpl {
+    fn name() -> String {
+        log("name called");
+        format!("plugin {}", divide(7, 2, Rounding::Up))
+    }
+}
+
+impl exports::component_test::wit_protocol::counters::Guest for GuestImpl {
+    type Counter = MyCounter;
+}
+
+pub struct MyCounter {
+    value: Cell<u32>,
+}
+
+impl exports::component_test::wit_protocol::counters::GuestCounter for MyCounter {
+    fn new(start: u32) -> Self {
+        Self {
+            value: Cell::new(start),
+        }
+    }
+
+    fn increment(&self) -> u32 {
+        self.value.set(self.value.get() + 1);
+        self.value.get()
+    }
+}
+
+export!(GuestImpl);
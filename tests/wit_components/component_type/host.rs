@@ -0,0 +1,91 @@
+use wasm_bridge::{
+    component::{
+        types::{ComponentInstance, ComponentItem},
+        Component, Type,
+    },
+    Config, Engine, Result,
+};
+
+pub fn run_test(component_bytes: &[u8]) -> Result<()> {
+    let mut config = Config::new();
+    config.wasm_component_model(true);
+
+    let engine = Engine::new(&config).unwrap();
+
+    #[allow(deprecated)]
+    let component = Component::new(&engine, &component_bytes).unwrap();
+
+    let ty = component.component_type();
+
+    // Imports
+    let names = ty
+        .imports(&engine)
+        .map(|(name, _)| name)
+        .collect::<Vec<_>>();
+    assert!(names.contains(&"component-test:wit-protocol/host-math"));
+    assert!(names.contains(&"log"));
+
+    let host_math = instance(ty.get_import(&engine, "component-test:wit-protocol/host-math"));
+    let divide = match host_math.get_export(&engine, "divide") {
+        Some(ComponentItem::ComponentFunc(func)) => func,
+        other => panic!("expected a function, got {other:?}"),
+    };
+    let params = divide.params().collect::<Vec<_>>();
+    assert_eq!(params.len(), 3);
+    assert_eq!(params[0], Type::U32);
+    let rounding = params[2].unwrap_enum().names().collect::<Vec<_>>();
+    assert_eq!(rounding, ["down", "up"]);
+    assert_eq!(divide.results().collect::<Vec<_>>(), [Type::U32]);
+
+    match ty.get_import(&engine, "log") {
+        Some(ComponentItem::ComponentFunc(log)) => {
+            assert_eq!(log.params().collect::<Vec<_>>(), [Type::String]);
+            assert_eq!(log.results().len(), 0);
+        }
+        other => panic!("expected a function, got {other:?}"),
+    }
+
+    // Exports
+    match ty.get_export(&engine, "name") {
+        Some(ComponentItem::ComponentFunc(name)) => {
+            assert_eq!(name.params().len(), 0);
+            assert_eq!(name.results().collect::<Vec<_>>(), [Type::String]);
+        }
+        other => panic!("expected a function, got {other:?}"),
+    }
+
+    let counters = instance(ty.get_export(&engine, "component-test:wit-protocol/counters"));
+    let counter = match counters.get_export(&engine, "counter") {
+        Some(ComponentItem::Resource(resource)) => resource,
+        other => panic!("expected a resource, got {other:?}"),
+    };
+    let constructor = match counters.get_export(&engine, "[constructor]counter") {
+        Some(ComponentItem::ComponentFunc(func)) => func,
+        other => panic!("expected a function, got {other:?}"),
+    };
+    assert_eq!(
+        constructor.results().collect::<Vec<_>>(),
+        [Type::Own(counter)]
+    );
+
+    let increment = match counters.get_export(&engine, "[method]counter.increment") {
+        Some(ComponentItem::ComponentFunc(func)) => func,
+        other => panic!("expected a function, got {other:?}"),
+    };
+    assert_eq!(
+        increment.params().collect::<Vec<_>>(),
+        [Type::Borrow(counter)]
+    );
+
+    assert!(ty.get_export(&engine, "missing").is_none());
+    assert!(ty.get_import(&engine, "name").is_none());
+
+    Ok(())
+}
+
+fn instance(item: Option<ComponentItem>) -> ComponentInstance {
+    match item {
+        Some(ComponentItem::ComponentInstance(instance)) => instance,
+        other => panic!("expected an instance, got {other:?}"),
+    }
+}
@@ -0,0 +1,44 @@
+wit_bindgen::generate!({
+    path: "../protocol.wit",
+    world: "dynamic-values",
+});
+
+use exports::component_test::wit_protocol::shapes::{Point, Shape, Style};
+
+struct GuestImpl;
+
+impl Guest for GuestImpl {
+    fn checked_div(a: i32, b: i32) -> Result<i32, String> {
+        a.checked_div(b).ok_or_else(|| "division by zero".into())
+    }
+
+    fn first(values: Vec<u64>) -> Option<u64> {
+        values.first().copied()
+    }
+}
+
+impl exports::component_test::wit_protocol::shapes::Guest for GuestImpl {
+    fn area(s: Shape) -> f32 {
+        match s {
+            Shape::Empty => 0.0,
+            Shape::Circle(radius) => 3.0 * radius * radius,
+            Shape::Rectangle(size) => size.x * size.y,
+        }
+    }
+
+    fn toggle_bold(s: Style) -> Style {
+        s ^ Style::BOLD
+    }
+
+    fn translate(points: Vec<Point>, delta: Point) -> Vec<Point> {
+        points
+            .into_iter()
+            .map(|point| Point {
+                x: point.x + delta.x,
+                y: point.y + delta.y,
+            })
+            .collect()
+    }
+}
+
+export!(GuestImpl);
@@ -0,0 +1,131 @@
+use wasm_bridge::{
+    component::{Component, Linker, Type, Val},
+    Config, Engine, Result, Store,
+};
+
+pub fn run_test(component_bytes: &[u8]) -> Result<()> {
+    let mut config = Config::new();
+    config.wasm_component_model(true);
+
+    let engine = Engine::new(&config).unwrap();
+    let mut store = Store::new(&engine, ());
+
+    #[allow(deprecated)]
+    let component = Component::new(&store.engine(), &component_bytes).unwrap();
+
+    let linker = Linker::new(store.engine());
+
+    #[allow(deprecated)]
+    let instance = linker.instantiate(&mut store, &component).unwrap();
+
+    // Root exports
+    let checked_div = instance.get_func(&mut store, "checked-div").unwrap();
+    assert_eq!(&*checked_div.params(&store), &[Type::S32, Type::S32]);
+    let result_type = checked_div.results(&store)[0].unwrap_result().clone();
+    assert_eq!(result_type.ok(), Some(Type::S32));
+    assert_eq!(result_type.err(), Some(Type::String));
+
+    let mut results = [Val::Bool(false)];
+    checked_div.call(&mut store, &[Val::S32(7), Val::S32(2)], &mut results)?;
+    checked_div.post_return(&mut store)?;
+    assert_eq!(results[0], Val::Result(Ok(Some(Box::new(Val::S32(3))))));
+
+    checked_div.call(&mut store, &[Val::S32(7), Val::S32(0)], &mut results)?;
+    checked_div.post_return(&mut store)?;
+    let error = Val::String("division by zero".into());
+    assert_eq!(results[0], Val::Result(Err(Some(Box::new(error)))));
+
+    let result = checked_div.call(&mut store, &[Val::S32(7)], &mut results);
+    assert!(result.is_err(), "argument count should be checked");
+
+    let first = instance.get_func(&mut store, "first").unwrap();
+    let values = Val::List(vec![Val::U64(u64::MAX), Val::U64(5)]);
+    first.call(&mut store, &[values], &mut results)?;
+    first.post_return(&mut store)?;
+    assert_eq!(results[0], Val::Option(Some(Box::new(Val::U64(u64::MAX)))));
+
+    first.call(&mut store, &[Val::List(vec![])], &mut results)?;
+    first.post_return(&mut store)?;
+    assert_eq!(results[0], Val::Option(None));
+
+    assert!(instance.get_func(&mut store, "missing").is_none());
+
+    // Interface exports
+    let shapes = "component-test:wit-protocol/shapes";
+    let get_func = |store: &mut Store<()>, name: &str| {
+        instance
+            .exports(store)
+            .root()
+            .instance(shapes)
+            .unwrap()
+            .func(name)
+            .unwrap()
+    };
+
+    let area = get_func(&mut store, "area");
+    let shape_type = area.params(&store)[0].unwrap_variant().clone();
+    let names = shape_type.cases().map(|case| case.name).collect::<Vec<_>>();
+    assert_eq!(names, ["empty", "circle", "rectangle"]);
+
+    let shapes_and_areas = [
+        (Val::Variant("empty".into(), None), 0.0),
+        (
+            Val::Variant("circle".into(), Some(Box::new(Val::Float32(2.0)))),
+            12.0,
+        ),
+        (
+            Val::Variant("rectangle".into(), Some(Box::new(point(3.0, 4.0)))),
+            12.0,
+        ),
+    ];
+    for (shape, expected) in shapes_and_areas {
+        area.call(&mut store, &[shape], &mut results)?;
+        area.post_return(&mut store)?;
+        assert_eq!(results[0], Val::Float32(expected));
+    }
+
+    let toggle_bold = get_func(&mut store, "toggle-bold");
+    let style = Val::Flags(vec!["italic".into()]);
+    toggle_bold.call(&mut store, &[style], &mut results)?;
+    toggle_bold.post_return(&mut store)?;
+    assert_eq!(results[0], Val::Flags(vec!["bold".into(), "italic".into()]));
+
+    let translate = get_func(&mut store, "translate");
+    let points = Val::List(vec![point(1.0, 2.0), point(-3.0, 0.5)]);
+    translate.call(&mut store, &[points, point(1.0, 1.0)], &mut results)?;
+    translate.post_return(&mut store)?;
+    assert_eq!(
+        results[0],
+        Val::List(vec![point(2.0, 3.0), point(-2.0, 1.5)])
+    );
+
+    // A value that does not match the type is an error. Wasmtime does not allow
+    // entering the instance again after that, so these checks are done last.
+    let result = area.call(
+        &mut store,
+        &[Val::Variant("square".into(), None)],
+        &mut results,
+    );
+    assert!(result.is_err(), "unknown case should be rejected");
+
+    // On the web, the instance can be entered again if the arguments could not be lowered
+    #[cfg(target_arch = "wasm32")]
+    {
+        let empty = Val::Variant("empty".into(), None);
+        area.call(&mut store, &[empty], &mut results)?;
+        area.post_return(&mut store)?;
+        assert_eq!(results[0], Val::Float32(0.0));
+    }
+
+    let result = checked_div.call(&mut store, &[Val::U32(7), Val::S32(2)], &mut results);
+    assert!(result.is_err(), "argument type should be checked");
+
+    Ok(())
+}
+
+fn point(x: f32, y: f32) -> Val {
+    Val::Record(vec![
+        ("x".into(), Val::Float32(x)),
+        ("y".into(), Val::Float32(y)),
+    ])
+}
@@ -0,0 +1,20 @@
+wit_bindgen::generate!({
+    path: "../protocol.wit",
+    world: "export-lookup",
+});
+
+struct GuestImpl;
+
+impl Guest for GuestImpl {
+    fn double(n: u32) -> u32 {
+        n * 2
+    }
+}
+
+impl exports::component_test::wit_protocol::calculator::Guest for GuestImpl {
+    fn add(a: u32, b: u32) -> u32 {
+        a + b
+    }
+}
+
+export!(GuestImpl);
@@ -0,0 +1,143 @@
+use wasm_bridge::{
+    component::{Component, Instance, Linker},
+    Config, Engine, Result, Store,
+};
+
+const CALCULATOR: &str = "component-test:wit-protocol/calculator";
+
+pub fn run_test(component_bytes: &[u8]) -> Result<()> {
+    let mut config = Config::new();
+    config.wasm_component_model(true);
+
+    let engine = Engine::new(&config).unwrap();
+    let mut store = Store::new(&engine, ());
+
+    #[allow(deprecated)]
+    let component = Component::new(&store.engine(), &component_bytes).unwrap();
+
+    let linker = Linker::new(store.engine());
+
+    #[allow(deprecated)]
+    let instance = linker.instantiate(&mut store, &component).unwrap();
+
+    // Missing exports and exports of a different kind
+    let root_instance =
+        |store: &mut Store<()>, name: &str| instance.exports(store).root().instance(name).is_some();
+    assert!(root_instance(&mut store, CALCULATOR));
+    assert!(!root_instance(&mut store, "missing"));
+    assert!(!root_instance(&mut store, "double"));
+
+    assert!(instance.get_func(&mut store, "missing").is_none());
+    assert!(instance.get_func(&mut store, CALCULATOR).is_none());
+
+    let nested = instance
+        .exports(&mut store)
+        .root()
+        .instance(CALCULATOR)
+        .unwrap()
+        .instance("add")
+        .is_some();
+    assert!(!nested, "a function is not an instance");
+
+    let result = instance
+        .exports(&mut store)
+        .root()
+        .typed_func::<(u32,), (u32,)>("missing");
+    assert!(result.is_err(), "missing function should be an error");
+
+    let result = instance
+        .exports(&mut store)
+        .root()
+        .typed_func::<(u32,), (u32,)>(CALCULATOR);
+    assert!(result.is_err(), "an instance is not a function");
+
+    let result = instance
+        .exports(&mut store)
+        .root()
+        .instance(CALCULATOR)
+        .unwrap()
+        .typed_func::<(u32, u32), (u32,)>("sub");
+    assert!(result.is_err(), "missing function should be an error");
+
+    // Functions of the root and of an exported instance
+    let add = instance
+        .exports(&mut store)
+        .root()
+        .instance(CALCULATOR)
+        .unwrap()
+        .typed_func::<(u32, u32), (u32,)>("add")?;
+    let (sum,) = add.call(&mut store, (2, 3))?;
+    add.post_return(&mut store)?;
+    assert_eq!(sum, 5);
+
+    let double = instance.get_typed_func::<(u32,), (u32,)>(&mut store, "double")?;
+    let (doubled,) = double.call(&mut store, (21,))?;
+    double.post_return(&mut store)?;
+    assert_eq!(doubled, 42);
+
+    lookup_by_index(&mut store, component_bytes, &component, &instance)?;
+
+    Ok(())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn lookup_by_index(
+    _store: &mut Store<()>,
+    _component_bytes: &[u8],
+    _component: &Component,
+    _instance: &Instance,
+) -> Result<()> {
+    // Export indices are not available in wasmtime 20
+    Ok(())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn lookup_by_index(
+    store: &mut Store<()>,
+    component_bytes: &[u8],
+    component: &Component,
+    instance: &Instance,
+) -> Result<()> {
+    use wasm_bridge::component::types::ComponentItem;
+
+    let (item, calculator) = component.export_index(None, CALCULATOR).unwrap();
+    assert!(matches!(item, ComponentItem::ComponentInstance(_)));
+
+    let (item, add) = component.export_index(Some(&calculator), "add").unwrap();
+    assert!(matches!(item, ComponentItem::ComponentFunc(_)));
+
+    assert!(component
+        .export_index(Some(&calculator), "double")
+        .is_none());
+    assert!(component.export_index(None, "missing").is_none());
+
+    // The instance finds the same indices
+    assert_eq!(
+        instance.get_export(&mut *store, None, CALCULATOR),
+        Some(calculator)
+    );
+    assert_eq!(
+        instance.get_export(&mut *store, Some(&calculator), "add"),
+        Some(add)
+    );
+
+    // The index is reused for every lookup
+    let add = instance.get_typed_func::<(u32, u32), (u32,)>(&mut *store, add)?;
+    for n in 0..10 {
+        let (sum,) = add.call(&mut *store, (n, n))?;
+        add.post_return(&mut *store)?;
+        assert_eq!(sum, 2 * n);
+    }
+
+    // An instance is not a function
+    assert!(instance.get_func(&mut *store, calculator).is_none());
+
+    // Indices of a different component are rejected
+    #[allow(deprecated)]
+    let other = Component::new(store.engine(), component_bytes)?;
+    let (_, other_calculator) = other.export_index(None, CALCULATOR).unwrap();
+    let (_, other_add) = other.export_index(Some(&other_calculator), "add").unwrap();
+    assert!(instance.get_func(&mut *store, other_add).is_none());
+
+    Ok(())
+}
@@ -0,0 +1,14 @@
+wit_bindgen::generate!({
+    path: "../protocol.wit",
+    world: "pre-instantiation",
+});
+
+struct GuestImpl;
+
+impl Guest for GuestImpl {
+    fn add_three(num: i32) -> i32 {
+        add_one(num + 1) + 1
+    }
+}
+
+export!(GuestImpl);
@@ -0,0 +1,66 @@
+use wasm_bridge::{
+    component::{Component, Linker},
+    Config, Engine, Result, Store, StoreContextMut,
+};
+
+wasm_bridge::component::bindgen!({
+    path: "../protocol.wit",
+    world: "pre-instantiation",
+});
+
+struct HostData {
+    calls: u32,
+}
+
+impl PreInstantiationImports for HostData {
+    fn add_one(&mut self, num: i32) -> Result<i32> {
+        self.calls += 1;
+        Ok(num + 1)
+    }
+}
+
+pub fn run_test(component_bytes: &[u8]) -> Result<()> {
+    let mut config = Config::new();
+    config.wasm_component_model(true);
+
+    let engine = Engine::new(&config).unwrap();
+
+    #[allow(deprecated)]
+    let component = Component::new(&engine, &component_bytes).unwrap();
+
+    let mut linker = Linker::new(&engine);
+    PreInstantiation::add_to_linker(&mut linker, |data| data).unwrap();
+
+    // The imports are resolved once, and then used for many short-lived stores
+    let instance_pre = linker.instantiate_pre(&component).unwrap();
+
+    for num in 0..100 {
+        let mut store = Store::new(&engine, HostData { calls: 0 });
+
+        #[allow(deprecated)]
+        let (instance, _) = PreInstantiation::instantiate_pre(&mut store, &instance_pre).unwrap();
+
+        let result = instance.call_add_three(&mut store, num).unwrap();
+        assert_eq!(result, num + 3);
+        assert_eq!(store.data().calls, 1);
+    }
+
+    // Missing imports are reported before instantiating
+    let empty_linker = Linker::<HostData>::new(&engine);
+    assert!(empty_linker.instantiate_pre(&component).is_err());
+
+    // So are imports with a different type
+    let mut wrong_linker = Linker::<HostData>::new(&engine);
+    wrong_linker
+        .root()
+        .func_wrap("add-one", |_: StoreContextMut<HostData>, (num,): (u32,)| {
+            Ok((num,))
+        })
+        .unwrap();
+    let Err(error) = wrong_linker.instantiate_pre(&component) else {
+        panic!("instantiating with a wrongly typed import should fail");
+    };
+    assert!(format!("{error:?}").contains("add-one"), "{error:?}");
+
+    Ok(())
+}
@@ -0,0 +1,22 @@
+wit_bindgen::generate!({
+    path: "../protocol.wit",
+    world: "post-return",
+});
+
+struct GuestImpl;
+
+impl Guest for GuestImpl {
+    fn echo(message: String) -> String {
+        message
+    }
+
+    fn length(message: String) -> u32 {
+        message.len() as u32
+    }
+
+    fn crash() {
+        panic!("Crash in guest code");
+    }
+}
+
+export!(GuestImpl);
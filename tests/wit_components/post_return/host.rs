@@ -0,0 +1,95 @@
+use wasm_bridge::{
+    component::{Component, Instance, Linker},
+    Config, Engine, Result, Store, Trap,
+};
+
+pub fn run_test(component_bytes: &[u8]) -> Result<()> {
+    let mut config = Config::new();
+    config.wasm_component_model(true);
+
+    let engine = Engine::new(&config).unwrap();
+    let mut store = Store::new(&engine, ());
+
+    #[allow(deprecated)]
+    let component = Component::new(&store.engine(), &component_bytes).unwrap();
+
+    let linker = Linker::new(store.engine());
+
+    #[allow(deprecated)]
+    let instance = linker.instantiate(&mut store, &component).unwrap();
+
+    let echo = instance.get_typed_func::<(&str,), (String,)>(&mut store, "echo")?;
+    let length = instance.get_typed_func::<(&str,), (u32,)>(&mut store, "length")?;
+
+    let (message,) = echo.call(&mut store, ("hello",))?;
+    assert_eq!(message, "hello");
+
+    // The instance cannot be entered until post_return is called
+    echo.call(&mut store, ("again",))
+        .expect_err("post_return was not called");
+    length
+        .call(&mut store, ("again",))
+        .expect_err("post_return was not called");
+
+    echo.post_return(&mut store)?;
+
+    let (len,) = length.call(&mut store, ("hello",))?;
+    length.post_return(&mut store)?;
+    assert_eq!(len, 5);
+
+    auto_post_return(&mut store, &instance)?;
+
+    // A trap poisons the instance, it cannot be entered even without a pending post_return
+    let crash = instance.get_typed_func::<(), ()>(&mut store, "crash")?;
+    let error = crash.call(&mut store, ()).expect_err("the guest panics");
+    assert!(error.downcast_ref::<Trap>().is_some(), "{error:?}");
+
+    let error = length
+        .call(&mut store, ("hello",))
+        .expect_err("the instance is poisoned");
+    assert_eq!(
+        error.downcast_ref::<Trap>(),
+        Some(&Trap::CannotEnterComponent)
+    );
+    #[cfg(target_arch = "wasm32")]
+    assert!(format!("{error:?}").contains("poisoned"), "{error:?}");
+
+    Ok(())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn auto_post_return(_store: &mut Store<()>, _instance: &Instance) -> Result<()> {
+    // Automatic post-return is a wasm-bridge extension on the web
+    Ok(())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn auto_post_return(store: &mut Store<()>, instance: &Instance) -> Result<()> {
+    // post_return without a call is an error on the web, wasmtime panics
+    let length = instance.get_typed_func::<(&str,), (u32,)>(&mut *store, "length")?;
+    length
+        .post_return(&mut *store)
+        .expect_err("length has not been called");
+
+    let echo = instance
+        .get_typed_func::<(&str,), (String,)>(&mut *store, "echo")?
+        .auto_post_return(true);
+
+    for i in 0..100 {
+        let message = format!("message {i}");
+        let (echoed,) = echo.call(&mut *store, (message.as_str(),))?;
+        assert_eq!(echoed, message);
+    }
+
+    // Calling post_return anyway is allowed
+    let (echoed,) = echo.call(&mut *store, ("hello",))?;
+    echo.post_return(&mut *store)?;
+    assert_eq!(echoed, "hello");
+
+    // Other functions can be called, the instance is not waiting for post_return
+    let (len,) = length.call(&mut *store, ("hello",))?;
+    length.post_return(&mut *store)?;
+    assert_eq!(len, 5);
+
+    Ok(())
+}
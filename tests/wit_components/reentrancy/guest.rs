@@ -0,0 +1,18 @@
+wit_bindgen::generate!({
+    path: "../protocol.wit",
+    world: "reentrancy",
+});
+
+struct GuestImpl;
+
+impl Guest for GuestImpl {
+    fn run(n: u32) -> u32 {
+        apply(n) + 1
+    }
+
+    fn square(n: u32) -> u32 {
+        n * n
+    }
+}
+
+export!(GuestImpl);
@@ -0,0 +1,79 @@
+use wasm_bridge::{
+    component::{Component, Linker, TypedFunc},
+    Config, Engine, Result, Store, StoreContextMut,
+};
+
+use wasm_bridge::component::__internal::anyhow::Context;
+
+/// Exported functions called by the host's `apply`, the last one is called first
+type Targets = Vec<TypedFunc<(u32,), (u32,)>>;
+
+fn apply(mut store: StoreContextMut<'_, Targets>, (n,): (u32,)) -> Result<(u32,)> {
+    let target = store.data_mut().pop().context("no function to apply")?;
+
+    // Calls back into a guest while this host function is running
+    let result = target.call(&mut store, (n,));
+    if result.is_ok() {
+        target.post_return(&mut store)?;
+    }
+
+    store.data_mut().push(target);
+    result
+}
+
+pub fn run_test(component_bytes: &[u8]) -> Result<()> {
+    let mut config = Config::new();
+    config.wasm_component_model(true);
+
+    let engine = Engine::new(&config).unwrap();
+    let mut store = Store::new(&engine, Targets::new());
+
+    #[allow(deprecated)]
+    let component = Component::new(&store.engine(), &component_bytes).unwrap();
+
+    let mut linker = Linker::new(store.engine());
+    linker.root().func_wrap("apply", apply)?;
+
+    let funcs = |store: &mut Store<Targets>| -> Result<_> {
+        #[allow(deprecated)]
+        let instance = linker.instantiate(&mut *store, &component)?;
+        let run = instance.get_typed_func::<(u32,), (u32,)>(&mut *store, "run")?;
+        let square = instance.get_typed_func::<(u32,), (u32,)>(&mut *store, "square")?;
+        Ok((run, square))
+    };
+
+    let (run_a, square_a) = funcs(&mut store)?;
+    let (run_b, _) = funcs(&mut store)?;
+    let (_, square_c) = funcs(&mut store)?;
+
+    // guest a -> host -> guest c
+    *store.data_mut() = vec![square_c];
+    for n in 0..5 {
+        let (result,) = run_a.call(&mut store, (n,))?;
+        run_a.post_return(&mut store)?;
+        assert_eq!(result, n * n + 1);
+    }
+
+    // guest a -> host -> guest b -> host -> guest c
+    let square_c = store.data_mut().pop().unwrap();
+    *store.data_mut() = vec![square_c, run_b];
+    for n in 0..5 {
+        let (result,) = run_a.call(&mut store, (n,))?;
+        run_a.post_return(&mut store)?;
+        assert_eq!(result, n * n + 2);
+    }
+
+    // The outer call is finished, the instances can be entered again
+    let (result,) = square_a.call(&mut store, (7,))?;
+    square_a.post_return(&mut store)?;
+    assert_eq!(result, 49);
+
+    // guest a -> host -> guest a is not allowed,
+    // instance a cannot be entered again after that
+    *store.data_mut() = vec![square_a];
+    run_a
+        .call(&mut store, (3,))
+        .expect_err("instance should not be re-entered");
+
+    Ok(())
+}